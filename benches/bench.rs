@@ -5,11 +5,13 @@ extern crate rand;
 extern crate threshold_crypto;
 
 use criterion::Criterion;
+use pairing::CurveProjective;
 use threshold_crypto::poly::Poly;
 use threshold_crypto::Fr;
 
 const TEST_DEGREES: [usize; 4] = [5, 10, 20, 40];
 const TEST_THRESHOLDS: [usize; 4] = [5, 10, 20, 40];
+const LARGE_THRESHOLDS: [usize; 3] = [100, 200, 400];
 const RNG_SEED: [u32; 4] = [1, 2, 3, 4];
 
 mod poly_benches {
@@ -87,18 +89,127 @@ mod poly_benches {
     }
 }
 
+mod commitment_benches {
+    use super::*;
+    use rand::{Rng, SeedableRng, XorShiftRng};
+    use threshold_crypto::poly::BivarPoly;
+    use threshold_crypto::G1;
+
+    /// Benchmarks evaluating a commitment at a random point.
+    fn evaluate(c: &mut Criterion) {
+        let mut rng = XorShiftRng::from_seed(RNG_SEED);
+        c.bench_function_over_inputs(
+            "Commitment evaluation",
+            move |b, &&deg| {
+                let commit = Poly::random(deg, &mut rng).commitment();
+                let x: Fr = rng.gen();
+                b.iter(|| commit.evaluate(x))
+            },
+            &LARGE_THRESHOLDS,
+        );
+    }
+
+    /// Benchmarks evaluating a commitment's coefficients at a random point with Horner's method,
+    /// i.e. with one scalar multiplication per coefficient, as a baseline for `evaluate`.
+    fn naive_evaluate(c: &mut Criterion) {
+        let mut rng = XorShiftRng::from_seed(RNG_SEED);
+        c.bench_function_over_inputs(
+            "Commitment evaluation (naive)",
+            move |b, &&deg| {
+                let coeff: Vec<G1> = (0..=deg).map(|_| rng.gen()).collect();
+                let x: Fr = rng.gen();
+                b.iter(|| {
+                    let mut result = G1::zero();
+                    for c in coeff.iter().rev() {
+                        result.mul_assign(x);
+                        result.add_assign(c);
+                    }
+                    result
+                })
+            },
+            &LARGE_THRESHOLDS,
+        );
+    }
+
+    /// Benchmarks evaluating a bivariate commitment at a random point.
+    fn bivar_evaluate(c: &mut Criterion) {
+        let mut rng = XorShiftRng::from_seed(RNG_SEED);
+        c.bench_function_over_inputs(
+            "Bivariate commitment evaluation",
+            move |b, &&deg| {
+                let commit = BivarPoly::random(deg, &mut rng).commitment();
+                let (x, y): (Fr, Fr) = (rng.gen(), rng.gen());
+                b.iter(|| commit.evaluate(x, y))
+            },
+            &TEST_DEGREES,
+        );
+    }
+
+    /// Benchmarks computing a row of a bivariate commitment at a random point.
+    fn bivar_row(c: &mut Criterion) {
+        let mut rng = XorShiftRng::from_seed(RNG_SEED);
+        c.bench_function_over_inputs(
+            "Bivariate commitment row",
+            move |b, &&deg| {
+                let commit = BivarPoly::random(deg, &mut rng).commitment();
+                let x: Fr = rng.gen();
+                b.iter(|| commit.row(x))
+            },
+            &TEST_DEGREES,
+        );
+    }
+
+    criterion_group!{
+        name = commitment_benches;
+        config = Criterion::default();
+        targets = evaluate, naive_evaluate, bivar_evaluate, bivar_row,
+    }
+}
+
 mod public_key_set_benches {
     use super::*;
-    use rand::{SeedableRng, XorShiftRng};
+    use rand::{Rng, SeedableRng, XorShiftRng};
     use std::collections::BTreeMap;
-    use threshold_crypto::SecretKeySet;
+    use threshold_crypto::{SecretKeySet, G2};
 
     /// Benchmarks combining signatures
     fn combine_signatures(c: &mut Criterion) {
+        combine_signatures_over(c, "Combine Signatures", &TEST_THRESHOLDS);
+    }
+
+    /// Benchmarks combining signatures, with thresholds in the hundreds.
+    fn combine_signatures_large(c: &mut Criterion) {
+        combine_signatures_over(c, "Combine Signatures (large threshold)", &LARGE_THRESHOLDS);
+    }
+
+    /// Benchmarks the sum of `threshold + 1` signatures with random coefficients, with one scalar
+    /// multiplication per signature, as a baseline for `combine_signatures_large`.
+    fn combine_signatures_naive(c: &mut Criterion) {
+        let mut rng = XorShiftRng::from_seed(RNG_SEED);
+        c.bench_function_over_inputs(
+            "Combine Signatures (naive sum)",
+            move |b, &&threshold| {
+                let terms: Vec<(G2, Fr)> = (0..=threshold).map(|_| rng.gen()).collect();
+                b.iter(|| {
+                    let mut result = G2::zero();
+                    for (sig, coeff) in &terms {
+                        let mut term = *sig;
+                        term.mul_assign(*coeff);
+                        result.add_assign(&term);
+                    }
+                    result
+                })
+            },
+            &LARGE_THRESHOLDS,
+        );
+    }
+
+    /// Benchmarks combining signatures, for each of the given thresholds.
+    fn combine_signatures_over(c: &mut Criterion, name: &str, thresholds: &'static [usize]) {
         let mut rng = XorShiftRng::from_seed(RNG_SEED);
         let msg = "Test message";
         c.bench_function_over_inputs(
-            "Combine Signatures",
+            name,
             move |b, &&threshold| {
                 let sk_set = SecretKeySet::random(threshold, &mut rng);
                 let pk_set = sk_set.public_keys();
@@ -116,18 +227,19 @@ mod public_key_set_benches {
                         .expect("could not combine signatures");
                 })
             },
-            &TEST_THRESHOLDS,
+            thresholds,
         );
     }
 
     criterion_group!{
         name = public_key_set_benches;
         config = Criterion::default();
-        targets = combine_signatures,
+        targets = combine_signatures, combine_signatures_large, combine_signatures_naive,
     }
 }
 
 criterion_main!(
     poly_benches::poly_benches,
+    commitment_benches::commitment_benches,
     public_key_set_benches::public_key_set_benches
 );
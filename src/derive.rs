@@ -0,0 +1,169 @@
+//! Deterministic derivation of secret keys from a seed, as specified in
+//! [EIP-2333](https://eips.ethereum.org/EIPS/eip-2333).
+//!
+//! The master key is derived from a seed of at least 32 bytes. Each child key is derived from its
+//! parent and a 32-bit index: The parent key is expanded into two Lamport secret keys, and the
+//! hash of the corresponding Lamport public keys is the input keying material for the child key.
+//! That way, a child key doesn't reveal anything about its parent or its siblings.
+
+use byteorder::{BigEndian, ByteOrder};
+use hkdf::Hkdf;
+use pairing::{Field, PrimeField, PrimeFieldRepr};
+use sha2::{Digest, Sha256};
+
+use into_fr::fr_from_bytes_mod_r;
+use secret::{clear_copy, fr_bytes, SecretBytes};
+use Fr;
+
+/// The minimum length of a seed, in bytes.
+pub(crate) const MIN_SEED_LEN: usize = 32;
+
+/// The initial salt for `hkdf_mod_r`. It is hashed before every attempt.
+const KEYGEN_SALT: &[u8] = b"BLS-SIG-KEYGEN-SALT-";
+/// The number of bytes that are reduced modulo `r`: `ceil((3 * ceil(log2(r))) / 16)`.
+const OKM_LEN: usize = 48;
+/// The output size of SHA-256, in bytes.
+const HASH_SIZE: usize = 32;
+/// The number of chunks of a Lamport secret key.
+const LAMPORT_CHUNKS: usize = 255;
+
+/// Returns the master secret key for the given seed.
+pub(crate) fn derive_master_sk(seed: &[u8]) -> Fr {
+    hkdf_mod_r(seed)
+}
+
+/// Returns the child secret key with the given index.
+pub(crate) fn derive_child_sk(parent_sk: &Fr, index: u32) -> Fr {
+    hkdf_mod_r(&parent_sk_to_lamport_pk(parent_sk, index))
+}
+
+/// Derives a nonzero field element from the input keying material.
+fn hkdf_mod_r(ikm: &[u8]) -> Fr {
+    let mut ikm_prime = SecretBytes(Vec::with_capacity(ikm.len() + 1));
+    ikm_prime.0.extend_from_slice(ikm);
+    ikm_prime.0.push(0);
+    let mut okm = SecretBytes(vec![0; OKM_LEN]);
+    let mut salt = KEYGEN_SALT.to_vec();
+    loop {
+        salt = Sha256::digest(&salt).to_vec();
+        Hkdf::<Sha256>::new(Some(&salt[..]), &ikm_prime)
+            .expand(&[0, OKM_LEN as u8], &mut okm.0)
+            .expect("output length is valid");
+        let sk = fr_from_bytes_mod_r(&okm);
+        if !Field::is_zero(&sk) {
+            return sk;
+        }
+    }
+}
+
+/// Returns the compressed Lamport public key for the parent key and the child index.
+fn parent_sk_to_lamport_pk(parent_sk: &Fr, index: u32) -> Vec<u8> {
+    let mut salt = [0u8; 4];
+    BigEndian::write_u32(&mut salt, index);
+    let mut ikm = SecretBytes(Vec::with_capacity(fr_bytes::<Fr>()));
+    let mut repr = parent_sk.into_repr();
+    repr.write_be(&mut ikm.0)
+        .expect("writing to a vector never fails");
+    clear_copy(&mut repr);
+    let not_ikm = SecretBytes(ikm.iter().map(|byte| !byte).collect());
+    let mut hasher = Sha256::new();
+    for ikm in &[ikm, not_ikm] {
+        let lamport_sk = ikm_to_lamport_sk(ikm, &salt);
+        for chunk in lamport_sk.chunks(HASH_SIZE) {
+            hasher.input(Sha256::digest(chunk));
+        }
+    }
+    hasher.result().to_vec()
+}
+
+/// Returns the Lamport secret key, consisting of `LAMPORT_CHUNKS` chunks of 32 bytes.
+fn ikm_to_lamport_sk(ikm: &[u8], salt: &[u8]) -> SecretBytes {
+    let mut okm = SecretBytes(vec![0; LAMPORT_CHUNKS * HASH_SIZE]);
+    Hkdf::<Sha256>::new(Some(salt), ikm)
+        .expand(&[], &mut okm.0)
+        .expect("output length is valid");
+    okm
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use error::Error;
+    use SecretKey;
+
+    /// Decodes a hexadecimal string.
+    #[cfg(not(feature = "use-insecure-test-only-mock-crypto"))]
+    fn from_hex(hex: &str) -> Vec<u8> {
+        (0..hex.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&hex[i..(i + 2)], 16).expect("valid hex string"))
+            .collect()
+    }
+
+    // The test vectors from EIP-2333: the seed, the master key, the child index and the child key.
+    #[cfg(not(feature = "use-insecure-test-only-mock-crypto"))]
+    const VECTORS: &[(&str, &str, u32, &str)] = &[
+        (
+            "c55257c360c07c72029aebc1b53c05ed0362ada38ead3e3e9efa3708e53495531f09a6987599d18264c1e1c92f2cf141630c7a3c4ab7c81b2f001698e7463b04",
+            "6083874454709270928345386274498605044986640685124978867557563392430687146096",
+            0,
+            "20397789859736650942317412262472558107875392172444076792671091975210932703118",
+        ),
+        (
+            "3141592653589793238462643383279502884197169399375105820974944592",
+            "29757020647961307431480504535336562678282505419141012933316116377660817309383",
+            3_141_592_653,
+            "25457201688850691947727629385191704516744796114925897962676248250929345014287",
+        ),
+        (
+            "0099FF991111002299DD7744EE3355BBDD8844115566CC55663355668888CC00",
+            "27580842291869792442942448775674722299803720648445448686099262467207037398656",
+            4_294_967_295,
+            "29358610794459428860402234341874281240803786294062035874021252734817515685787",
+        ),
+        (
+            "d4e56740f876aef8c010b86a40d5f56745a118d0906a34e69aec8c0db1cb8fa3",
+            "19022158461524446591288038168518313374041767046816487870552872741050760015818",
+            42,
+            "31372231650479070279774297061823572166496564838472787488249775572789064611981",
+        ),
+    ];
+
+    #[cfg(not(feature = "use-insecure-test-only-mock-crypto"))]
+    #[test]
+    fn test_vectors() {
+        for &(seed, master, index, child) in VECTORS {
+            let master_sk = SecretKey::from_seed(from_hex(seed)).expect("valid seed");
+            let expected = Fr::from_str(master).expect("valid master key");
+            assert_eq!(expected, *master_sk.0);
+            let child_sk = master_sk.derive_child(index);
+            let expected = Fr::from_str(child).expect("valid child key");
+            assert_eq!(expected, *child_sk.0);
+        }
+    }
+
+    #[test]
+    fn test_derive_path() {
+        let seed = [42u8; MIN_SEED_LEN];
+        let master_sk = SecretKey::from_seed(&seed[..]).expect("valid seed");
+        let master_sk2 = SecretKey::from_seed(&seed[..]).expect("valid seed");
+        assert_eq!(master_sk, master_sk2);
+        let sk = master_sk.derive_path(vec![12381, 3600, 0, 0]);
+        let expected = master_sk
+            .derive_child(12381)
+            .derive_child(3600)
+            .derive_child(0)
+            .derive_child(0);
+        assert_eq!(expected, sk);
+        assert_ne!(sk, master_sk.derive_path(vec![12381, 3600, 1, 0]));
+        assert_eq!(master_sk, master_sk.derive_path(vec![]));
+
+        let expected = Error::InvalidLength {
+            expected: MIN_SEED_LEN,
+            actual: MIN_SEED_LEN - 1,
+        };
+        let result = SecretKey::from_seed(&seed[1..]);
+        assert_eq!(Some(expected), result.err());
+    }
+}
@@ -0,0 +1,1087 @@
+//! Distributed key generation, without a trusted dealer.
+//!
+//! All messages are broadcast: The nodes only need a single public channel, on which they all
+//! receive the same messages in the same order. Secret values are encrypted to their recipients'
+//! public keys.
+//!
+//! Each of the `n` nodes acts as a dealer: It creates a random symmetric bivariate polynomial `f`
+//! of degree `t` and sends a `Part`, containing the commitment to `f` and, for every node `m`,
+//! the row `f(m + 1, _)` encrypted to node `m`. Node `m` verifies its row against the commitment
+//! and sends an `Ack`, containing for every node `s` the value `f(m + 1, s + 1)` encrypted to
+//! node `s`. Since `f` is symmetric, that is also a point on node `s`'s row.
+//!
+//! A dealer is complete once `2 * t + 1` nodes have acknowledged its part: Then at least `t + 1`
+//! honest nodes sent their values on, so every node can interpolate its row and compute
+//! `f(m + 1, 0)`, even if the dealer didn't send it a valid row itself. Node `m`'s secret key
+//! share is the sum of the values `f(m + 1, 0)` of all complete dealers. The master secret key,
+//! the sum of the values `f(0, 0)`, is not known to anyone.
+//!
+//! A node that didn't receive a valid row from a dealer sends a `Complaint`. The dealer must
+//! answer each complaint with a `Justification`, publicly revealing the complainer's row, which
+//! every node checks against the commitment. A dealer is disqualified if it doesn't answer a
+//! complaint with a valid justification, or if more than `t` nodes complain: Otherwise too many
+//! rows would become public. Since all nodes handle the same messages, they agree on which
+//! dealers are complete and which are disqualified, and up to `t` misbehaving dealers can't
+//! prevent the key generation from completing.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use byteorder::{BigEndian, ByteOrder};
+use memsec::memzero;
+use pairing::{CurveAffine, Field, PrimeField, PrimeFieldRepr};
+use rand::Rng;
+
+use error::{Error, Result};
+use poly::{BivarCommitment, BivarPoly, Commitment, Poly};
+use secret::clear_fr;
+use {Ciphertext, Fr, G1Affine, PublicKey, PublicKeySet, SecretKey, SecretKeyShare};
+
+/// A dealer's message: the commitment to the dealer's bivariate polynomial, and each node's row
+/// of it, encrypted to that node.
+#[derive(Deserialize, Serialize, Clone, PartialEq, Eq, Debug)]
+pub struct Part {
+    commit: BivarCommitment,
+    rows: Vec<Ciphertext>,
+}
+
+/// A node's message confirming that it received a valid part from `dealer`. It contains a value
+/// of every node's row, encrypted to that node.
+#[derive(Deserialize, Serialize, Clone, PartialEq, Eq, Debug)]
+pub struct Ack {
+    dealer: usize,
+    values: Vec<Ciphertext>,
+}
+
+impl Ack {
+    /// Returns the index of the dealer whose part is acknowledged.
+    pub fn dealer(&self) -> usize {
+        self.dealer
+    }
+}
+
+/// A node's complaint that it didn't receive a valid part from `dealer`.
+#[derive(Deserialize, Serialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Complaint {
+    pub(crate) dealer: usize,
+}
+
+impl Complaint {
+    /// Returns the index of the dealer the complaint is about.
+    pub fn dealer(&self) -> usize {
+        self.dealer
+    }
+}
+
+/// A dealer's answer to a complaint: the complainer's row, in plain text.
+#[derive(Deserialize, Serialize, Clone, PartialEq, Eq, Debug)]
+pub struct Justification {
+    complainer: usize,
+    row: Poly,
+}
+
+impl Justification {
+    /// Returns the index of the node whose complaint is answered.
+    pub fn complainer(&self) -> usize {
+        self.complainer
+    }
+}
+
+/// A dealer's answer to a complaint about a single value: the complainer's value, in plain text.
+/// This is used in the refresh, resharing and recovery, where each node receives only one value
+/// from each dealer.
+#[derive(Deserialize, Serialize, Clone, PartialEq, Eq, Debug)]
+pub struct ValueJustification {
+    complainer: usize,
+    #[serde(with = "::serde_impl::field")]
+    value: Fr,
+}
+
+impl ValueJustification {
+    /// Returns the justification revealing the complainer's value of `poly`.
+    pub(crate) fn new(complainer: usize, poly: &Poly) -> ValueJustification {
+        ValueJustification {
+            complainer,
+            value: poly.evaluate(complainer + 1),
+        }
+    }
+
+    /// Returns the index of the node whose complaint is answered.
+    pub fn complainer(&self) -> usize {
+        self.complainer
+    }
+}
+
+/// A reason to reject a message. These are faults of the sender.
+#[derive(Clone, Copy, Eq, PartialEq, Debug, Fail)]
+pub enum Fault {
+    #[fail(display = "The sender's index is out of range")]
+    UnknownSender,
+    #[fail(display = "The dealer sent more than one part")]
+    DuplicatePart,
+    #[fail(display = "The commitment has the wrong degree")]
+    WrongDegree,
+    #[fail(display = "The part doesn't contain a row for every node")]
+    RowCount,
+    #[fail(display = "The row can't be decrypted")]
+    RowDecryption,
+    #[fail(display = "The row doesn't match the commitment")]
+    RowMismatch,
+    #[fail(display = "The acknowledged dealer is unknown")]
+    UnknownDealer,
+    #[fail(display = "The sender acknowledged the same dealer more than once")]
+    DuplicateAck,
+    #[fail(display = "The ack doesn't contain a value for every node")]
+    ValueCount,
+    #[fail(display = "The value can't be decrypted")]
+    ValueDecryption,
+    #[fail(display = "The value doesn't match the commitment")]
+    ValueMismatch,
+    #[fail(display = "The sender complained about the same dealer more than once")]
+    DuplicateComplaint,
+    #[fail(display = "The justification doesn't answer any complaint")]
+    UnexpectedJustification,
+    #[fail(display = "The dealer answered the same complaint more than once")]
+    DuplicateJustification,
+    #[fail(display = "The justification doesn't match the commitment")]
+    InvalidJustification,
+    #[fail(display = "The committed polynomial's constant term is not zero")]
+    NonZeroConstant,
+    #[fail(display = "The committed constant term doesn't match the dealer's key share")]
+    ConstantMismatch,
+    #[fail(display = "The masking polynomial is not zero at the recovering node's index")]
+    NonZeroMask,
+    #[fail(display = "The sender sent more than one contribution")]
+    DuplicateContribution,
+}
+
+/// The state of a dealer.
+#[derive(Default)]
+struct Dealer {
+    /// The commitment to the dealer's bivariate polynomial, once we received it.
+    commit: Option<BivarCommitment>,
+    /// Our row, if we received a valid one.
+    row: Option<Poly>,
+    /// The nodes that acknowledged the dealer's part.
+    acks: BTreeSet<usize>,
+    /// The valid values `f(our_idx + 1, s + 1)` of our row, received from the nodes `s`.
+    values: BTreeMap<usize, Fr>,
+    /// The nodes that complained about the dealer.
+    complaints: BTreeSet<usize>,
+    /// The complainers whose complaints the dealer answered with a valid justification.
+    justified: BTreeSet<usize>,
+    /// Whether the dealer sent an invalid justification.
+    invalid_justification: bool,
+}
+
+impl Drop for Dealer {
+    fn drop(&mut self) {
+        for value in self.values.values_mut() {
+            clear_fr(value);
+        }
+    }
+}
+
+/// A node's state in the distributed key generation.
+pub struct KeyGen {
+    /// Our node index.
+    our_idx: usize,
+    /// Our secret key, to decrypt the rows and values sent to us.
+    sec_key: SecretKey,
+    /// The public keys of all nodes, by index. All of them act as dealers.
+    pub_keys: Vec<PublicKey>,
+    /// The threshold `t`: Any `t + 1` nodes will be able to sign or decrypt with the keys.
+    threshold: usize,
+    /// Our own bivariate polynomial, as a dealer.
+    bi_poly: BivarPoly,
+    /// The state of each dealer, by index.
+    dealers: Vec<Dealer>,
+}
+
+impl KeyGen {
+    /// Creates a new key generation instance for node `our_idx`, with the given public keys of
+    /// all nodes, and returns it together with our own part as a dealer.
+    ///
+    /// Dealers can only become complete if there are more than `2 * threshold` nodes.
+    ///
+    /// Returns `Error::IndexOutOfRange` if `our_idx` is not an index of `pub_keys`, and
+    /// `Error::KeyMismatch` if `sec_key` doesn't match our public key in `pub_keys`.
+    pub fn new<R: Rng>(
+        our_idx: usize,
+        sec_key: SecretKey,
+        pub_keys: Vec<PublicKey>,
+        threshold: usize,
+        rng: &mut R,
+    ) -> Result<(KeyGen, Part)> {
+        check_our_key(our_idx, &sec_key, &pub_keys)?;
+        let bi_poly = BivarPoly::try_random(threshold, rng)?;
+        let rows = pub_keys
+            .iter()
+            .enumerate()
+            .map(|(m, pk)| {
+                let label = Round::KeyGenRow.label(&[our_idx, m]);
+                encrypt_fr_vec(pk, &bi_poly.row(m + 1).coeff, &label, rng)
+            }).collect();
+        let part = Part {
+            commit: bi_poly.commitment(),
+            rows,
+        };
+        let key_gen = KeyGen {
+            our_idx,
+            sec_key,
+            dealers: pub_keys.iter().map(|_| Dealer::default()).collect(),
+            pub_keys,
+            threshold,
+            bi_poly,
+        };
+        Ok((key_gen, part))
+    }
+
+    /// Handles a part from `dealer`. If it is valid, returns our ack, which must be sent to all
+    /// nodes.
+    ///
+    /// Even if our row is invalid, the commitment is kept, so that we can still compute our row
+    /// from the other nodes' acks.
+    pub fn handle_part<R: Rng>(
+        &mut self,
+        dealer: usize,
+        part: Part,
+        rng: &mut R,
+    ) -> ::std::result::Result<Ack, Fault> {
+        if dealer >= self.pub_keys.len() {
+            return Err(Fault::UnknownSender);
+        }
+        if self.dealers[dealer].commit.is_some() {
+            return Err(Fault::DuplicatePart);
+        }
+        let Part { commit, rows } = part;
+        if !self.is_valid_commitment(&commit) {
+            return Err(Fault::WrongDegree);
+        }
+        if rows.len() != self.pub_keys.len() {
+            return Err(Fault::RowCount);
+        }
+        let row = self.decrypt_row(dealer, &rows[self.our_idx]);
+        let row_commit = commit.row(self.our_idx + 1);
+        self.dealers[dealer].commit = Some(commit);
+        let row = row.ok_or(Fault::RowDecryption)?;
+        if row.commitment() != row_commit {
+            return Err(Fault::RowMismatch);
+        }
+        Ok(self.accept_row(dealer, row, rng))
+    }
+
+    /// Handles an ack from node `sender`. The part of the acknowledged dealer must have been
+    /// handled before.
+    ///
+    /// An ack with a value for us that is invalid still counts towards the dealer's completion,
+    /// since the other nodes can't check that value.
+    pub fn handle_ack(&mut self, sender: usize, ack: Ack) -> ::std::result::Result<(), Fault> {
+        if sender >= self.pub_keys.len() {
+            return Err(Fault::UnknownSender);
+        }
+        if ack.dealer >= self.pub_keys.len() {
+            return Err(Fault::UnknownDealer);
+        }
+        if ack.values.len() != self.pub_keys.len() {
+            return Err(Fault::ValueCount);
+        }
+        let value = self.decrypt_value(ack.dealer, sender, &ack.values[self.our_idx]);
+        let our_idx = self.our_idx;
+        let dealer = &mut self.dealers[ack.dealer];
+        let value_g1 = match dealer.commit {
+            None => return Err(Fault::UnknownDealer),
+            Some(ref commit) => commit.evaluate(our_idx + 1, sender + 1),
+        };
+        if !dealer.acks.insert(sender) {
+            return Err(Fault::DuplicateAck);
+        }
+        let mut value = value.ok_or(Fault::ValueDecryption)?;
+        if value_g1 != G1Affine::one().mul(value) {
+            clear_fr(&mut value);
+            return Err(Fault::ValueMismatch);
+        }
+        dealer.values.insert(sender, value);
+        clear_fr(&mut value);
+        Ok(())
+    }
+
+    /// Returns our complaints about all dealers that didn't send us a valid part. They must be
+    /// sent to all nodes once the deadline for the parts has passed.
+    pub fn complaints(&self) -> Vec<Complaint> {
+        (0..self.pub_keys.len())
+            .filter(|&dealer| self.dealers[dealer].row.is_none())
+            .map(|dealer| Complaint { dealer }).collect()
+    }
+
+    /// Handles a complaint from node `sender`. If it is about us, returns our justification,
+    /// which must be sent to all nodes.
+    ///
+    /// If more than `threshold` nodes complain about us, we don't answer: We are disqualified
+    /// anyway, and revealing more rows would reveal our secret.
+    pub fn handle_complaint(
+        &mut self,
+        sender: usize,
+        complaint: Complaint,
+    ) -> ::std::result::Result<Option<Justification>, Fault> {
+        if sender >= self.pub_keys.len() {
+            return Err(Fault::UnknownSender);
+        }
+        if complaint.dealer >= self.pub_keys.len() {
+            return Err(Fault::UnknownDealer);
+        }
+        let dealer = &mut self.dealers[complaint.dealer];
+        if !dealer.complaints.insert(sender) {
+            return Err(Fault::DuplicateComplaint);
+        }
+        if complaint.dealer != self.our_idx || dealer.complaints.len() > self.threshold {
+            return Ok(None);
+        }
+        Ok(Some(Justification {
+            complainer: sender,
+            row: self.bi_poly.row(sender + 1),
+        }))
+    }
+
+    /// Handles a justification from `dealer`. If it answers our own complaint, returns our ack,
+    /// as in `handle_part`.
+    ///
+    /// An invalid justification disqualifies the dealer.
+    pub fn handle_justification<R: Rng>(
+        &mut self,
+        dealer: usize,
+        justification: Justification,
+        rng: &mut R,
+    ) -> ::std::result::Result<Option<Ack>, Fault> {
+        if dealer >= self.pub_keys.len() {
+            return Err(Fault::UnknownSender);
+        }
+        let Justification { complainer, row } = justification;
+        let state = &mut self.dealers[dealer];
+        if !state.complaints.contains(&complainer) {
+            return Err(Fault::UnexpectedJustification);
+        }
+        if state.justified.contains(&complainer) {
+            return Err(Fault::DuplicateJustification);
+        }
+        let valid = match state.commit {
+            None => false,
+            Some(ref commit) => row.commitment() == commit.row(complainer + 1),
+        };
+        if !valid {
+            state.invalid_justification = true;
+            return Err(Fault::InvalidJustification);
+        }
+        state.justified.insert(complainer);
+        if complainer != self.our_idx || state.row.is_some() {
+            return Ok(None);
+        }
+        Ok(Some(self.accept_row(dealer, row, rng)))
+    }
+
+    /// Returns the indices of the dealers that at least `2 * threshold + 1` nodes acknowledged.
+    pub fn complete_dealers(&self) -> BTreeSet<usize> {
+        (0..self.pub_keys.len())
+            .filter(|&dealer| self.dealers[dealer].acks.len() > 2 * self.threshold)
+            .collect()
+    }
+
+    /// Returns the indices of the dealers that didn't validly answer all complaints, or that more
+    /// than `threshold` nodes complained about.
+    ///
+    /// This is only final after all complaints and justifications have been handled.
+    pub fn disqualified_dealers(&self) -> BTreeSet<usize> {
+        (0..self.pub_keys.len())
+            .filter(|&idx| {
+                let dealer = &self.dealers[idx];
+                dealer.invalid_justification
+                    || dealer.complaints.len() > self.threshold
+                    || dealer.complaints.len() > dealer.justified.len()
+            }).collect()
+    }
+
+    /// Returns `true` if at least `threshold + 1` dealers are complete and not disqualified. Then
+    /// at least one of them is honest, so nobody knows the master secret key.
+    pub fn is_ready(&self) -> bool {
+        self.qualified_dealers().len() > self.threshold
+    }
+
+    /// Returns the public key set and our secret key share, generated from the contributions of
+    /// all complete dealers that are not disqualified.
+    ///
+    /// Returns `Error::NotEnoughShares` if fewer than `threshold + 1` dealers qualify, or if we
+    /// don't have enough valid values to compute our row.
+    pub fn generate(&self) -> Result<(PublicKeySet, SecretKeyShare)> {
+        let qualified = self.qualified_dealers();
+        if qualified.len() <= self.threshold {
+            return Err(Error::NotEnoughShares {
+                supplied: qualified.len(),
+                needed: self.threshold + 1,
+            });
+        }
+        let mut pk_commit = Poly::zero().commitment();
+        let mut sk_val = Fr::zero();
+        for idx in qualified {
+            let dealer = &self.dealers[idx];
+            if let Some(ref commit) = dealer.commit {
+                pk_commit += commit.row(0);
+            }
+            if let Some(ref row) = dealer.row {
+                sk_val.add_assign(&row.evaluate(0));
+            } else if dealer.values.len() > self.threshold {
+                let samples = dealer.values.iter().take(self.threshold + 1);
+                let row = Poly::interpolate(samples.map(|(s, value)| (s + 1, *value)));
+                sk_val.add_assign(&row.evaluate(0));
+            } else {
+                clear_fr(&mut sk_val);
+                return Err(Error::NotEnoughShares {
+                    supplied: dealer.values.len(),
+                    needed: self.threshold + 1,
+                });
+            }
+        }
+        let sk_share = SecretKeyShare::from_mut(&mut sk_val);
+        Ok((pk_commit.into(), sk_share))
+    }
+
+    /// Returns the complete dealers that are not disqualified.
+    fn qualified_dealers(&self) -> BTreeSet<usize> {
+        let disqualified = self.disqualified_dealers();
+        let complete = self.complete_dealers();
+        complete.difference(&disqualified).cloned().collect()
+    }
+
+    /// Returns `true` if the commitment has the right degree and number of coefficients.
+    fn is_valid_commitment(&self, commit: &BivarCommitment) -> bool {
+        let coeff_num = (self.threshold + 1) * (self.threshold + 2) / 2;
+        commit.degree() == self.threshold && commit.coeff.len() == coeff_num
+    }
+
+    /// Stores our valid row from `dealer`, and returns our ack for it.
+    fn accept_row<R: Rng>(&mut self, dealer: usize, row: Poly, rng: &mut R) -> Ack {
+        let values = self
+            .pub_keys
+            .iter()
+            .enumerate()
+            .map(|(s, pk)| {
+                let label = Round::KeyGenAck.label(&[dealer, self.our_idx, s]);
+                encrypt_fr_vec(pk, &[row.evaluate(s + 1)], &label, rng)
+            }).collect();
+        self.dealers[dealer].row = Some(row);
+        Ack { dealer, values }
+    }
+
+    /// Decrypts a row of degree `threshold` from `dealer`.
+    fn decrypt_row(&self, dealer: usize, ct: &Ciphertext) -> Option<Poly> {
+        let label = Round::KeyGenRow.label(&[dealer, self.our_idx]);
+        decrypt_fr_vec(&self.sec_key, ct, &label, self.threshold + 1).map(Poly::from)
+    }
+
+    /// Decrypts a single value of `dealer`'s polynomial, from an ack by `sender`.
+    fn decrypt_value(&self, dealer: usize, sender: usize, ct: &Ciphertext) -> Option<Fr> {
+        let label = Round::KeyGenAck.label(&[dealer, sender, self.our_idx]);
+        decrypt_fr(&self.sec_key, ct, &label)
+    }
+}
+
+/// The state of a dealer of a univariate polynomial, of which every node receives one value.
+#[derive(Default)]
+struct ValueDealer {
+    /// The commitment to the dealer's polynomial, once we received it.
+    commit: Option<Commitment>,
+    /// Our value, if we received a valid one.
+    value: Option<Fr>,
+    /// The nodes that complained about the dealer.
+    complaints: BTreeSet<usize>,
+    /// The complainers whose complaints the dealer answered with a valid justification.
+    justified: BTreeSet<usize>,
+    /// Whether the dealer sent an invalid justification.
+    invalid_justification: bool,
+}
+
+impl Drop for ValueDealer {
+    fn drop(&mut self) {
+        if let Some(ref mut value) = self.value {
+            clear_fr(value);
+        }
+    }
+}
+
+/// The dealers' parts, complaints and justifications in the refresh, resharing and recovery,
+/// where each dealer commits to a univariate polynomial and sends one value of it to every node.
+///
+/// As in the key generation, a node that didn't receive a valid value complains, and the dealer
+/// must publicly reveal the value. Since all nodes handle the same messages, they agree on which
+/// dealers qualify, even if some of them sent invalid values to some nodes.
+pub(crate) struct ValueDealings {
+    /// The protocol the values are dealt in.
+    round: Round,
+    /// Our node index, or `None` if we don't receive any values.
+    our_idx: Option<usize>,
+    /// Our secret key, to decrypt the values sent to us.
+    sec_key: SecretKey,
+    /// The number of nodes that receive values.
+    node_num: usize,
+    /// The maximum number of complaints about a dealer. Each of them reveals a value, so more
+    /// would reveal the dealer's polynomial.
+    threshold: usize,
+    /// The state of each dealer, by index.
+    dealers: Vec<ValueDealer>,
+}
+
+impl ValueDealings {
+    /// Creates the state for `dealer_num` dealers and `node_num` receiving nodes.
+    pub(crate) fn new(
+        round: Round,
+        our_idx: Option<usize>,
+        sec_key: SecretKey,
+        dealer_num: usize,
+        node_num: usize,
+        threshold: usize,
+    ) -> ValueDealings {
+        ValueDealings {
+            round,
+            our_idx,
+            sec_key,
+            node_num,
+            threshold,
+            dealers: (0..dealer_num).map(|_| ValueDealer::default()).collect(),
+        }
+    }
+
+    /// Returns `true` if `dealer` is a valid dealer index.
+    pub(crate) fn is_dealer(&self, dealer: usize) -> bool {
+        dealer < self.dealers.len()
+    }
+
+    /// Returns `true` if we already received a part from `dealer`.
+    pub(crate) fn has_part(&self, dealer: usize) -> bool {
+        self.dealers[dealer].commit.is_some()
+    }
+
+    /// Handles the commitment and the encrypted values of a part from `dealer`, whose other
+    /// properties the caller has already checked.
+    ///
+    /// Even if our value is invalid, the commitment is kept, so that we can complain and receive
+    /// our value in the dealer's justification.
+    pub(crate) fn handle_part(
+        &mut self,
+        dealer: usize,
+        commit: Commitment,
+        values: &[Ciphertext],
+    ) -> ::std::result::Result<(), Fault> {
+        if values.len() != self.node_num {
+            return Err(Fault::ValueCount);
+        }
+        let our_idx = match self.our_idx {
+            None => {
+                self.dealers[dealer].commit = Some(commit);
+                return Ok(());
+            }
+            Some(our_idx) => our_idx,
+        };
+        let label = self.round.label(&[dealer, our_idx]);
+        let value = decrypt_fr(&self.sec_key, &values[our_idx], &label);
+        let value_g1 = commit.evaluate(our_idx + 1);
+        self.dealers[dealer].commit = Some(commit);
+        let mut value = value.ok_or(Fault::ValueDecryption)?;
+        if value_g1 != G1Affine::one().mul(value) {
+            clear_fr(&mut value);
+            return Err(Fault::ValueMismatch);
+        }
+        self.dealers[dealer].value = Some(value);
+        clear_fr(&mut value);
+        Ok(())
+    }
+
+    /// Returns our complaints about all dealers whose part contained an invalid value for us.
+    pub(crate) fn complaints(&self) -> Vec<Complaint> {
+        if self.our_idx.is_none() {
+            return Vec::new();
+        }
+        (0..self.dealers.len())
+            .filter(|&dealer| {
+                let state = &self.dealers[dealer];
+                state.commit.is_some() && state.value.is_none()
+            })
+            .map(|dealer| Complaint { dealer }).collect()
+    }
+
+    /// Handles a complaint from node `sender`. If `ours` contains the complained-about dealer's
+    /// index and polynomial, returns the justification, which must be sent to all nodes.
+    ///
+    /// If more than `threshold` nodes complain about a dealer, it doesn't answer: It is
+    /// disqualified anyway, and revealing more values would reveal its polynomial.
+    pub(crate) fn handle_complaint(
+        &mut self,
+        sender: usize,
+        complaint: Complaint,
+        ours: Option<(usize, &Poly)>,
+    ) -> ::std::result::Result<Option<ValueJustification>, Fault> {
+        if sender >= self.node_num {
+            return Err(Fault::UnknownSender);
+        }
+        if complaint.dealer >= self.dealers.len() {
+            return Err(Fault::UnknownDealer);
+        }
+        let state = &mut self.dealers[complaint.dealer];
+        if !state.complaints.insert(sender) {
+            return Err(Fault::DuplicateComplaint);
+        }
+        match ours {
+            Some((idx, poly)) if idx == complaint.dealer => {
+                if state.complaints.len() > self.threshold {
+                    return Ok(None);
+                }
+                Ok(Some(ValueJustification::new(sender, poly)))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// Handles a justification from `dealer`. If it answers our own complaint, we use the value.
+    ///
+    /// An invalid justification disqualifies the dealer.
+    pub(crate) fn handle_justification(
+        &mut self,
+        dealer: usize,
+        justification: ValueJustification,
+    ) -> ::std::result::Result<(), Fault> {
+        if dealer >= self.dealers.len() {
+            return Err(Fault::UnknownSender);
+        }
+        let ValueJustification {
+            complainer,
+            mut value,
+        } = justification;
+        let state = &mut self.dealers[dealer];
+        if !state.complaints.contains(&complainer) {
+            return Err(Fault::UnexpectedJustification);
+        }
+        if state.justified.contains(&complainer) {
+            return Err(Fault::DuplicateJustification);
+        }
+        let valid = match state.commit {
+            None => false,
+            Some(ref commit) => commit.evaluate(complainer + 1) == G1Affine::one().mul(value),
+        };
+        if !valid {
+            clear_fr(&mut value);
+            state.invalid_justification = true;
+            return Err(Fault::InvalidJustification);
+        }
+        state.justified.insert(complainer);
+        if Some(complainer) == self.our_idx && state.value.is_none() {
+            state.value = Some(value);
+        }
+        clear_fr(&mut value);
+        Ok(())
+    }
+
+    /// Returns the indices of the dealers that didn't validly answer all complaints, or that more
+    /// than `threshold` nodes complained about.
+    ///
+    /// This is only final after all complaints and justifications have been handled.
+    pub(crate) fn disqualified_dealers(&self) -> BTreeSet<usize> {
+        (0..self.dealers.len())
+            .filter(|&idx| {
+                let dealer = &self.dealers[idx];
+                dealer.invalid_justification
+                    || dealer.complaints.len() > self.threshold
+                    || dealer.complaints.len() > dealer.justified.len()
+            }).collect()
+    }
+
+    /// Returns the indices of the dealers that sent a part and are not disqualified.
+    pub(crate) fn qualified_dealers(&self) -> BTreeSet<usize> {
+        let disqualified = self.disqualified_dealers();
+        (0..self.dealers.len())
+            .filter(|dealer| self.has_part(*dealer) && !disqualified.contains(dealer))
+            .collect()
+    }
+
+    /// Returns the commitment of `dealer`'s part, if we received it.
+    pub(crate) fn commitment(&self, dealer: usize) -> Option<&Commitment> {
+        self.dealers[dealer].commit.as_ref()
+    }
+
+    /// Returns our value of `dealer`'s polynomial.
+    ///
+    /// Returns `Error::MissingValue` if we don't have a valid value from the dealer.
+    pub(crate) fn value(&self, dealer: usize) -> Result<&Fr> {
+        let value = self.dealers[dealer].value.as_ref();
+        value.ok_or(Error::MissingValue { dealer })
+    }
+}
+
+/// Returns `Error::IndexOutOfRange` if `our_idx` is not an index of `pub_keys`, and
+/// `Error::KeyMismatch` if `sec_key` doesn't match the public key with that index.
+pub(crate) fn check_our_key(
+    our_idx: usize,
+    sec_key: &SecretKey,
+    pub_keys: &[PublicKey],
+) -> Result<()> {
+    check_index(our_idx, pub_keys.len())?;
+    if sec_key.public_key() != pub_keys[our_idx] {
+        return Err(Error::KeyMismatch);
+    }
+    Ok(())
+}
+
+/// Returns `Error::IndexOutOfRange` if `index` is not less than the number of nodes, `count`.
+pub(crate) fn check_index(index: usize, count: usize) -> Result<()> {
+    if index >= count {
+        return Err(Error::IndexOutOfRange { index, count });
+    }
+    Ok(())
+}
+
+/// The kinds of encrypted messages in the key generation, refresh, resharing and recovery.
+///
+/// Each ciphertext is labeled with its kind and the indices of its sender and recipient, so that
+/// a node can't copy another node's ciphertexts, or replay them in a different context.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Round {
+    /// A row in a key generation `Part`.
+    KeyGenRow = 1,
+    /// A value in a key generation `Ack`.
+    KeyGenAck,
+    /// A value in a `RefreshPart`.
+    Refresh,
+    /// A value in a `ResharePart`.
+    Reshare,
+    /// A value in a `RecoveryPart`.
+    Recovery,
+    /// A recovery `Contribution`.
+    RecoveryContribution,
+}
+
+impl Round {
+    /// Returns the label for a ciphertext of this kind: a byte identifying the kind, followed by
+    /// the indices as big-endian `u64`s.
+    pub(crate) fn label(self, indices: &[usize]) -> Vec<u8> {
+        let mut label = vec![self as u8];
+        let mut buf = [0u8; 8];
+        for &idx in indices {
+            BigEndian::write_u64(&mut buf, idx as u64);
+            label.extend_from_slice(&buf);
+        }
+        label
+    }
+}
+
+/// Encrypts the field elements to the public key, with the given label.
+pub(crate) fn encrypt_fr_vec<R: Rng>(
+    pk: &PublicKey,
+    values: &[Fr],
+    label: &[u8],
+    rng: &mut R,
+) -> Ciphertext {
+    let mut bytes = fr_vec_to_bytes(values);
+    let ct = pk.encrypt_with_label_and_rng(rng, &bytes, label);
+    clear_bytes(&mut bytes);
+    ct
+}
+
+/// Decrypts `len` field elements, or returns `None` if the ciphertext is invalid or doesn't
+/// have the given label.
+pub(crate) fn decrypt_fr_vec(
+    sk: &SecretKey,
+    ct: &Ciphertext,
+    label: &[u8],
+    len: usize,
+) -> Option<Vec<Fr>> {
+    if ct.label() != label {
+        return None;
+    }
+    let mut bytes = sk.decrypt(ct)?;
+    let values = fr_vec_from_bytes(&bytes);
+    clear_bytes(&mut bytes);
+    values.filter(|values| values.len() == len)
+}
+
+/// Decrypts a single field element, or returns `None` if the ciphertext is invalid or doesn't
+/// have the given label.
+pub(crate) fn decrypt_fr(sk: &SecretKey, ct: &Ciphertext, label: &[u8]) -> Option<Fr> {
+    let mut values = decrypt_fr_vec(sk, ct, label, 1)?;
+    let value = values[0];
+    clear_fr(&mut values[0]);
+    Some(value)
+}
+
+/// Returns the concatenated big-endian representations of the field elements.
+fn fr_vec_to_bytes(values: &[Fr]) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    for value in values {
+        value
+            .into_repr()
+            .write_be(&mut bytes)
+            .expect("writing to a vector never fails");
+    }
+    bytes
+}
+
+/// Parses concatenated big-endian representations of field elements, or returns `None` if they
+/// are invalid.
+fn fr_vec_from_bytes(bytes: &[u8]) -> Option<Vec<Fr>> {
+    let fr_len = fr_vec_to_bytes(&[Fr::zero()]).len();
+    let chunks = bytes.chunks_exact(fr_len);
+    if !chunks.remainder().is_empty() {
+        return None;
+    }
+    chunks
+        .map(|chunk| {
+            let mut repr = Fr::zero().into_repr();
+            repr.read_be(chunk).ok()?;
+            Fr::from_repr(repr).ok()
+        }).collect()
+}
+
+/// Overwrites a buffer that contained secret values with zeros.
+fn clear_bytes(bytes: &mut [u8]) {
+    unsafe { memzero(bytes.as_mut_ptr(), bytes.len()) };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use rand::{self, Rng};
+
+    /// Runs the key generation with `num_nodes` nodes, where each dealer's part is modified by
+    /// `corrupt(dealer, part, pub_keys)`, and `justify(dealer, justification)` instead of their
+    /// justifications. All messages are broadcast to all nodes, in the same order.
+    fn run_key_gen<F, G>(num_nodes: usize, threshold: usize, corrupt: F, justify: G) -> Vec<KeyGen>
+    where
+        F: Fn(usize, &mut Part, &[PublicKey]),
+        G: Fn(usize, Justification) -> Option<Justification>,
+    {
+        let mut rng = rand::thread_rng();
+        let sec_keys: Vec<SecretKey> = (0..num_nodes).map(|_| rng.gen()).collect();
+        let pub_keys: Vec<PublicKey> = sec_keys.iter().map(SecretKey::public_key).collect();
+        let mut nodes = Vec::new();
+        let mut parts = Vec::new();
+        for (idx, sec_key) in sec_keys.into_iter().enumerate() {
+            let (node, mut part) = KeyGen::new(idx, sec_key, pub_keys.clone(), threshold, &mut rng)
+                .expect("new key gen");
+            corrupt(idx, &mut part, &pub_keys);
+            nodes.push(node);
+            parts.push(part);
+        }
+
+        let mut acks = Vec::new();
+        for (dealer, part) in parts.into_iter().enumerate() {
+            for (m, node) in nodes.iter_mut().enumerate() {
+                if let Ok(ack) = node.handle_part(dealer, part.clone(), &mut rng) {
+                    acks.push((m, ack));
+                }
+            }
+        }
+
+        let mut complaints = Vec::new();
+        for (m, node) in nodes.iter().enumerate() {
+            complaints.extend(node.complaints().into_iter().map(|c| (m, c)));
+        }
+        let mut justifications = Vec::new();
+        for (sender, complaint) in complaints {
+            for (m, node) in nodes.iter_mut().enumerate() {
+                let result = node.handle_complaint(sender, complaint);
+                if let Some(justification) = result.expect("valid complaint") {
+                    justifications.extend(justify(m, justification).map(|j| (m, j)));
+                }
+            }
+        }
+        for (dealer, justification) in justifications {
+            for (m, node) in nodes.iter_mut().enumerate() {
+                let result = node.handle_justification(dealer, justification.clone(), &mut rng);
+                if let Ok(Some(ack)) = result {
+                    acks.push((m, ack));
+                }
+            }
+        }
+
+        for (sender, ack) in acks {
+            for node in &mut nodes {
+                node.handle_ack(sender, ack.clone()).expect("valid ack");
+            }
+        }
+        nodes
+    }
+
+    /// Replaces node `m`'s row in `dealer`'s part with an invalid one.
+    fn corrupt_row(part: &mut Part, dealer: usize, m: usize, pk: &PublicKey) {
+        let mut rng = rand::thread_rng();
+        let label = Round::KeyGenRow.label(&[dealer, m]);
+        part.rows[m] = encrypt_fr_vec(pk, &Poly::monomial(1).coeff, &label, &mut rng);
+    }
+
+    /// Checks that all nodes generate matching keys, and returns the public key set.
+    fn check_keys(nodes: &[KeyGen]) -> PublicKeySet {
+        let mut pk_set = None;
+        for (m, node) in nodes.iter().enumerate() {
+            assert!(node.is_ready());
+            let (node_pk_set, sk_share) = node.generate().expect("generate keys");
+            assert_eq!(node_pk_set.public_key_share(m), sk_share.public_key_share());
+            if let Some(ref pk_set) = pk_set {
+                assert_eq!(*pk_set, node_pk_set);
+            }
+            pk_set = Some(node_pk_set);
+        }
+        pk_set.expect("at least one node")
+    }
+
+    #[test]
+    fn test_key_gen() {
+        let nodes = run_key_gen(4, 1, |_, _, _| (), |_, j| Some(j));
+        for node in &nodes {
+            assert_eq!(4, node.complete_dealers().len());
+            assert!(node.disqualified_dealers().is_empty());
+        }
+        let pk_set = check_keys(&nodes);
+
+        // Any two nodes can sign.
+        let msg = "Totally real news";
+        let sigs: BTreeMap<_, _> = nodes[1..3]
+            .iter()
+            .enumerate()
+            .map(|(i, node)| {
+                let (_, sk_share) = node.generate().expect("generate keys");
+                (i + 1, sk_share.sign(msg))
+            }).collect();
+        let sig = pk_set.combine_signatures(&sigs).expect("enough shares");
+        assert!(pk_set.public_key().verify(&sig, msg));
+    }
+
+    #[test]
+    fn test_key_gen_invalid_row() {
+        // Dealer 2 sends an invalid row to node 0. Node 0 complains, and receives the correct
+        // row in the justification.
+        let corrupt = |dealer: usize, part: &mut Part, pub_keys: &[PublicKey]| {
+            if dealer == 2 {
+                corrupt_row(part, dealer, 0, &pub_keys[0]);
+            }
+        };
+        let nodes = run_key_gen(4, 1, corrupt, |_, j| Some(j));
+        for node in &nodes {
+            assert_eq!(4, node.complete_dealers().len());
+            assert!(node.disqualified_dealers().is_empty());
+        }
+        check_keys(&nodes);
+    }
+
+    #[test]
+    fn test_key_gen_disqualified() {
+        // Dealers 1 and 3 send invalid rows to node 0. Dealer 1 doesn't answer the complaint,
+        // and dealer 3 sends an invalid justification.
+        let corrupt = |dealer: usize, part: &mut Part, pub_keys: &[PublicKey]| {
+            if dealer == 1 || dealer == 3 {
+                corrupt_row(part, dealer, 0, &pub_keys[0]);
+            }
+        };
+        let justify = |dealer: usize, mut justification: Justification| match dealer {
+            1 => None,
+            3 => {
+                justification.row += Poly::monomial(1);
+                Some(justification)
+            }
+            _ => Some(justification),
+        };
+        let nodes = run_key_gen(4, 1, corrupt, justify);
+        let disqualified: BTreeSet<usize> = [1, 3].iter().cloned().collect();
+        for node in &nodes {
+            assert_eq!(disqualified, node.disqualified_dealers());
+        }
+
+        // The keys are generated from the contributions of dealers 0 and 2.
+        let pk_set = check_keys(&nodes);
+        let commit = nodes[0].dealers[0].commit.as_ref().expect("commitment");
+        let mut pk_commit = commit.row(0);
+        let commit = nodes[0].dealers[2].commit.as_ref().expect("commitment");
+        pk_commit += commit.row(0);
+        assert_eq!(PublicKeySet::from(pk_commit), pk_set);
+    }
+
+    #[test]
+    fn test_faults() {
+        let mut rng = rand::thread_rng();
+        let sec_keys: Vec<SecretKey> = (0..4).map(|_| rng.gen()).collect();
+        let pub_keys: Vec<PublicKey> = sec_keys.iter().map(SecretKey::public_key).collect();
+        let new_key_gen = |idx: usize, threshold, rng: &mut rand::ThreadRng| {
+            KeyGen::new(idx, sec_keys[idx].clone(), pub_keys.clone(), threshold, rng)
+                .expect("new key gen")
+        };
+        let (mut node, _) = new_key_gen(0, 1, &mut rng);
+        let (_, part) = new_key_gen(1, 1, &mut rng);
+        let (_, wrong_degree_part) = new_key_gen(2, 2, &mut rng);
+
+        // Our index must be valid, and our secret key must match our public key.
+        let result = KeyGen::new(4, sec_keys[0].clone(), pub_keys.clone(), 1, &mut rng);
+        let expected = Error::IndexOutOfRange { index: 4, count: 4 };
+        assert_eq!(Some(expected), result.err());
+        let result = KeyGen::new(1, sec_keys[0].clone(), pub_keys.clone(), 1, &mut rng);
+        assert_eq!(Some(Error::KeyMismatch), result.err());
+
+        let result = node.handle_part(4, part.clone(), &mut rng);
+        assert_eq!(Err(Fault::UnknownSender), result);
+        let result = node.handle_part(2, wrong_degree_part, &mut rng);
+        assert_eq!(Err(Fault::WrongDegree), result);
+        let mut short_part = part.clone();
+        short_part.rows.pop();
+        let result = node.handle_part(3, short_part, &mut rng);
+        assert_eq!(Err(Fault::RowCount), result);
+        let ack = node
+            .handle_part(1, part.clone(), &mut rng)
+            .expect("valid part");
+        let result = node.handle_part(1, part.clone(), &mut rng);
+        assert_eq!(Err(Fault::DuplicatePart), result);
+
+        let mut wrong_ack = ack.clone();
+        let label = Round::KeyGenAck.label(&[1, 2, 0]);
+        wrong_ack.values[0] = encrypt_fr_vec(&pub_keys[0], &[Fr::one()], &label, &mut rng);
+        let result = node.handle_ack(2, wrong_ack);
+        assert_eq!(Err(Fault::ValueMismatch), result);
+        let mut unknown_ack = ack.clone();
+        unknown_ack.dealer = 3;
+        let result = node.handle_ack(0, unknown_ack);
+        assert_eq!(Err(Fault::UnknownDealer), result);
+        node.handle_ack(0, ack.clone()).expect("valid ack");
+        let result = node.handle_ack(0, ack);
+        assert_eq!(Err(Fault::DuplicateAck), result);
+        assert!(!node.is_ready());
+
+        // We complain about dealers 0, 2 and 3, from whom we have no valid row.
+        let complaints = node.complaints();
+        let dealers: Vec<usize> = complaints.iter().map(Complaint::dealer).collect();
+        assert_eq!(vec![0, 2, 3], dealers);
+        let complaint = Complaint { dealer: 0 };
+        let justification = node
+            .handle_complaint(1, complaint)
+            .expect("valid complaint")
+            .expect("justification");
+        assert_eq!(1, justification.complainer());
+        let result = node.handle_complaint(1, complaint);
+        assert_eq!(Err(Fault::DuplicateComplaint), result);
+
+        // We don't know our own part yet, so we can't verify the justification.
+        let result = node.handle_justification(0, justification.clone(), &mut rng);
+        assert_eq!(Err(Fault::InvalidJustification), result);
+        let result = node.handle_justification(0, justification.clone(), &mut rng);
+        assert_eq!(Err(Fault::InvalidJustification), result);
+        let result = node.handle_justification(2, justification, &mut rng);
+        assert_eq!(Err(Fault::UnexpectedJustification), result);
+        assert!(node.disqualified_dealers().contains(&0));
+
+        // The rows are labeled with the dealer's index, so dealer 3 can't copy dealer 1's part.
+        let result = node.handle_part(3, part, &mut rng);
+        assert_eq!(Err(Fault::RowDecryption), result);
+    }
+
+    #[test]
+    fn test_fr_vec_bytes() {
+        let mut rng = rand::thread_rng();
+        let values: Vec<Fr> = (0..5).map(|_| rng.gen()).collect();
+        let bytes = fr_vec_to_bytes(&values);
+        assert_eq!(Some(values), fr_vec_from_bytes(&bytes));
+        assert_eq!(None, fr_vec_from_bytes(&bytes[1..]));
+        assert_eq!(Some(Vec::new()), fr_vec_from_bytes(&[]));
+    }
+}
@@ -1,28 +1,166 @@
 //! Crypto errors.
 
+use std::fmt;
+
+use hex_fmt::HexFmt;
+
 /// A crypto error.
 #[derive(Clone, Eq, PartialEq, Debug, Fail)]
 pub enum Error {
-    #[fail(display = "Not enough signature shares")]
-    NotEnoughShares,
-    #[fail(display = "Signature shares contain a duplicated index")]
-    DuplicateEntry,
+    // Combining shares, and verifying signatures and ciphertexts.
+    #[fail(
+        display = "Not enough shares: {} supplied, {} needed",
+        supplied, needed
+    )]
+    NotEnoughShares {
+        /// The number of shares that were supplied.
+        supplied: usize,
+        /// The number of shares that are needed.
+        needed: usize,
+    },
+    #[fail(display = "Shares contain a duplicated index: {:?}", index)]
+    DuplicateEntry {
+        /// The index that appears more than once.
+        index: ShareIndex,
+    },
+    #[fail(
+        display = "Not enough valid shares: {} valid, {} needed, invalid: {:?}",
+        valid, needed, indices
+    )]
+    InvalidShares {
+        /// The indices of the shares that failed verification.
+        indices: Vec<ShareIndex>,
+        /// The number of shares that passed verification.
+        valid: usize,
+        /// The number of valid shares that are needed.
+        needed: usize,
+    },
+    #[fail(display = "Invalid signatures at positions {:?}", indices)]
+    InvalidSignatures {
+        /// The positions of the invalid signatures in the batch.
+        indices: Vec<usize>,
+    },
+    #[fail(
+        display = "Participant {} supplied {} shares, but has weight {}",
+        participant, supplied, weight
+    )]
+    WrongWeight {
+        /// The participant's index.
+        participant: usize,
+        /// The number of shares the participant supplied.
+        supplied: usize,
+        /// The participant's weight, i.e. the number of shares it holds.
+        weight: usize,
+    },
+    #[fail(display = "Two different node IDs are mapped to the same index")]
+    IdCollision,
+    #[fail(display = "Invalid ciphertext")]
+    InvalidCiphertext,
+
+    // Decoding keys, signatures and other values from bytes.
+    #[fail(
+        display = "Invalid length: expected {} bytes, got {}",
+        expected, actual
+    )]
+    InvalidLength {
+        /// The expected number of bytes.
+        expected: usize,
+        /// The actual number of bytes.
+        actual: usize,
+    },
+    #[fail(display = "Invalid encoding of a group element: {}", _0)]
+    InvalidPoint(String),
+    #[fail(display = "Invalid encoding of a field element: {}", _0)]
+    InvalidFieldElement(String),
+
+    // Polynomials and the key generation, refresh and recovery protocols.
     #[fail(display = "The degree is too high for the coefficients to be indexed by usize.")]
     DegreeTooHigh,
+    #[fail(display = "No valid value from dealer {}", dealer)]
+    MissingValue {
+        /// The index of the dealer.
+        dealer: usize,
+    },
+    #[fail(display = "The secret key doesn't match the expected public key")]
+    KeyMismatch,
+    #[fail(display = "Index {} is out of range for {} nodes", index, count)]
+    IndexOutOfRange {
+        /// The node index.
+        index: usize,
+        /// The number of nodes.
+        count: usize,
+    },
 }
 
 /// A crypto result.
 pub type Result<T> = ::std::result::Result<T, Error>;
 
+/// The index of a share, as the caller supplied it, e.g. to `combine_signatures`.
+///
+/// Its `Debug` output is the integer, or the hexadecimal representation of the field element.
+#[derive(Clone, Eq, PartialEq)]
+pub enum ShareIndex {
+    /// An integer index, e.g. a `usize`.
+    Int(i128),
+    /// Any other index, e.g. a `NodeId`, as the big-endian representation of its field element.
+    Field(Vec<u8>),
+}
+
+impl fmt::Debug for ShareIndex {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ShareIndex::Int(i) => write!(f, "{}", i),
+            ShareIndex::Field(ref bytes) => write!(f, "{:0.10}", HexFmt(bytes)),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use super::Error;
+    use super::{Error, ShareIndex};
 
     /// No-op function that compiles only if its argument is `Send + Sync`.
     fn is_send_and_sync<T: Send + Sync>(_: T) {}
 
     #[test]
     fn errors_are_send_and_sync() {
-        is_send_and_sync(Error::NotEnoughShares);
+        is_send_and_sync(Error::NotEnoughShares {
+            supplied: 2,
+            needed: 3,
+        });
+        is_send_and_sync(Error::InvalidShares {
+            indices: vec![ShareIndex::Int(5)],
+            valid: 2,
+            needed: 3,
+        });
+    }
+
+    #[test]
+    fn error_display() {
+        let err = Error::NotEnoughShares {
+            supplied: 2,
+            needed: 3,
+        };
+        assert_eq!("Not enough shares: 2 supplied, 3 needed", err.to_string());
+        let err = Error::InvalidLength {
+            expected: 48,
+            actual: 47,
+        };
+        assert_eq!("Invalid length: expected 48 bytes, got 47", err.to_string());
+        let err = Error::InvalidSignatures {
+            indices: vec![3, 7],
+        };
+        assert_eq!("Invalid signatures at positions [3, 7]", err.to_string());
+        let err = Error::InvalidShares {
+            indices: vec![ShareIndex::Int(4), ShareIndex::Int(-2)],
+            valid: 1,
+            needed: 3,
+        };
+        let msg = "Not enough valid shares: 1 valid, 3 needed, invalid: [4, -2]";
+        assert_eq!(msg, err.to_string());
+        let err = Error::DuplicateEntry {
+            index: ShareIndex::Field(vec![0xab, 0x05]),
+        };
+        assert_eq!("Shares contain a duplicated index: ab05", err.to_string());
     }
 }
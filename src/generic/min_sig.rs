@@ -0,0 +1,653 @@
+//! The "minimal signature" variant of the keys and signatures, with public keys in `G2` and
+//! signatures in `G1`.
+//!
+//! The types in the parent module have public keys in `G1` and signatures in `G2`. With
+//! BLS12-381, that makes public keys 48 and signatures 96 bytes long. The types in this module
+//! swap the groups: Signatures are 48 and public keys 96 bytes long, which is preferable if many
+//! more signatures than keys need to be stored or transmitted.
+//!
+//! The secret keys are the same in both variants, and can be converted with `From`. However, a
+//! signature can only be verified with the public key of its own variant, and signature shares
+//! of different variants can't be combined.
+
+use std::borrow::Borrow;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+
+use hex_fmt::HexFmt;
+use pairing::{CurveAffine, CurveProjective, EncodedPoint, Engine};
+use rand::{Rand, Rng};
+
+use error::{Error, Result};
+use generic::poly::{evaluate_g1, Poly};
+use generic::{
+    hash_g1, interpolate, into_fr_plus_1, not_enough_valid_shares, point_from_bytes, point_to_bytes,
+};
+use into_fr::IntoFr;
+use {generic, serde_impl};
+
+/// A public key in `G2`.
+#[derive(Deserialize, Serialize)]
+#[serde(bound = "")]
+pub struct PublicKey<E: Engine>(#[serde(with = "serde_impl::projective")] pub(crate) E::G2);
+
+impl<E: Engine> Clone for PublicKey<E> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<E: Engine> Copy for PublicKey<E> {}
+
+impl<E: Engine> PartialEq for PublicKey<E> {
+    fn eq(&self, other: &Self) -> bool {
+        self.0 == other.0
+    }
+}
+
+impl<E: Engine> Eq for PublicKey<E> {}
+
+impl<E: Engine> Hash for PublicKey<E> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.into_affine().into_compressed().as_ref().hash(state);
+    }
+}
+
+impl<E: Engine> fmt::Debug for PublicKey<E> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let uncomp = self.0.into_affine().into_uncompressed();
+        write!(f, "PublicKey({:0.10})", HexFmt(uncomp))
+    }
+}
+
+impl<E: Engine> PublicKey<E> {
+    /// Returns `true` if the signature matches the element of `G1`.
+    ///
+    /// To verify signatures made by other BLS implementations, use `hash_to_g1` to hash the
+    /// message with the same domain separation tag as the signer.
+    pub fn verify_g1<H: Into<E::G1Affine>>(&self, sig: &Signature<E>, hash: H) -> bool {
+        E::pairing(hash, self.0) == E::pairing(sig.0, E::G2Affine::one())
+    }
+
+    /// Returns `true` if the signature matches the message.
+    ///
+    /// This is equivalent to `verify_g1(sig, hash_g1(msg))`.
+    pub fn verify<M: AsRef<[u8]>>(&self, sig: &Signature<E>, msg: M) -> bool {
+        self.verify_g1(sig, hash_g1::<E, M>(msg))
+    }
+
+    /// Returns a byte string representation of the public key.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        point_to_bytes(&self.0)
+    }
+
+    /// Decodes a public key from its byte string representation.
+    ///
+    /// Returns an error if the length is wrong, if the bytes don't encode a point in the prime
+    /// order subgroup, or if they encode the identity element.
+    pub fn from_bytes<B: AsRef<[u8]>>(bytes: B) -> Result<Self> {
+        Ok(PublicKey(point_from_bytes(bytes.as_ref(), false)?))
+    }
+
+    /// Returns the sum of the given public keys.
+    ///
+    /// A signature on a message created by each of the corresponding secret keys, combined with
+    /// `Signature::aggregate`, can be verified with the aggregated public key. This is vulnerable
+    /// to rogue key attacks, unless it has been verified that the owner of each key knows the
+    /// corresponding secret key.
+    pub fn aggregate<I, B>(pub_keys: I) -> PublicKey<E>
+    where
+        I: IntoIterator<Item = B>,
+        B: Borrow<PublicKey<E>>,
+    {
+        let mut sum = E::G2::zero();
+        for pk in pub_keys {
+            sum.add_assign(&pk.borrow().0);
+        }
+        PublicKey(sum)
+    }
+}
+
+/// A public key share in `G2`.
+#[derive(Deserialize, Serialize)]
+#[serde(bound = "")]
+pub struct PublicKeyShare<E: Engine>(pub(crate) PublicKey<E>);
+
+impl<E: Engine> Clone for PublicKeyShare<E> {
+    fn clone(&self) -> Self {
+        PublicKeyShare(self.0)
+    }
+}
+
+impl<E: Engine> PartialEq for PublicKeyShare<E> {
+    fn eq(&self, other: &Self) -> bool {
+        self.0 == other.0
+    }
+}
+
+impl<E: Engine> Eq for PublicKeyShare<E> {}
+
+impl<E: Engine> Hash for PublicKeyShare<E> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.hash(state);
+    }
+}
+
+impl<E: Engine> fmt::Debug for PublicKeyShare<E> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let uncomp = (self.0).0.into_affine().into_uncompressed();
+        write!(f, "PublicKeyShare({:0.10})", HexFmt(uncomp))
+    }
+}
+
+impl<E: Engine> PublicKeyShare<E> {
+    /// Returns `true` if the signature matches the element of `G1`.
+    pub fn verify_g1<H: Into<E::G1Affine>>(&self, sig: &SignatureShare<E>, hash: H) -> bool {
+        self.0.verify_g1(&sig.0, hash)
+    }
+
+    /// Returns `true` if the signature matches the message.
+    ///
+    /// This is equivalent to `verify_g1(sig, hash_g1(msg))`.
+    pub fn verify<M: AsRef<[u8]>>(&self, sig: &SignatureShare<E>, msg: M) -> bool {
+        self.verify_g1(sig, hash_g1::<E, M>(msg))
+    }
+
+    /// Returns a byte string representation of the public key share.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.0.to_bytes()
+    }
+
+    /// Decodes a public key share from its byte string representation.
+    ///
+    /// Returns an error if the length is wrong, if the bytes don't encode a point in the prime
+    /// order subgroup, or if they encode the identity element.
+    pub fn from_bytes<B: AsRef<[u8]>>(bytes: B) -> Result<Self> {
+        Ok(PublicKeyShare(PublicKey::from_bytes(bytes)?))
+    }
+}
+
+/// A signature in `G1`.
+// Note: Random signatures can be generated for testing.
+#[derive(Deserialize, Serialize)]
+#[serde(bound = "")]
+pub struct Signature<E: Engine>(#[serde(with = "serde_impl::projective")] pub(crate) E::G1);
+
+impl<E: Engine> Clone for Signature<E> {
+    fn clone(&self) -> Self {
+        Signature(self.0)
+    }
+}
+
+impl<E: Engine> PartialEq for Signature<E> {
+    fn eq(&self, other: &Self) -> bool {
+        self.0 == other.0
+    }
+}
+
+impl<E: Engine> Eq for Signature<E> {}
+
+impl<E: Engine> Rand for Signature<E> {
+    fn rand<R: Rng>(rng: &mut R) -> Self {
+        Signature(rng.gen())
+    }
+}
+
+impl<E: Engine> fmt::Debug for Signature<E> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let uncomp = self.0.into_affine().into_uncompressed();
+        write!(f, "Signature({:0.10})", HexFmt(uncomp))
+    }
+}
+
+impl<E: Engine> Hash for Signature<E> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.into_affine().into_compressed().as_ref().hash(state);
+    }
+}
+
+impl<E: Engine> Signature<E> {
+    /// Returns the sum of the given signatures.
+    pub fn aggregate<I, B>(sigs: I) -> Signature<E>
+    where
+        I: IntoIterator<Item = B>,
+        B: Borrow<Signature<E>>,
+    {
+        let mut sum = E::G1::zero();
+        for sig in sigs {
+            sum.add_assign(&sig.borrow().0);
+        }
+        Signature(sum)
+    }
+
+    /// Returns a byte string representation of the signature.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        point_to_bytes(&self.0)
+    }
+
+    /// Decodes a signature from its byte string representation.
+    ///
+    /// Returns an error if the length is wrong, or if the bytes don't encode a point in the
+    /// prime order subgroup.
+    pub fn from_bytes<B: AsRef<[u8]>>(bytes: B) -> Result<Self> {
+        Ok(Signature(point_from_bytes(bytes.as_ref(), true)?))
+    }
+}
+
+/// A signature share in `G1`.
+// Note: Random signature shares can be generated for testing.
+#[derive(Deserialize, Serialize)]
+#[serde(bound = "")]
+pub struct SignatureShare<E: Engine>(pub Signature<E>);
+
+impl<E: Engine> Clone for SignatureShare<E> {
+    fn clone(&self) -> Self {
+        SignatureShare(self.0.clone())
+    }
+}
+
+impl<E: Engine> PartialEq for SignatureShare<E> {
+    fn eq(&self, other: &Self) -> bool {
+        self.0 == other.0
+    }
+}
+
+impl<E: Engine> Eq for SignatureShare<E> {}
+
+impl<E: Engine> Rand for SignatureShare<E> {
+    fn rand<R: Rng>(rng: &mut R) -> Self {
+        SignatureShare(rng.gen())
+    }
+}
+
+impl<E: Engine> Hash for SignatureShare<E> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.hash(state);
+    }
+}
+
+impl<E: Engine> fmt::Debug for SignatureShare<E> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let uncomp = (self.0).0.into_affine().into_uncompressed();
+        write!(f, "SignatureShare({:0.10})", HexFmt(uncomp))
+    }
+}
+
+impl<E: Engine> SignatureShare<E> {
+    /// Returns a byte string representation of the signature share.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.0.to_bytes()
+    }
+
+    /// Decodes a signature share from its byte string representation.
+    ///
+    /// Returns an error if the length is wrong, or if the bytes don't encode a point in the
+    /// prime order subgroup.
+    pub fn from_bytes<B: AsRef<[u8]>>(bytes: B) -> Result<Self> {
+        Ok(SignatureShare(Signature::from_bytes(bytes)?))
+    }
+}
+
+/// A secret key that signs in `G1`. Its public key is in `G2`.
+pub struct SecretKey<E: Engine>(pub(crate) generic::SecretKey<E>);
+
+impl<E: Engine> Clone for SecretKey<E> {
+    fn clone(&self) -> Self {
+        SecretKey(self.0.clone())
+    }
+}
+
+impl<E: Engine> PartialEq for SecretKey<E> {
+    fn eq(&self, other: &Self) -> bool {
+        self.0 == other.0
+    }
+}
+
+impl<E: Engine> Eq for SecretKey<E> {}
+
+impl<E: Engine> Default for SecretKey<E> {
+    fn default() -> Self {
+        SecretKey(generic::SecretKey::default())
+    }
+}
+
+impl<E: Engine> Rand for SecretKey<E> {
+    fn rand<R: Rng>(rng: &mut R) -> Self {
+        SecretKey(rng.gen())
+    }
+}
+
+/// A debug statement where the secret prime field element is redacted.
+impl<E: Engine> fmt::Debug for SecretKey<E> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("SecretKey").field(&"...").finish()
+    }
+}
+
+impl<E: Engine> From<generic::SecretKey<E>> for SecretKey<E> {
+    fn from(sk: generic::SecretKey<E>) -> SecretKey<E> {
+        SecretKey(sk)
+    }
+}
+
+impl<E: Engine> From<SecretKey<E>> for generic::SecretKey<E> {
+    fn from(sk: SecretKey<E>) -> generic::SecretKey<E> {
+        sk.0
+    }
+}
+
+impl<E: Engine> SecretKey<E> {
+    /// Creates a new random instance of `SecretKey`. See `generic::SecretKey::random`.
+    pub fn random() -> Self {
+        SecretKey(generic::SecretKey::random())
+    }
+
+    /// Returns the matching public key.
+    pub fn public_key(&self) -> PublicKey<E> {
+        PublicKey(E::G2Affine::one().mul(*(self.0).0))
+    }
+
+    /// Signs the given element of `G1`.
+    pub fn sign_g1<H: Into<E::G1Affine>>(&self, hash: H) -> Signature<E> {
+        Signature(hash.into().mul(*(self.0).0))
+    }
+
+    /// Signs the given message.
+    ///
+    /// This is equivalent to `sign_g1(hash_g1(msg))`.
+    pub fn sign<M: AsRef<[u8]>>(&self, msg: M) -> Signature<E> {
+        self.sign_g1(hash_g1::<E, M>(msg))
+    }
+
+    /// Generates a non-redacted debug string. This method differs from
+    /// the `Debug` implementation in that it *does* leak the secret prime
+    /// field element.
+    pub fn reveal(&self) -> String {
+        let uncomp = self.public_key().0.into_affine().into_uncompressed();
+        format!("SecretKey({:0.10})", HexFmt(uncomp))
+    }
+}
+
+/// A secret key share that signs in `G1`. Its public key share is in `G2`.
+pub struct SecretKeyShare<E: Engine>(pub(crate) SecretKey<E>);
+
+impl<E: Engine> Clone for SecretKeyShare<E> {
+    fn clone(&self) -> Self {
+        SecretKeyShare(self.0.clone())
+    }
+}
+
+impl<E: Engine> PartialEq for SecretKeyShare<E> {
+    fn eq(&self, other: &Self) -> bool {
+        self.0 == other.0
+    }
+}
+
+impl<E: Engine> Eq for SecretKeyShare<E> {}
+
+impl<E: Engine> Rand for SecretKeyShare<E> {
+    fn rand<R: Rng>(rng: &mut R) -> Self {
+        SecretKeyShare(rng.gen())
+    }
+}
+
+impl<E: Engine> Default for SecretKeyShare<E> {
+    fn default() -> Self {
+        SecretKeyShare(SecretKey::default())
+    }
+}
+
+impl<E: Engine> fmt::Debug for SecretKeyShare<E> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("SecretKeyShare").field(&"...").finish()
+    }
+}
+
+impl<E: Engine> From<generic::SecretKeyShare<E>> for SecretKeyShare<E> {
+    fn from(sk_share: generic::SecretKeyShare<E>) -> SecretKeyShare<E> {
+        SecretKeyShare(SecretKey(sk_share.0))
+    }
+}
+
+impl<E: Engine> From<SecretKeyShare<E>> for generic::SecretKeyShare<E> {
+    fn from(sk_share: SecretKeyShare<E>) -> generic::SecretKeyShare<E> {
+        generic::SecretKeyShare((sk_share.0).0)
+    }
+}
+
+impl<E: Engine> SecretKeyShare<E> {
+    /// Returns the matching public key share.
+    pub fn public_key_share(&self) -> PublicKeyShare<E> {
+        PublicKeyShare(self.0.public_key())
+    }
+
+    /// Signs the given element of `G1`.
+    pub fn sign_g1<H: Into<E::G1Affine>>(&self, hash: H) -> SignatureShare<E> {
+        SignatureShare(self.0.sign_g1(hash))
+    }
+
+    /// Signs the given message.
+    pub fn sign<M: AsRef<[u8]>>(&self, msg: M) -> SignatureShare<E> {
+        SignatureShare(self.0.sign(msg))
+    }
+
+    /// Generates a non-redacted debug string. This method differs from
+    /// the `Debug` implementation in that it *does* leak the secret prime
+    /// field element.
+    pub fn reveal(&self) -> String {
+        let uncomp = self.0.public_key().0.into_affine().into_uncompressed();
+        format!("SecretKeyShare({:0.10})", HexFmt(uncomp))
+    }
+}
+
+/// A public key in `G2` and an associated set of public key shares.
+#[derive(Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct PublicKeySet<E: Engine> {
+    /// The coefficients of a polynomial whose value at `0` is the "master key", and value at
+    /// `i + 1` is key share number `i`.
+    #[serde(with = "serde_impl::projective_vec")]
+    pub(crate) coeff: Vec<E::G2>,
+}
+
+impl<E: Engine> Clone for PublicKeySet<E> {
+    fn clone(&self) -> Self {
+        PublicKeySet {
+            coeff: self.coeff.clone(),
+        }
+    }
+}
+
+impl<E: Engine> PartialEq for PublicKeySet<E> {
+    fn eq(&self, other: &Self) -> bool {
+        self.coeff == other.coeff
+    }
+}
+
+impl<E: Engine> Eq for PublicKeySet<E> {}
+
+impl<E: Engine> Hash for PublicKeySet<E> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.coeff.len().hash(state);
+        for c in &self.coeff {
+            c.into_affine().into_compressed().as_ref().hash(state);
+        }
+    }
+}
+
+impl<E: Engine> fmt::Debug for PublicKeySet<E> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("PublicKeySet")
+            .field("coeff", &self.coeff)
+            .finish()
+    }
+}
+
+impl<E: Engine> PublicKeySet<E> {
+    /// Returns the threshold `t`: any set of `t + 1` signature shares can be combined into a full
+    /// signature.
+    pub fn threshold(&self) -> usize {
+        self.coeff.len().saturating_sub(1)
+    }
+
+    /// Returns the public key.
+    pub fn public_key(&self) -> PublicKey<E> {
+        PublicKey(self.coeff[0])
+    }
+
+    /// Returns the `i`-th public key share.
+    pub fn public_key_share<T: IntoFr<E::Fr>>(&self, i: T) -> PublicKeyShare<E> {
+        let value = evaluate_g1(&self.coeff, into_fr_plus_1(i));
+        PublicKeyShare(PublicKey(value))
+    }
+
+    /// Returns a byte string representation of the public key set: the concatenated coefficients,
+    /// starting with the public key.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.coeff.iter().flat_map(point_to_bytes).collect()
+    }
+
+    /// Decodes a public key set from its byte string representation.
+    ///
+    /// Returns an error if the length is not a positive multiple of the size of a public key, if
+    /// any coefficient is not a point in the prime order subgroup, or if the public key is the
+    /// identity element.
+    pub fn from_bytes<B: AsRef<[u8]>>(bytes: B) -> Result<Self> {
+        let bytes = bytes.as_ref();
+        let pk_len = <E::G2Affine as CurveAffine>::Compressed::size();
+        if bytes.is_empty() || bytes.len() % pk_len != 0 {
+            return Err(Error::InvalidLength {
+                expected: (bytes.len() / pk_len + 1) * pk_len,
+                actual: bytes.len(),
+            });
+        }
+        let coeff = bytes
+            .chunks(pk_len)
+            .enumerate()
+            .map(|(i, chunk)| point_from_bytes(chunk, i != 0))
+            .collect::<Result<_>>()?;
+        Ok(PublicKeySet { coeff })
+    }
+
+    /// Combines the shares into a signature that can be verified with the main public key.
+    pub fn combine_signatures<'a, T, I>(&self, shares: I) -> Result<Signature<E>>
+    where
+        I: IntoIterator<Item = (T, &'a SignatureShare<E>)>,
+        T: IntoFr<E::Fr>,
+    {
+        let samples = shares.into_iter().map(|(i, share)| (i, &(share.0).0));
+        Ok(Signature(interpolate(self.threshold(), samples)?))
+    }
+
+    /// Verifies the shares and combines the valid ones into a signature of `msg`. Returns the
+    /// signature and the indices of the invalid shares that were found.
+    ///
+    /// The shares are verified one by one, until `threshold() + 1` valid ones have been found.
+    /// If the shares run out before that, it returns `Error::InvalidShares`, or
+    /// `Error::NotEnoughShares` if all were valid.
+    pub fn combine_signatures_verified<'a, T, I, M>(
+        &self,
+        shares: I,
+        msg: M,
+    ) -> Result<(Signature<E>, Vec<T>)>
+    where
+        I: IntoIterator<Item = (T, &'a SignatureShare<E>)>,
+        T: IntoFr<E::Fr>,
+        M: AsRef<[u8]>,
+    {
+        self.combine_signatures_verified_g1(shares, hash_g1::<E, M>(msg))
+    }
+
+    /// Verifies the shares of the given element of `G1` and combines the valid ones into a
+    /// signature. Returns the signature and the indices of the invalid shares.
+    pub(crate) fn combine_signatures_verified_g1<'a, T, I>(
+        &self,
+        shares: I,
+        hash: E::G1,
+    ) -> Result<(Signature<E>, Vec<T>)>
+    where
+        I: IntoIterator<Item = (T, &'a SignatureShare<E>)>,
+        T: IntoFr<E::Fr>,
+    {
+        let needed = self.threshold() + 1;
+        let mut valid = Vec::with_capacity(needed);
+        let mut faulty = Vec::new();
+        for (i, share) in shares {
+            if valid.len() == needed {
+                break;
+            }
+            if self.public_key_share(i).verify_g1(share, hash) {
+                valid.push((i, share));
+            } else {
+                faulty.push(i);
+            }
+        }
+        if valid.len() < needed {
+            return Err(not_enough_valid_shares::<E::Fr, _>(
+                valid.len(),
+                &faulty,
+                needed,
+            ));
+        }
+        let sig = self.combine_signatures(valid)?;
+        Ok((sig, faulty))
+    }
+}
+
+/// A secret key and an associated set of secret key shares, that sign in `G1`.
+pub struct SecretKeySet<E: Engine>(generic::SecretKeySet<E>);
+
+impl<E: Engine> From<generic::SecretKeySet<E>> for SecretKeySet<E> {
+    fn from(sk_set: generic::SecretKeySet<E>) -> SecretKeySet<E> {
+        SecretKeySet(sk_set)
+    }
+}
+
+impl<E: Engine> From<Poly<E>> for SecretKeySet<E> {
+    fn from(poly: Poly<E>) -> SecretKeySet<E> {
+        SecretKeySet(generic::SecretKeySet::from(poly))
+    }
+}
+
+impl<E: Engine> SecretKeySet<E> {
+    /// Creates a set of secret key shares, where any `threshold + 1` of them can collaboratively
+    /// sign.
+    ///
+    /// # Panic
+    ///
+    /// Panics if the `threshold` is too large for the coefficients to fit into a `Vec`.
+    pub fn random<R: Rng>(threshold: usize, rng: &mut R) -> Self {
+        SecretKeySet(generic::SecretKeySet::random(threshold, rng))
+    }
+
+    /// Creates a set of secret key shares, where any `threshold + 1` of them can collaboratively
+    /// sign. Returns an `Err` where `random` would panic.
+    pub fn try_random<R: Rng>(threshold: usize, rng: &mut R) -> Result<Self> {
+        generic::SecretKeySet::try_random(threshold, rng).map(SecretKeySet)
+    }
+
+    /// Returns the threshold `t`: any set of `t + 1` signature shares can be combined into a full
+    /// signature.
+    pub fn threshold(&self) -> usize {
+        self.0.threshold()
+    }
+
+    /// Returns the `i`-th secret key share.
+    pub fn secret_key_share<T: IntoFr<E::Fr>>(&self, i: T) -> SecretKeyShare<E> {
+        SecretKeyShare::from(self.0.secret_key_share(i))
+    }
+
+    /// Returns the corresponding public key set. That information can be shared publicly.
+    pub fn public_keys(&self) -> PublicKeySet<E> {
+        let to_g2 = |c: &E::Fr| E::G2Affine::one().mul(*c);
+        PublicKeySet {
+            coeff: self.0.poly.coeff.iter().map(to_g2).collect(),
+        }
+    }
+
+    /// Returns the secret master key.
+    #[cfg(test)]
+    pub(crate) fn secret_key(&self) -> SecretKey<E> {
+        SecretKey(self.0.secret_key())
+    }
+}
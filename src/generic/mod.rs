@@ -0,0 +1,1608 @@
+//! Keys, signatures and ciphertexts that are generic over the pairing engine.
+//!
+//! The types at the crate root are aliases for these types with the engine `PEngine`, i.e.
+//! BLS12-381. Use the generic types directly to work with keys on a different curve, e.g.
+//! `PublicKey<Bn256>`, for any implementation of `pairing::Engine`. Keys on different curves can
+//! be used side by side in the same binary.
+//!
+//! The hash-to-curve functions `hash_to_g1` and `hash_to_g2` are only specified for BLS12-381, so
+//! the methods that depend on them, i.e. the ones that take a domain separation tag, proofs of
+//! possession and EIP-2333 key derivation, are only available for the aliased types. For other
+//! engines, messages are hashed with `hash_g2`.
+//!
+//! The `min_sig` module contains a variant with public keys in `G2` and signatures in `G1`.
+
+pub mod min_sig;
+pub mod poly;
+
+use std::borrow::Borrow;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::iter;
+use std::mem::size_of;
+use std::ptr::copy_nonoverlapping;
+
+use byteorder::{BigEndian, ByteOrder};
+use hex_fmt::HexFmt;
+use pairing::{
+    CurveAffine, CurveProjective, EncodedPoint, Engine, Field, PrimeField, PrimeFieldRepr,
+};
+use rand::{self, OsRng, Rand, Rng};
+use tiny_keccak::sha3_256;
+
+use self::poly::{Commitment, Poly};
+use error::{Error, Result};
+use into_fr::IntoFr;
+use multiexp::multiexp;
+use secret::{clear_copy, clear_fr, fr_bytes, ContainsSecret, MemRange};
+use {rng_from_hash, serde_impl, SecretBytes};
+
+const ERR_OS_RNG: &str = "could not initialize the OS random number generator";
+
+/// The tag that precedes a non-empty label in the input of `hash_g1_g2`, padded with zeros.
+const LABEL_TAG: &[u8] = b"threshold_crypto ciphertext label";
+
+/// The length of the padded `LABEL_TAG`.
+const LABEL_TAG_LEN: usize = 64;
+
+/// A public key.
+#[derive(Deserialize, Serialize)]
+#[serde(bound = "")]
+pub struct PublicKey<E: Engine>(#[serde(with = "serde_impl::projective")] pub(crate) E::G1);
+
+impl<E: Engine> Clone for PublicKey<E> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<E: Engine> Copy for PublicKey<E> {}
+
+impl<E: Engine> PartialEq for PublicKey<E> {
+    fn eq(&self, other: &Self) -> bool {
+        self.0 == other.0
+    }
+}
+
+impl<E: Engine> Eq for PublicKey<E> {}
+
+impl<E: Engine> Hash for PublicKey<E> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.into_affine().into_compressed().as_ref().hash(state);
+    }
+}
+
+impl<E: Engine> fmt::Debug for PublicKey<E> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let uncomp = self.0.into_affine().into_uncompressed();
+        write!(f, "PublicKey({:0.10})", HexFmt(uncomp))
+    }
+}
+
+impl<E: Engine> PublicKey<E> {
+    /// Returns `true` if the signature matches the element of `G2`.
+    ///
+    /// To verify signatures made by other BLS implementations, use `hash_to_g2` to hash the
+    /// message with the same domain separation tag as the signer.
+    pub fn verify_g2<H: Into<E::G2Affine>>(&self, sig: &Signature<E>, hash: H) -> bool {
+        E::pairing(self.0, hash) == E::pairing(E::G1Affine::one(), sig.0)
+    }
+
+    /// Returns `true` if the signature matches the message.
+    ///
+    /// This is equivalent to `verify_g2(sig, hash_g2(msg))`.
+    pub fn verify<M: AsRef<[u8]>>(&self, sig: &Signature<E>, msg: M) -> bool {
+        self.verify_g2(sig, hash_g2::<E, M>(msg))
+    }
+
+    /// Encrypts the message using the OS random number generator.
+    ///
+    /// Uses the `OsRng` by default. To pass in a custom random number generator, use
+    /// `encrypt_with_rng()`.
+    pub fn encrypt<M: AsRef<[u8]>>(&self, msg: M) -> Ciphertext<E> {
+        self.encrypt_with_rng(&mut OsRng::new().expect(ERR_OS_RNG), msg)
+    }
+
+    /// Encrypts the message.
+    pub fn encrypt_with_rng<R: Rng, M: AsRef<[u8]>>(&self, rng: &mut R, msg: M) -> Ciphertext<E> {
+        self.encrypt_with_label_and_rng(rng, msg, b"")
+    }
+
+    /// Encrypts the message, binding the ciphertext to the given label, using the OS random
+    /// number generator.
+    ///
+    /// The label is not encrypted, but any modification of it invalidates the ciphertext. It can
+    /// be used to tie the ciphertext to a particular context, e.g. a protocol message type, so that
+    /// it can't be replayed in a different one: The recipient needs to check `Ciphertext::label`.
+    pub fn encrypt_with_label<M, L>(&self, msg: M, label: L) -> Ciphertext<E>
+    where
+        M: AsRef<[u8]>,
+        L: AsRef<[u8]>,
+    {
+        let mut rng = OsRng::new().expect(ERR_OS_RNG);
+        self.encrypt_with_label_and_rng(&mut rng, msg, label)
+    }
+
+    /// Encrypts the message, binding the ciphertext to the given label.
+    pub fn encrypt_with_label_and_rng<R, M, L>(
+        &self,
+        rng: &mut R,
+        msg: M,
+        label: L,
+    ) -> Ciphertext<E>
+    where
+        R: Rng,
+        M: AsRef<[u8]>,
+        L: AsRef<[u8]>,
+    {
+        let r: E::Fr = rng.gen();
+        let u = E::G1Affine::one().mul(r);
+        let v: Vec<u8> = {
+            let g = self.0.into_affine().mul(r);
+            xor_with_hash(g, msg.as_ref())
+        };
+        let label = label.as_ref().to_vec();
+        let w = hash_g1_g2::<E, _, _>(u, &v, &label).into_affine().mul(r);
+        Ciphertext(u, v, w, label)
+    }
+
+    /// Returns a byte string representation of the public key.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        point_to_bytes(&self.0)
+    }
+
+    /// Decodes a public key from its byte string representation.
+    ///
+    /// Returns an error if the length is wrong, if the bytes don't encode a point in the prime
+    /// order subgroup, or if they encode the identity element.
+    pub fn from_bytes<B: AsRef<[u8]>>(bytes: B) -> Result<Self> {
+        Ok(PublicKey(point_from_bytes(bytes.as_ref(), false)?))
+    }
+
+    /// Verifies a batch of signatures, each with its own public key and message. Returns
+    /// `Error::InvalidSignatures` with the indices of the invalid entries, if there are any.
+    ///
+    /// The signatures are multiplied with random coefficients and checked together, which takes
+    /// one pairing per distinct message, plus one, instead of two per signature. Only if that
+    /// fails, the batch is split up recursively to find the invalid signatures.
+    pub fn verify_batch<M: AsRef<[u8]>>(items: &[(PublicKey<E>, Signature<E>, M)]) -> Result<()> {
+        let items = items.iter().map(|(pk, sig, msg)| (pk.0, sig.0, msg));
+        verify_batch::<E, _, _, _>(items, |msg| hash_g2::<E, _>(msg))
+    }
+
+    /// Returns the sum of the given public keys.
+    ///
+    /// A signature on a message created by each of the corresponding secret keys, combined with
+    /// `Signature::aggregate`, can be verified with the aggregated public key. This is vulnerable
+    /// to rogue key attacks, unless each key's `ProofOfPossession` has been verified.
+    pub fn aggregate<I, B>(pub_keys: I) -> PublicKey<E>
+    where
+        I: IntoIterator<Item = B>,
+        B: Borrow<PublicKey<E>>,
+    {
+        let mut sum = E::G1::zero();
+        for pk in pub_keys {
+            sum.add_assign(&pk.borrow().0);
+        }
+        PublicKey(sum)
+    }
+}
+
+/// A public key share.
+#[derive(Deserialize, Serialize)]
+#[serde(bound = "")]
+pub struct PublicKeyShare<E: Engine>(pub(crate) PublicKey<E>);
+
+impl<E: Engine> Clone for PublicKeyShare<E> {
+    fn clone(&self) -> Self {
+        PublicKeyShare(self.0)
+    }
+}
+
+impl<E: Engine> PartialEq for PublicKeyShare<E> {
+    fn eq(&self, other: &Self) -> bool {
+        self.0 == other.0
+    }
+}
+
+impl<E: Engine> Eq for PublicKeyShare<E> {}
+
+impl<E: Engine> Hash for PublicKeyShare<E> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.hash(state);
+    }
+}
+
+impl<E: Engine> fmt::Debug for PublicKeyShare<E> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let uncomp = (self.0).0.into_affine().into_uncompressed();
+        write!(f, "PublicKeyShare({:0.10})", HexFmt(uncomp))
+    }
+}
+
+impl<E: Engine> PublicKeyShare<E> {
+    /// Returns `true` if the signature matches the element of `G2`.
+    pub fn verify_g2<H: Into<E::G2Affine>>(&self, sig: &SignatureShare<E>, hash: H) -> bool {
+        self.0.verify_g2(&sig.0, hash)
+    }
+
+    /// Returns `true` if the signature matches the message.
+    ///
+    /// This is equivalent to `verify_g2(sig, hash_g2(msg))`.
+    pub fn verify<M: AsRef<[u8]>>(&self, sig: &SignatureShare<E>, msg: M) -> bool {
+        self.verify_g2(sig, hash_g2::<E, M>(msg))
+    }
+
+    /// Verifies a batch of signature shares, each with its own public key share and message.
+    /// Returns `Error::InvalidSignatures` with the indices of the invalid entries, if there are
+    /// any.
+    ///
+    /// This is much faster than verifying the shares one by one, in particular if they are all
+    /// signatures of the same message: Then it only takes two pairings in total.
+    pub fn verify_batch<M: AsRef<[u8]>>(
+        items: &[(PublicKeyShare<E>, SignatureShare<E>, M)],
+    ) -> Result<()> {
+        let items = items
+            .iter()
+            .map(|(pk, sig, msg)| ((pk.0).0, (sig.0).0, msg));
+        verify_batch::<E, _, _, _>(items, |msg| hash_g2::<E, _>(msg))
+    }
+
+    /// Returns `true` if the decryption share matches the ciphertext, including its label.
+    pub fn verify_decryption_share(&self, share: &DecryptionShare<E>, ct: &Ciphertext<E>) -> bool {
+        let Ciphertext(ref u, ref v, ref w, ref label) = *ct;
+        let hash = hash_g1_g2::<E, _, _>(*u, v, label);
+        E::pairing(share.0, hash) == E::pairing((self.0).0, *w)
+    }
+
+    /// Returns a byte string representation of the public key share.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.0.to_bytes()
+    }
+
+    /// Decodes a public key share from its byte string representation.
+    ///
+    /// Returns an error if the length is wrong, if the bytes don't encode a point in the prime
+    /// order subgroup, or if they encode the identity element.
+    pub fn from_bytes<B: AsRef<[u8]>>(bytes: B) -> Result<Self> {
+        Ok(PublicKeyShare(PublicKey::from_bytes(bytes)?))
+    }
+}
+
+/// A signature.
+// Note: Random signatures can be generated for testing.
+#[derive(Deserialize, Serialize)]
+#[serde(bound = "")]
+pub struct Signature<E: Engine>(#[serde(with = "serde_impl::projective")] pub(crate) E::G2);
+
+impl<E: Engine> Clone for Signature<E> {
+    fn clone(&self) -> Self {
+        Signature(self.0)
+    }
+}
+
+impl<E: Engine> PartialEq for Signature<E> {
+    fn eq(&self, other: &Self) -> bool {
+        self.0 == other.0
+    }
+}
+
+impl<E: Engine> Eq for Signature<E> {}
+
+impl<E: Engine> Rand for Signature<E> {
+    fn rand<R: Rng>(rng: &mut R) -> Self {
+        Signature(rng.gen())
+    }
+}
+
+impl<E: Engine> fmt::Debug for Signature<E> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let uncomp = self.0.into_affine().into_uncompressed();
+        write!(f, "Signature({:0.10})", HexFmt(uncomp))
+    }
+}
+
+impl<E: Engine> Hash for Signature<E> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.into_affine().into_compressed().as_ref().hash(state);
+    }
+}
+
+impl<E: Engine> Signature<E> {
+    /// Returns the sum of the given signatures.
+    ///
+    /// To verify signatures by different keys on different messages, wrap the result in an
+    /// `AggregateSignature`.
+    pub fn aggregate<I, B>(sigs: I) -> Signature<E>
+    where
+        I: IntoIterator<Item = B>,
+        B: Borrow<Signature<E>>,
+    {
+        let mut sum = E::G2::zero();
+        for sig in sigs {
+            sum.add_assign(&sig.borrow().0);
+        }
+        Signature(sum)
+    }
+
+    /// Returns a byte string representation of the signature.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        point_to_bytes(&self.0)
+    }
+
+    /// Decodes a signature from its byte string representation.
+    ///
+    /// Returns an error if the length is wrong, or if the bytes don't encode a point in the
+    /// prime order subgroup.
+    pub fn from_bytes<B: AsRef<[u8]>>(bytes: B) -> Result<Self> {
+        Ok(Signature(point_from_bytes(bytes.as_ref(), true)?))
+    }
+
+    pub fn parity(&self) -> bool {
+        let uncomp = self.0.into_affine().into_uncompressed();
+        let xor_bytes: u8 = uncomp.as_ref().iter().fold(0, |result, byte| result ^ byte);
+        let parity = 0 != xor_bytes.count_ones() % 2;
+        debug!("Signature: {:0.10}, parity: {}", HexFmt(uncomp), parity);
+        parity
+    }
+}
+
+/// A signature share.
+// Note: Random signature shares can be generated for testing.
+#[derive(Deserialize, Serialize)]
+#[serde(bound = "")]
+pub struct SignatureShare<E: Engine>(pub Signature<E>);
+
+impl<E: Engine> Clone for SignatureShare<E> {
+    fn clone(&self) -> Self {
+        SignatureShare(self.0.clone())
+    }
+}
+
+impl<E: Engine> PartialEq for SignatureShare<E> {
+    fn eq(&self, other: &Self) -> bool {
+        self.0 == other.0
+    }
+}
+
+impl<E: Engine> Eq for SignatureShare<E> {}
+
+impl<E: Engine> Rand for SignatureShare<E> {
+    fn rand<R: Rng>(rng: &mut R) -> Self {
+        SignatureShare(rng.gen())
+    }
+}
+
+impl<E: Engine> Hash for SignatureShare<E> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.hash(state);
+    }
+}
+
+impl<E: Engine> fmt::Debug for SignatureShare<E> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let uncomp = (self.0).0.into_affine().into_uncompressed();
+        write!(f, "SignatureShare({:0.10})", HexFmt(uncomp))
+    }
+}
+
+impl<E: Engine> SignatureShare<E> {
+    /// Returns a byte string representation of the signature share.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.0.to_bytes()
+    }
+
+    /// Decodes a signature share from its byte string representation.
+    ///
+    /// Returns an error if the length is wrong, or if the bytes don't encode a point in the
+    /// prime order subgroup.
+    pub fn from_bytes<B: AsRef<[u8]>>(bytes: B) -> Result<Self> {
+        Ok(SignatureShare(Signature::from_bytes(bytes)?))
+    }
+}
+
+/// An aggregate of signatures by different keys, on distinct messages.
+///
+/// It has the size of a single signature, and verifying it takes `n + 1` pairings instead of the
+/// `2 * n` needed to verify the `n` individual signatures.
+#[derive(Deserialize, Serialize)]
+#[serde(bound = "")]
+pub struct AggregateSignature<E: Engine>(Signature<E>);
+
+impl<E: Engine> Clone for AggregateSignature<E> {
+    fn clone(&self) -> Self {
+        AggregateSignature(self.0.clone())
+    }
+}
+
+impl<E: Engine> PartialEq for AggregateSignature<E> {
+    fn eq(&self, other: &Self) -> bool {
+        self.0 == other.0
+    }
+}
+
+impl<E: Engine> Eq for AggregateSignature<E> {}
+
+impl<E: Engine> Hash for AggregateSignature<E> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.hash(state);
+    }
+}
+
+impl<E: Engine> fmt::Debug for AggregateSignature<E> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("AggregateSignature").field(&self.0).finish()
+    }
+}
+
+impl<E: Engine> From<Signature<E>> for AggregateSignature<E> {
+    fn from(sig: Signature<E>) -> AggregateSignature<E> {
+        AggregateSignature(sig)
+    }
+}
+
+impl<E: Engine> AggregateSignature<E> {
+    /// Aggregates the given signatures.
+    pub fn aggregate<I, B>(sigs: I) -> AggregateSignature<E>
+    where
+        I: IntoIterator<Item = B>,
+        B: Borrow<Signature<E>>,
+    {
+        AggregateSignature(Signature::aggregate(sigs))
+    }
+
+    /// Returns the aggregated signature.
+    pub fn signature(&self) -> &Signature<E> {
+        &self.0
+    }
+
+    /// Returns a byte string representation of the aggregate signature.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.0.to_bytes()
+    }
+
+    /// Decodes an aggregate signature from its byte string representation.
+    ///
+    /// Returns an error if the length is wrong, or if the bytes don't encode a point in the
+    /// prime order subgroup.
+    pub fn from_bytes<B: AsRef<[u8]>>(bytes: B) -> Result<Self> {
+        Ok(AggregateSignature(Signature::from_bytes(bytes)?))
+    }
+
+    /// Returns `true` if the aggregate consists of a signature of each message by the
+    /// corresponding key.
+    ///
+    /// Returns `false` if `items` is empty or contains two equal messages: Without further
+    /// precautions, aggregating several signatures on the same message is insecure. See
+    /// `verify_allow_duplicates`.
+    pub fn verify<M: AsRef<[u8]>>(&self, items: &[(PublicKey<E>, M)]) -> bool {
+        !has_duplicate_messages(items) && self.verify_grouped(items, |msg| hash_g2::<E, _>(msg))
+    }
+
+    /// Returns `true` if the aggregate consists of a signature of each message by the
+    /// corresponding key. Messages may appear several times.
+    ///
+    /// *WARNING* This is only secure if the `ProofOfPossession` of every key has been verified:
+    /// Otherwise an attacker can choose their public key so that they can forge a signature on
+    /// behalf of others.
+    ///
+    /// The keys that signed the same message are added up, so that the verification only takes
+    /// one pairing per distinct message, plus one.
+    pub fn verify_allow_duplicates<M: AsRef<[u8]>>(&self, items: &[(PublicKey<E>, M)]) -> bool {
+        self.verify_grouped(items, |msg| hash_g2::<E, _>(msg))
+    }
+
+    /// Returns `true` if the aggregate consists of a signature of each message by the
+    /// corresponding key, where `hash` maps the messages to `G2`. The keys of equal messages are
+    /// added up before computing the pairings.
+    pub(crate) fn verify_grouped<M, F>(&self, items: &[(PublicKey<E>, M)], hash: F) -> bool
+    where
+        M: AsRef<[u8]>,
+        F: Fn(&[u8]) -> E::G2,
+    {
+        if items.is_empty() {
+            return false;
+        }
+        let mut keys_by_msg: BTreeMap<&[u8], E::G1> = BTreeMap::new();
+        for (pk, msg) in items {
+            let key = keys_by_msg.entry(msg.as_ref()).or_insert_with(E::G1::zero);
+            key.add_assign(&pk.0);
+        }
+        let hashed = keys_by_msg
+            .into_iter()
+            .map(|(msg, key)| (PublicKey(key), hash(msg)));
+        self.verify_g2(hashed)
+    }
+
+    /// Returns `true` if the aggregate consists of a signature of each element of `G2` by the
+    /// corresponding key.
+    fn verify_g2<I: IntoIterator<Item = (PublicKey<E>, E::G2)>>(&self, items: I) -> bool {
+        let pairs = items
+            .into_iter()
+            .map(|(pk, hash)| (pk.0.into_affine(), hash.into_affine()));
+        pairing_product::<E, _>(pairs) == E::pairing(E::G1Affine::one(), (self.0).0)
+    }
+}
+
+/// A secret key; wraps a single prime field element. The field element is
+/// heap allocated to avoid any stack copying that result when passing
+/// `SecretKey`s between stack frames.
+pub struct SecretKey<E: Engine>(pub(crate) Box<E::Fr>);
+
+impl<E: Engine> PartialEq for SecretKey<E> {
+    fn eq(&self, other: &Self) -> bool {
+        self.0 == other.0
+    }
+}
+
+impl<E: Engine> Eq for SecretKey<E> {}
+
+/// Creates a `SecretKey` containing the zero prime field element.
+impl<E: Engine> Default for SecretKey<E> {
+    fn default() -> Self {
+        let mut fr = E::Fr::zero();
+        SecretKey::from_mut(&mut fr)
+    }
+}
+
+/// Creates a random `SecretKey` from a given RNG. If you do not need to specify your own RNG, you
+/// should use `SecretKey::random()` as your constructor instead.
+impl<E: Engine> Rand for SecretKey<E> {
+    fn rand<R: Rng>(rng: &mut R) -> Self {
+        let mut fr = E::Fr::rand(rng);
+        SecretKey::from_mut(&mut fr)
+    }
+}
+
+/// Creates a new `SecretKey` by cloning another `SecretKey`'s prime field element.
+impl<E: Engine> Clone for SecretKey<E> {
+    fn clone(&self) -> Self {
+        let mut fr = *self.0;
+        SecretKey::from_mut(&mut fr)
+    }
+}
+
+/// Zeroes out the memory allocated from the `SecretKey`'s field element.
+impl<E: Engine> Drop for SecretKey<E> {
+    fn drop(&mut self) {
+        self.zero_secret();
+    }
+}
+/// A debug statement where the secret prime field element is redacted.
+impl<E: Engine> fmt::Debug for SecretKey<E> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("SecretKey").field(&"...").finish()
+    }
+}
+
+impl<E: Engine> ContainsSecret for SecretKey<E> {
+    fn secret_memory(&self) -> MemRange {
+        let ptr = &*self.0 as *const E::Fr as *mut u8;
+        let n_bytes = size_of::<E::Fr>();
+        MemRange { ptr, n_bytes }
+    }
+}
+
+impl<E: Engine> SecretKey<E> {
+    /// Creates a new `SecretKey` from a mutable reference to a field element. This constructor
+    /// takes a reference to avoid any unnecessary stack copying/moving of secrets (i.e. the field
+    /// element). The field element is copied bytewise onto the heap, the resulting `Box` is
+    /// stored in the returned `SecretKey`.
+    ///
+    /// *WARNING* this constructor will overwrite the referenced `Fr` element with zeros after it
+    /// has been copied onto the heap.
+    pub fn from_mut(fr: &mut E::Fr) -> Self {
+        let mut boxed_fr = Box::new(E::Fr::zero());
+        unsafe {
+            copy_nonoverlapping(fr as *const E::Fr, &mut *boxed_fr as *mut E::Fr, 1);
+        }
+        clear_fr(fr);
+        SecretKey(boxed_fr)
+    }
+
+    /// Creates a new random instance of `SecretKey`. If you want to use/define your own random
+    /// number generator, you should use the constructor: `SecretKey::rand()`. If you do not need
+    /// to specify your own RNG, you should use the `SecretKey::random()` constructor, which uses
+    /// [`rand::thead_rng()`](https://docs.rs/rand/0.4.3/rand/fn.thread_rng.html) internally as
+    /// its RNG.
+    pub fn random() -> Self {
+        let mut rng = rand::thread_rng();
+        SecretKey::rand(&mut rng)
+    }
+
+    /// Returns the matching public key.
+    pub fn public_key(&self) -> PublicKey<E> {
+        PublicKey(E::G1Affine::one().mul(*self.0))
+    }
+
+    /// Returns the big-endian representation of the secret key, in a buffer that is overwritten
+    /// with zeros when dropped.
+    ///
+    /// *WARNING* Anyone who learns these bytes can sign and decrypt on behalf of the key owner.
+    pub fn to_bytes_secret(&self) -> SecretBytes {
+        let mut bytes = SecretBytes(Vec::with_capacity(fr_bytes::<E::Fr>()));
+        let mut repr = self.0.into_repr();
+        repr.write_be(&mut bytes.0)
+            .expect("writing to a vector never fails");
+        clear_copy(&mut repr);
+        bytes
+    }
+
+    /// Decodes a secret key from its big-endian representation, as returned by
+    /// `to_bytes_secret`.
+    ///
+    /// Returns an error if the length is wrong, or if the bytes don't represent a field element.
+    pub fn from_bytes<B: AsRef<[u8]>>(bytes: B) -> Result<Self> {
+        let bytes = bytes.as_ref();
+        let expected = fr_bytes::<E::Fr>();
+        if bytes.len() != expected {
+            return Err(Error::InvalidLength {
+                expected,
+                actual: bytes.len(),
+            });
+        }
+        let mut repr = E::Fr::zero().into_repr();
+        let read_result = repr.read_be(bytes);
+        let fr_result = E::Fr::from_repr(repr);
+        clear_copy(&mut repr);
+        read_result.map_err(|err| Error::InvalidFieldElement(err.to_string()))?;
+        let mut fr = fr_result.map_err(|err| Error::InvalidFieldElement(err.to_string()))?;
+        Ok(SecretKey::from_mut(&mut fr))
+    }
+
+    /// Signs the given element of `G2`.
+    ///
+    /// To create signatures that can be verified by other BLS implementations, use `hash_to_g2`
+    /// to hash the message, e.g. `sign_g2(hash_to_g2(msg, dst))`.
+    pub fn sign_g2<H: Into<E::G2Affine>>(&self, hash: H) -> Signature<E> {
+        Signature(hash.into().mul(*self.0))
+    }
+
+    /// Signs the given message.
+    ///
+    /// This is equivalent to `sign_g2(hash_g2(msg))`.
+    pub fn sign<M: AsRef<[u8]>>(&self, msg: M) -> Signature<E> {
+        self.sign_g2(hash_g2::<E, M>(msg))
+    }
+
+    /// Returns the decrypted text, or `None`, if the ciphertext isn't valid.
+    pub fn decrypt(&self, ct: &Ciphertext<E>) -> Option<Vec<u8>> {
+        if !ct.verify() {
+            return None;
+        }
+        let Ciphertext(ref u, ref v, _, _) = *ct;
+        let g = u.into_affine().mul(*self.0);
+        Some(xor_with_hash(g, v))
+    }
+
+    /// Generates a non-redacted debug string. This method differs from
+    /// the `Debug` implementation in that it *does* leak the secret prime
+    /// field element.
+    pub fn reveal(&self) -> String {
+        let uncomp = self.public_key().0.into_affine().into_uncompressed();
+        format!("SecretKey({:0.10})", HexFmt(uncomp))
+    }
+}
+
+/// A secret key share.
+pub struct SecretKeyShare<E: Engine>(pub(crate) SecretKey<E>);
+
+impl<E: Engine> Clone for SecretKeyShare<E> {
+    fn clone(&self) -> Self {
+        SecretKeyShare(self.0.clone())
+    }
+}
+
+impl<E: Engine> PartialEq for SecretKeyShare<E> {
+    fn eq(&self, other: &Self) -> bool {
+        self.0 == other.0
+    }
+}
+
+impl<E: Engine> Eq for SecretKeyShare<E> {}
+
+impl<E: Engine> Rand for SecretKeyShare<E> {
+    fn rand<R: Rng>(rng: &mut R) -> Self {
+        SecretKeyShare(rng.gen())
+    }
+}
+
+impl<E: Engine> Default for SecretKeyShare<E> {
+    fn default() -> Self {
+        SecretKeyShare(SecretKey::default())
+    }
+}
+
+impl<E: Engine> fmt::Debug for SecretKeyShare<E> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("SecretKeyShare").field(&"...").finish()
+    }
+}
+
+impl<E: Engine> SecretKeyShare<E> {
+    /// Creates a new `SecretKeyShare` from a mutable reference to a field element. This
+    /// constructor takes a reference to avoid any unnecessary stack copying/moving of secrets
+    /// field elements. The field element will be copied bytewise onto the heap, the resulting
+    /// `Box` is stored in the `SecretKey` which is then wrapped in a `SecretKeyShare`.
+    ///
+    /// *WARNING* this constructor will overwrite the pointed to `Fr` element with zeros once it
+    /// has been copied into a new `SecretKeyShare`.
+    pub fn from_mut(fr: &mut E::Fr) -> Self {
+        SecretKeyShare(SecretKey::from_mut(fr))
+    }
+
+    /// Returns the matching public key share.
+    pub fn public_key_share(&self) -> PublicKeyShare<E> {
+        PublicKeyShare(self.0.public_key())
+    }
+
+    /// Returns the big-endian representation of the secret key share, in a buffer that is
+    /// overwritten with zeros when dropped.
+    ///
+    /// *WARNING* Anyone who learns these bytes can create signature and decryption shares on
+    /// behalf of the share owner.
+    pub fn to_bytes_secret(&self) -> SecretBytes {
+        self.0.to_bytes_secret()
+    }
+
+    /// Decodes a secret key share from its big-endian representation, as returned by
+    /// `to_bytes_secret`.
+    ///
+    /// Returns an error if the length is wrong, or if the bytes don't represent a field element.
+    pub fn from_bytes<B: AsRef<[u8]>>(bytes: B) -> Result<Self> {
+        Ok(SecretKeyShare(SecretKey::from_bytes(bytes)?))
+    }
+
+    /// Signs the given element of `G2`.
+    pub fn sign_g2<H: Into<E::G2Affine>>(&self, hash: H) -> SignatureShare<E> {
+        SignatureShare(self.0.sign_g2(hash))
+    }
+
+    /// Signs the given message.
+    pub fn sign<M: AsRef<[u8]>>(&self, msg: M) -> SignatureShare<E> {
+        SignatureShare(self.0.sign(msg))
+    }
+
+    /// Returns a decryption share, or `None`, if the ciphertext isn't valid.
+    pub fn decrypt_share(&self, ct: &Ciphertext<E>) -> Option<DecryptionShare<E>> {
+        if !ct.verify() {
+            return None;
+        }
+        Some(self.decrypt_share_no_verify(ct))
+    }
+
+    /// Returns a decryption share, without validating the ciphertext.
+    pub fn decrypt_share_no_verify(&self, ct: &Ciphertext<E>) -> DecryptionShare<E> {
+        DecryptionShare(ct.0.into_affine().mul(*(self.0).0))
+    }
+
+    /// Generates a non-redacted debug string. This method differs from
+    /// the `Debug` implementation in that it *does* leak the secret prime
+    /// field element.
+    pub fn reveal(&self) -> String {
+        let uncomp = self.0.public_key().0.into_affine().into_uncompressed();
+        format!("SecretKeyShare({:0.10})", HexFmt(uncomp))
+    }
+}
+
+/// An encrypted message, together with an unencrypted label that is bound to it.
+///
+/// The label is part of the serialized ciphertext, even if it is empty. Serialized ciphertexts
+/// from versions without labels can therefore not be deserialized, and vice versa.
+#[derive(Deserialize, Serialize)]
+#[serde(bound = "")]
+pub struct Ciphertext<E: Engine>(
+    #[serde(with = "serde_impl::projective")] pub(crate) E::G1,
+    pub(crate) Vec<u8>,
+    #[serde(with = "serde_impl::projective")] pub(crate) E::G2,
+    pub(crate) Vec<u8>,
+);
+
+impl<E: Engine> Clone for Ciphertext<E> {
+    fn clone(&self) -> Self {
+        Ciphertext(self.0, self.1.clone(), self.2, self.3.clone())
+    }
+}
+
+impl<E: Engine> PartialEq for Ciphertext<E> {
+    fn eq(&self, other: &Self) -> bool {
+        self.0 == other.0 && self.1 == other.1 && self.2 == other.2 && self.3 == other.3
+    }
+}
+
+impl<E: Engine> Eq for Ciphertext<E> {}
+
+impl<E: Engine> Hash for Ciphertext<E> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        let Ciphertext(ref u, ref v, ref w, ref label) = *self;
+        u.into_affine().into_compressed().as_ref().hash(state);
+        v.hash(state);
+        w.into_affine().into_compressed().as_ref().hash(state);
+        label.hash(state);
+    }
+}
+
+impl<E: Engine> fmt::Debug for Ciphertext<E> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("Ciphertext")
+            .field(&self.0)
+            .field(&self.1)
+            .field(&self.2)
+            .field(&self.3)
+            .finish()
+    }
+}
+
+impl<E: Engine> Ciphertext<E> {
+    /// Returns `true` if this is a valid ciphertext. This check is necessary to prevent
+    /// chosen-ciphertext attacks.
+    pub fn verify(&self) -> bool {
+        let Ciphertext(ref u, ref v, ref w, ref label) = *self;
+        let hash = hash_g1_g2::<E, _, _>(*u, v, label);
+        E::pairing(E::G1Affine::one(), *w) == E::pairing(*u, hash)
+    }
+
+    /// Returns the label the ciphertext was created with. It is empty unless the message was
+    /// encrypted using `PublicKey::encrypt_with_label`.
+    pub fn label(&self) -> &[u8] {
+        &self.3
+    }
+
+    /// Returns a byte string representation of the ciphertext: the two group elements, the
+    /// label's length as a big-endian `u32`, the label, and the encrypted message.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the label is longer than `u32::MAX` bytes.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let Ciphertext(ref u, ref v, ref w, ref label) = *self;
+        let mut bytes = point_to_bytes(u);
+        bytes.extend(point_to_bytes(w));
+        assert!(label.len() <= u32::MAX as usize, "label too long");
+        let mut len_bytes = [0u8; 4];
+        BigEndian::write_u32(&mut len_bytes, label.len() as u32);
+        bytes.extend_from_slice(&len_bytes);
+        bytes.extend_from_slice(label);
+        bytes.extend_from_slice(v);
+        bytes
+    }
+
+    /// Decodes a ciphertext from its byte string representation.
+    ///
+    /// Returns an error if the bytes are too short, or if they don't encode two nonzero points
+    /// in the prime order subgroups. This does not check whether the ciphertext is valid: Use
+    /// `verify` for that.
+    pub fn from_bytes<B: AsRef<[u8]>>(bytes: B) -> Result<Self> {
+        let bytes = bytes.as_ref();
+        let u_len = <E::G1Affine as CurveAffine>::Compressed::size();
+        let w_len = <E::G2Affine as CurveAffine>::Compressed::size();
+        let header_len = u_len + w_len + 4;
+        if bytes.len() < header_len {
+            return Err(Error::InvalidLength {
+                expected: header_len,
+                actual: bytes.len(),
+            });
+        }
+        let u = point_from_bytes(&bytes[..u_len], false)?;
+        let w = point_from_bytes(&bytes[u_len..(u_len + w_len)], false)?;
+        let label_len = BigEndian::read_u32(&bytes[(header_len - 4)..header_len]) as usize;
+        if bytes.len() - header_len < label_len {
+            return Err(Error::InvalidLength {
+                expected: header_len + label_len,
+                actual: bytes.len(),
+            });
+        }
+        let (label, v) = bytes[header_len..].split_at(label_len);
+        Ok(Ciphertext(u, v.to_vec(), w, label.to_vec()))
+    }
+}
+
+/// A decryption share. A threshold of decryption shares can be used to decrypt a message.
+#[derive(Deserialize, Serialize)]
+#[serde(bound = "")]
+pub struct DecryptionShare<E: Engine>(#[serde(with = "serde_impl::projective")] pub(crate) E::G1);
+
+impl<E: Engine> Clone for DecryptionShare<E> {
+    fn clone(&self) -> Self {
+        DecryptionShare(self.0)
+    }
+}
+
+impl<E: Engine> PartialEq for DecryptionShare<E> {
+    fn eq(&self, other: &Self) -> bool {
+        self.0 == other.0
+    }
+}
+
+impl<E: Engine> Eq for DecryptionShare<E> {}
+
+impl<E: Engine> Rand for DecryptionShare<E> {
+    fn rand<R: Rng>(rng: &mut R) -> Self {
+        DecryptionShare(rng.gen())
+    }
+}
+
+impl<E: Engine> Hash for DecryptionShare<E> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.into_affine().into_compressed().as_ref().hash(state);
+    }
+}
+
+impl<E: Engine> fmt::Debug for DecryptionShare<E> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("DecryptionShare").field(&self.0).finish()
+    }
+}
+
+impl<E: Engine> DecryptionShare<E> {
+    /// Returns a byte string representation of the decryption share.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        point_to_bytes(&self.0)
+    }
+
+    /// Decodes a decryption share from its byte string representation.
+    ///
+    /// Returns an error if the length is wrong, or if the bytes don't encode a point in the
+    /// prime order subgroup.
+    pub fn from_bytes<B: AsRef<[u8]>>(bytes: B) -> Result<Self> {
+        Ok(DecryptionShare(point_from_bytes(bytes.as_ref(), true)?))
+    }
+}
+
+/// A public key and an associated set of public key shares.
+#[derive(Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct PublicKeySet<E: Engine> {
+    /// The coefficients of a polynomial whose value at `0` is the "master key", and value at
+    /// `i + 1` is key share number `i`.
+    pub(crate) commit: Commitment<E>,
+}
+
+impl<E: Engine> Clone for PublicKeySet<E> {
+    fn clone(&self) -> Self {
+        PublicKeySet {
+            commit: self.commit.clone(),
+        }
+    }
+}
+
+impl<E: Engine> PartialEq for PublicKeySet<E> {
+    fn eq(&self, other: &Self) -> bool {
+        self.commit == other.commit
+    }
+}
+
+impl<E: Engine> Eq for PublicKeySet<E> {}
+
+impl<E: Engine> Hash for PublicKeySet<E> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.commit.hash(state);
+    }
+}
+
+impl<E: Engine> fmt::Debug for PublicKeySet<E> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("PublicKeySet")
+            .field("commit", &self.commit)
+            .finish()
+    }
+}
+
+impl<E: Engine> From<Commitment<E>> for PublicKeySet<E> {
+    fn from(commit: Commitment<E>) -> PublicKeySet<E> {
+        PublicKeySet { commit }
+    }
+}
+
+impl<E: Engine> PublicKeySet<E> {
+    /// Returns the threshold `t`: any set of `t + 1` signature shares can be combined into a full
+    /// signature.
+    pub fn threshold(&self) -> usize {
+        self.commit.degree()
+    }
+
+    /// Returns the public key.
+    pub fn public_key(&self) -> PublicKey<E> {
+        PublicKey(self.commit.coeff[0])
+    }
+
+    /// Returns a byte string representation of the public key set: the concatenated coefficients
+    /// of the commitment, starting with the public key.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.commit.coeff.iter().flat_map(point_to_bytes).collect()
+    }
+
+    /// Decodes a public key set from its byte string representation.
+    ///
+    /// Returns an error if the length is not a positive multiple of the size of a public key, if
+    /// any coefficient is not a point in the prime order subgroup, or if the public key is the
+    /// identity element.
+    pub fn from_bytes<B: AsRef<[u8]>>(bytes: B) -> Result<Self> {
+        let bytes = bytes.as_ref();
+        let pk_len = <E::G1Affine as CurveAffine>::Compressed::size();
+        if bytes.is_empty() || bytes.len() % pk_len != 0 {
+            return Err(Error::InvalidLength {
+                expected: (bytes.len() / pk_len + 1) * pk_len,
+                actual: bytes.len(),
+            });
+        }
+        let coeff = bytes
+            .chunks(pk_len)
+            .enumerate()
+            .map(|(i, chunk)| point_from_bytes(chunk, i != 0))
+            .collect::<Result<_>>()?;
+        Ok(PublicKeySet::from(Commitment { coeff }))
+    }
+
+    /// Returns the `i`-th public key share.
+    pub fn public_key_share<T: IntoFr<E::Fr>>(&self, i: T) -> PublicKeyShare<E> {
+        let value = self.commit.evaluate_fr(into_fr_plus_1(i));
+        PublicKeyShare(PublicKey(value))
+    }
+
+    /// Combines the shares into a signature that can be verified with the main public key.
+    ///
+    /// The shares must all have been created for the same hash: If they were signed with
+    /// `SecretKeyShare::sign_with_dst`, they must all use the same tag, and the result must be
+    /// verified with `PublicKey::verify_with_dst` and that tag.
+    pub fn combine_signatures<'a, T, I>(&self, shares: I) -> Result<Signature<E>>
+    where
+        I: IntoIterator<Item = (T, &'a SignatureShare<E>)>,
+        T: IntoFr<E::Fr>,
+    {
+        let samples = shares.into_iter().map(|(i, share)| (i, &(share.0).0));
+        Ok(Signature(interpolate(self.commit.degree(), samples)?))
+    }
+
+    /// Verifies the shares and combines the valid ones into a signature of `msg`. Returns the
+    /// signature and the indices of the invalid shares that were found.
+    ///
+    /// Only as many shares are verified as necessary: The remaining ones are checked in batches
+    /// until `threshold() + 1` of them have turned out to be valid. If the shares run out before
+    /// that, it returns `Error::InvalidShares`, or `Error::NotEnoughShares` if all were valid.
+    pub fn combine_signatures_verified<'a, T, I, M>(
+        &self,
+        shares: I,
+        msg: M,
+    ) -> Result<(Signature<E>, Vec<T>)>
+    where
+        I: IntoIterator<Item = (T, &'a SignatureShare<E>)>,
+        T: IntoFr<E::Fr>,
+        M: AsRef<[u8]>,
+    {
+        self.combine_signatures_verified_g2(shares, hash_g2::<E, M>(msg))
+    }
+
+    /// Verifies the shares of the given element of `G2` and combines the valid ones into a
+    /// signature. Returns the signature and the indices of the invalid shares.
+    pub(crate) fn combine_signatures_verified_g2<'a, T, I>(
+        &self,
+        shares: I,
+        hash: E::G2,
+    ) -> Result<(Signature<E>, Vec<T>)>
+    where
+        I: IntoIterator<Item = (T, &'a SignatureShare<E>)>,
+        T: IntoFr<E::Fr>,
+    {
+        let needed = self.threshold() + 1;
+        let mut shares = shares.into_iter();
+        let mut valid = Vec::with_capacity(needed);
+        let mut faulty = Vec::new();
+        while valid.len() < needed {
+            // Verify as many of the remaining shares as are still missing, as one batch.
+            let batch: Vec<(T, &SignatureShare<E>)> =
+                shares.by_ref().take(needed - valid.len()).collect();
+            if batch.is_empty() {
+                return Err(not_enough_valid_shares::<E::Fr, _>(
+                    valid.len(),
+                    &faulty,
+                    needed,
+                ));
+            }
+            let items = batch
+                .iter()
+                .map(|&(i, share)| ((self.public_key_share(i).0).0, (share.0).0, b""));
+            let invalid = invalid_signatures::<E, _, _, _>(items, |_| hash);
+            for (pos, (i, share)) in batch.into_iter().enumerate() {
+                if invalid.contains(&pos) {
+                    faulty.push(i);
+                } else {
+                    valid.push((i, share));
+                }
+            }
+        }
+        let sig = self.combine_signatures(valid)?;
+        Ok((sig, faulty))
+    }
+
+    /// Verifies the shares of the given element of `G2` that are needed, i.e. the first
+    /// `threshold() + 1`, and combines them into a signature. Returns `Error::InvalidShares` if
+    /// any of them is invalid.
+    pub(crate) fn combine_signatures_checked_g2<'a, T, I>(
+        &self,
+        shares: I,
+        hash: E::G2,
+    ) -> Result<Signature<E>>
+    where
+        I: IntoIterator<Item = (T, &'a SignatureShare<E>)>,
+        T: IntoFr<E::Fr>,
+    {
+        let needed = self.threshold() + 1;
+        let shares: Vec<(T, &SignatureShare<E>)> = shares.into_iter().take(needed).collect();
+        let items = shares
+            .iter()
+            .map(|&(i, share)| ((self.public_key_share(i).0).0, (share.0).0, b""));
+        let invalid = invalid_signatures::<E, _, _, _>(items, |_| hash);
+        if !invalid.is_empty() {
+            let faulty: Vec<T> = invalid.iter().map(|&pos| shares[pos].0).collect();
+            let valid = shares.len() - faulty.len();
+            return Err(not_enough_valid_shares::<E::Fr, _>(valid, &faulty, needed));
+        }
+        self.combine_signatures(shares)
+    }
+
+    /// Combines the shares to decrypt the ciphertext.
+    pub fn decrypt<'a, T, I>(&self, shares: I, ct: &Ciphertext<E>) -> Result<Vec<u8>>
+    where
+        I: IntoIterator<Item = (T, &'a DecryptionShare<E>)>,
+        T: IntoFr<E::Fr>,
+    {
+        let samples = shares.into_iter().map(|(i, share)| (i, &share.0));
+        let g: E::G1 = interpolate(self.commit.degree(), samples)?;
+        Ok(xor_with_hash(g, &ct.1))
+    }
+
+    /// Verifies the shares and combines the valid ones to decrypt the ciphertext. Returns the
+    /// plaintext and the indices of the invalid shares that were found.
+    ///
+    /// The shares are verified one by one, until `threshold() + 1` valid ones have been found.
+    /// If the shares run out before that, it returns `Error::InvalidShares`, or
+    /// `Error::NotEnoughShares` if all were valid. If the ciphertext itself is invalid, it
+    /// returns `Error::InvalidCiphertext`.
+    pub fn decrypt_verified<'a, T, I>(
+        &self,
+        shares: I,
+        ct: &Ciphertext<E>,
+    ) -> Result<(Vec<u8>, Vec<T>)>
+    where
+        I: IntoIterator<Item = (T, &'a DecryptionShare<E>)>,
+        T: IntoFr<E::Fr>,
+    {
+        if !ct.verify() {
+            return Err(Error::InvalidCiphertext);
+        }
+        let needed = self.threshold() + 1;
+        let mut valid = Vec::with_capacity(needed);
+        let mut faulty = Vec::new();
+        for (i, share) in shares {
+            if valid.len() == needed {
+                break;
+            }
+            if self.public_key_share(i).verify_decryption_share(share, ct) {
+                valid.push((i, share));
+            } else {
+                faulty.push(i);
+            }
+        }
+        if valid.len() < needed {
+            return Err(not_enough_valid_shares::<E::Fr, _>(
+                valid.len(),
+                &faulty,
+                needed,
+            ));
+        }
+        let msg = self.decrypt(valid, ct)?;
+        Ok((msg, faulty))
+    }
+}
+
+/// A secret key and an associated set of secret key shares.
+pub struct SecretKeySet<E: Engine> {
+    /// The coefficients of a polynomial whose value at `0` is the "master key", and value at
+    /// `i + 1` is key share number `i`.
+    poly: Poly<E>,
+}
+
+impl<E: Engine> From<Poly<E>> for SecretKeySet<E> {
+    fn from(poly: Poly<E>) -> SecretKeySet<E> {
+        SecretKeySet { poly }
+    }
+}
+
+impl<E: Engine> SecretKeySet<E> {
+    /// Creates a set of secret key shares, where any `threshold + 1` of them can collaboratively
+    /// sign and decrypt. This constuctor is identical to the `SecretKey::try_random()` in every
+    /// way except that this constructor panics if the other returns an error.
+    ///
+    /// # Panic
+    ///
+    /// Panics if the `threshold` is too large for the coefficients to fit into a `Vec`.
+    pub fn random<R: Rng>(threshold: usize, rng: &mut R) -> Self {
+        SecretKeySet::try_random(threshold, rng)
+            .unwrap_or_else(|e| panic!("Failed to create random `SecretKeySet`: {}", e))
+    }
+
+    /// Creates a set of secret key shares, where any `threshold + 1` of them can collaboratively
+    /// sign and decrypt. This constuctor is identical to the `SecretKey::random()` in every
+    /// way except that this constructor returns an `Err` where the `random` would panic.
+    pub fn try_random<R: Rng>(threshold: usize, rng: &mut R) -> Result<Self> {
+        Poly::try_random(threshold, rng).map(SecretKeySet::from)
+    }
+
+    /// Returns the threshold `t`: any set of `t + 1` signature shares can be combined into a full
+    /// signature.
+    pub fn threshold(&self) -> usize {
+        self.poly.degree()
+    }
+
+    /// Returns the `i`-th secret key share.
+    pub fn secret_key_share<T: IntoFr<E::Fr>>(&self, i: T) -> SecretKeyShare<E> {
+        let mut fr = self.poly.evaluate_fr(into_fr_plus_1(i));
+        SecretKeyShare::from_mut(&mut fr)
+    }
+
+    /// Returns the corresponding public key set. That information can be shared publicly.
+    pub fn public_keys(&self) -> PublicKeySet<E> {
+        PublicKeySet {
+            commit: self.poly.commitment(),
+        }
+    }
+
+    /// Returns the secret master key.
+    #[cfg(test)]
+    pub(crate) fn secret_key(&self) -> SecretKey<E> {
+        let mut fr = self.poly.evaluate(0);
+        SecretKey::from_mut(&mut fr)
+    }
+}
+
+/// Returns a hash of the given message in `G1`.
+///
+/// This uses a random number generator seeded with the message's SHA3 digest, which is specific
+/// to this crate. For a hash that other BLS implementations can reproduce, use `hash_to_g1`.
+pub fn hash_g1<E: Engine, M: AsRef<[u8]>>(msg: M) -> E::G1 {
+    rng_from_hash(msg.as_ref()).gen()
+}
+
+/// Returns a hash of the given message in `G2`.
+///
+/// This uses a random number generator seeded with the message's SHA3 digest, which is specific
+/// to this crate. For a hash that other BLS implementations can reproduce, use `hash_to_g2`.
+pub fn hash_g2<E: Engine, M: AsRef<[u8]>>(msg: M) -> E::G2 {
+    rng_from_hash(msg.as_ref()).gen()
+}
+
+/// Returns a hash of the group element, message and label, in the second group.
+pub(crate) fn hash_g1_g2<E, M, L>(g1: E::G1, msg: M, label: L) -> E::G2
+where
+    E: Engine,
+    M: AsRef<[u8]>,
+    L: AsRef<[u8]>,
+{
+    // Without a label, the input is the same as before labels were introduced, so existing
+    // ciphertexts and decryption shares remain valid. That input is at most 64 bytes plus a
+    // point, so a label is prefixed with a 64-byte tag and its length: This way, it can neither
+    // be confused with a part of the message nor with an unlabeled input.
+    let label = label.as_ref();
+    let mut data = Vec::new();
+    if !label.is_empty() {
+        data.extend(LABEL_TAG);
+        data.resize(LABEL_TAG_LEN + 8, 0);
+        BigEndian::write_u64(&mut data[LABEL_TAG_LEN..], label.len() as u64);
+        data.extend(label);
+    }
+    // If the message is large, hash it, otherwise copy it.
+    // TODO: Benchmark and optimize the threshold.
+    if msg.as_ref().len() > 64 {
+        data.extend(&sha3_256(msg.as_ref()));
+    } else {
+        data.extend(msg.as_ref());
+    }
+    data.extend(g1.into_affine().into_compressed().as_ref());
+    hash_g2::<E, _>(&data)
+}
+
+/// Returns the product of the pairings of the given pairs of group elements.
+///
+/// This performs a Miller loop for each pair, but only a single final exponentiation.
+fn pairing_product<E, I>(pairs: I) -> E::Fqk
+where
+    E: Engine,
+    I: IntoIterator<Item = (E::G1Affine, E::G2Affine)>,
+{
+    let prepared: Vec<_> = pairs
+        .into_iter()
+        .map(|(g1, g2)| (g1.prepare(), g2.prepare()))
+        .collect();
+    let refs: Vec<_> = prepared.iter().map(|(g1, g2)| (g1, g2)).collect();
+    let miller = E::miller_loop(&refs);
+    E::final_exponentiation(&miller).expect("Miller loop result is nonzero")
+}
+
+/// A signature that is part of a batch: the public key, the signature, and the index of the
+/// message's hash.
+type BatchEntry<E> = (<E as Engine>::G1, <E as Engine>::G2, usize);
+
+/// Verifies a batch of signatures, where `hash` maps the messages to `G2`. Returns
+/// `Error::InvalidSignatures` with the indices of the invalid entries, if there are any.
+pub(crate) fn verify_batch<E, I, M, H>(items: I, hash: H) -> Result<()>
+where
+    E: Engine,
+    I: IntoIterator<Item = (E::G1, E::G2, M)>,
+    M: AsRef<[u8]>,
+    H: Fn(&[u8]) -> E::G2,
+{
+    let indices = invalid_signatures::<E, _, _, _>(items, hash);
+    if indices.is_empty() {
+        Ok(())
+    } else {
+        Err(Error::InvalidSignatures { indices })
+    }
+}
+
+/// Verifies a batch of signatures, where `hash` maps the messages to `G2`, and returns the
+/// indices of the invalid entries.
+fn invalid_signatures<E, I, M, H>(items: I, hash: H) -> Vec<usize>
+where
+    E: Engine,
+    I: IntoIterator<Item = (E::G1, E::G2, M)>,
+    M: AsRef<[u8]>,
+    H: Fn(&[u8]) -> E::G2,
+{
+    // Every distinct message is only hashed once.
+    let mut hash_indices: BTreeMap<Vec<u8>, usize> = BTreeMap::new();
+    let mut hashes = Vec::new();
+    let mut entries: Vec<BatchEntry<E>> = Vec::new();
+    for (pk, sig, msg) in items {
+        let msg = msg.as_ref();
+        let index = match hash_indices.get(msg) {
+            Some(&index) => index,
+            None => {
+                hashes.push(hash(msg));
+                hash_indices.insert(msg.to_vec(), hashes.len() - 1);
+                hashes.len() - 1
+            }
+        };
+        entries.push((pk, sig, index));
+    }
+    // The identity element of the pairings' target group. (In the mock engine it is written
+    // additively, so this is not necessarily `Fqk::one()`.)
+    let gt_identity = pairing_product::<E, _>(iter::empty());
+    let mut rng = rand::thread_rng();
+    let mut invalid = Vec::new();
+    find_invalid::<E, _>(&mut rng, &entries, &hashes, gt_identity, 0, &mut invalid);
+    invalid
+}
+
+/// Checks the batch, and if it fails, bisects it to find the invalid entries. Their indices,
+/// plus `offset`, are appended to `invalid`.
+fn find_invalid<E: Engine, R: Rng>(
+    rng: &mut R,
+    entries: &[BatchEntry<E>],
+    hashes: &[E::G2],
+    gt_identity: E::Fqk,
+    offset: usize,
+    invalid: &mut Vec<usize>,
+) {
+    if check_batch::<E, _>(rng, entries, hashes, gt_identity) {
+        return;
+    }
+    if entries.len() == 1 {
+        invalid.push(offset);
+        return;
+    }
+    let mid = entries.len() / 2;
+    let (left, right) = entries.split_at(mid);
+    find_invalid::<E, _>(rng, left, hashes, gt_identity, offset, invalid);
+    find_invalid::<E, _>(rng, right, hashes, gt_identity, offset + mid, invalid);
+}
+
+/// Returns `true` if all signatures in the batch are valid, except with negligible probability.
+///
+/// Each entry is multiplied with a random coefficient `r`, and the equation
+/// `e(g, sum(r * sig)) == product(e(sum(r * pk), hash))` is checked as a single multi-pairing,
+/// where the sums of keys are taken over the entries with equal hashes. The result is compared
+/// to `gt_identity`, the identity element of the target group.
+fn check_batch<E: Engine, R: Rng>(
+    rng: &mut R,
+    entries: &[BatchEntry<E>],
+    hashes: &[E::G2],
+    gt_identity: E::Fqk,
+) -> bool {
+    let mut sig_sum = E::G2::zero();
+    let mut key_sums = vec![E::G1::zero(); hashes.len()];
+    for &(pk, sig, index) in entries {
+        let r: E::Fr = rng.gen();
+        sig_sum.add_assign(&sig.into_affine().mul(r));
+        key_sums[index].add_assign(&pk.into_affine().mul(r));
+    }
+    let mut minus_one = E::G1Affine::one();
+    minus_one.negate();
+    let pairs = key_sums
+        .into_iter()
+        .zip(hashes)
+        .map(|(key, hash)| (key.into_affine(), hash.into_affine()))
+        .chain(iter::once((minus_one, sig_sum.into_affine())));
+    pairing_product::<E, _>(pairs) == gt_identity
+}
+
+/// Returns `true` if two of the messages are equal.
+pub(crate) fn has_duplicate_messages<E: Engine, M: AsRef<[u8]>>(
+    items: &[(PublicKey<E>, M)],
+) -> bool {
+    let mut msgs = BTreeSet::new();
+    !items.iter().all(|(_, msg)| msgs.insert(msg.as_ref()))
+}
+
+/// Returns the bitwise xor of `bytes` with a sequence of pseudorandom bytes determined by `g1`.
+pub(crate) fn xor_with_hash<C: CurveProjective>(g1: C, bytes: &[u8]) -> Vec<u8> {
+    let mut rng = rng_from_hash(g1.into_affine().into_compressed().as_ref());
+    let xor = |(a, b): (u8, &u8)| a ^ b;
+    rng.gen_iter().zip(bytes).map(xor).collect()
+}
+
+/// Given a list of `t + 1` samples `(i - 1, f(i) * g)` for a polynomial `f` of degree `t`, and a
+/// group generator `g`, returns `f(0) * g`.
+pub(crate) fn interpolate<C, B, T, I>(t: usize, items: I) -> Result<C>
+where
+    C: CurveProjective,
+    I: IntoIterator<Item = (T, B)>,
+    T: IntoFr<C::Scalar>,
+    B: Borrow<C>,
+{
+    let (indices, samples): (Vec<T>, Vec<C>) = items
+        .into_iter()
+        .take(t + 1)
+        .map(|(i, sample)| (i, *sample.borrow()))
+        .unzip();
+    if samples.len() <= t {
+        return Err(Error::NotEnoughShares {
+            supplied: samples.len(),
+            needed: t + 1,
+        });
+    }
+
+    if t == 0 {
+        return Ok(samples[0]);
+    }
+
+    let xs: Vec<C::Scalar> = indices.iter().map(into_fr_plus_1).collect();
+    let coeffs = lagrange_coeffs(&xs).map_err(|pos| Error::DuplicateEntry {
+        index: indices[pos].into_share_index(),
+    })?;
+    Ok(multiexp(&samples, &coeffs))
+}
+
+/// Returns the values at `0` of the Lagrange polynomials for the given points: The `i`-th one is
+/// `1` at `xs[i]` and `0` at all other points.
+///
+/// If a point appears more than once, returns the position of one of its occurrences instead.
+pub(crate) fn lagrange_coeffs<F: PrimeField>(xs: &[F]) -> ::std::result::Result<Vec<F>, usize> {
+    if xs.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    // Compute the products `x_prod[i]` of all but the `i`-th entry.
+    let mut x_prod: Vec<F> = Vec::with_capacity(xs.len());
+    let mut tmp = F::one();
+    x_prod.push(tmp);
+    for x in &xs[..xs.len() - 1] {
+        tmp.mul_assign(x);
+        x_prod.push(tmp);
+    }
+    tmp = F::one();
+    for (i, x) in xs[1..].iter().enumerate().rev() {
+        tmp.mul_assign(x);
+        x_prod[i].mul_assign(&tmp);
+    }
+
+    for (pos, (l0, x)) in x_prod.iter_mut().zip(xs).enumerate() {
+        // Compute the value at 0 of the Lagrange polynomial that is `0` at the other data
+        // points but `1` at `x`.
+        let mut denom = F::one();
+        for (_, x0) in xs.iter().enumerate().filter(|&(pos0, _)| pos0 != pos) {
+            let mut diff = *x0;
+            diff.sub_assign(x);
+            if diff.is_zero() {
+                return Err(pos);
+            }
+            denom.mul_assign(&diff);
+        }
+        l0.mul_assign(&denom.inverse().expect("denominator is nonzero"));
+    }
+    Ok(x_prod)
+}
+
+/// Returns the error for a verified combination that found only `valid` valid shares.
+fn not_enough_valid_shares<F: PrimeField, T: IntoFr<F>>(
+    valid: usize,
+    faulty: &[T],
+    needed: usize,
+) -> Error {
+    if faulty.is_empty() {
+        Error::NotEnoughShares {
+            supplied: valid,
+            needed,
+        }
+    } else {
+        Error::InvalidShares {
+            indices: faulty.iter().map(|i| i.into_share_index()).collect(),
+            valid,
+            needed,
+        }
+    }
+}
+
+/// Returns the compressed representation of the group element.
+pub(crate) fn point_to_bytes<C: CurveProjective>(point: &C) -> Vec<u8> {
+    point.into_affine().into_compressed().as_ref().to_vec()
+}
+
+/// Decodes a group element from its compressed representation. Returns an error if the length is
+/// wrong, if the bytes don't encode a point in the prime order subgroup, or if they encode the
+/// identity element and `allow_zero` is `false`.
+pub(crate) fn point_from_bytes<C: CurveProjective>(bytes: &[u8], allow_zero: bool) -> Result<C> {
+    let mut compressed = <C::Affine as CurveAffine>::Compressed::empty();
+    if bytes.len() != compressed.as_ref().len() {
+        return Err(Error::InvalidLength {
+            expected: compressed.as_ref().len(),
+            actual: bytes.len(),
+        });
+    }
+    compressed.as_mut().copy_from_slice(bytes);
+    let affine = compressed
+        .into_affine()
+        .map_err(|err| Error::InvalidPoint(err.to_string()))?;
+    if !allow_zero && affine.is_zero() {
+        let msg = "the identity element is not allowed";
+        return Err(Error::InvalidPoint(msg.to_string()));
+    }
+    Ok(affine.into_projective())
+}
+
+pub(crate) fn into_fr_plus_1<F: PrimeField, I: IntoFr<F>>(x: I) -> F {
+    let mut result = F::one();
+    result.add_assign(&x.into_fr());
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use error::ShareIndex;
+    use mock::Mocktography;
+
+    #[test]
+    fn test_other_engine() {
+        let mut rng = rand::thread_rng();
+        let sk_set = SecretKeySet::<Mocktography>::random(2, &mut rng);
+        let pk_set = sk_set.public_keys();
+        let msg = b"Totally real news";
+
+        // The shares are combined and verified just like with `PEngine`.
+        let sigs: BTreeMap<_, _> = (0usize..5)
+            .map(|i| (i, sk_set.secret_key_share(i).sign(msg)))
+            .collect();
+        let sig = pk_set.combine_signatures(&sigs).expect("signatures match");
+        assert!(pk_set.public_key().verify(&sig, msg));
+        assert_eq!(sk_set.secret_key().sign(msg), sig);
+
+        // The errors contain the caller's indices, independently of the engine.
+        let wrong_sig = sk_set.secret_key_share(7).sign(msg);
+        let shares = vec![(3usize, &wrong_sig), (1, &sigs[&1]), (4, &wrong_sig)];
+        let expected = Error::InvalidShares {
+            indices: vec![ShareIndex::Int(3), ShareIndex::Int(4)],
+            valid: 1,
+            needed: 3,
+        };
+        let result = pk_set.combine_signatures_verified(shares, msg);
+        assert_eq!(Some(expected), result.err());
+        let shares = vec![(2usize, &sigs[&2]), (0, &sigs[&0]), (2, &sigs[&2])];
+        let expected = Error::DuplicateEntry {
+            index: ShareIndex::Int(2),
+        };
+        assert_eq!(Some(expected), pk_set.combine_signatures(shares).err());
+    }
+}
@@ -0,0 +1,723 @@
+//! Uni- and bivariate polynomials and commitments, generic over the pairing engine.
+//!
+//! The types in the `poly` module are aliases for these types with the engine `PEngine`. See its
+//! documentation for how they are used in distributed key generation.
+//!
+//! Pedersen commitments are out of scope here: Their second generator is derived with
+//! `hash_to_g1`, which is only specified for BLS12-381, so they are only available in the `poly`
+//! module, for `PEngine`. The same applies to the `pvss` module, which uses them.
+
+use std::borrow::Borrow;
+use std::fmt::{self, Debug, Formatter};
+use std::hash::{Hash, Hasher};
+use std::mem::size_of_val;
+use std::{cmp, iter, ops};
+
+use pairing::{CurveAffine, CurveProjective, Engine, Field, PrimeField, PrimeFieldRepr};
+use rand::Rng;
+
+use error::{Error, Result};
+use into_fr::IntoFr;
+use multiexp::multiexp;
+use secret::{clear_fr, ContainsSecret, MemRange, Safe};
+use serde_impl;
+
+/// The maximum number of bits of a point at which a polynomial in `G1` is evaluated with Horner's
+/// method instead of a multi-scalar multiplication. Share indices are usually much smaller.
+const SMALL_SCALAR_BITS: u32 = 32;
+
+/// A univariate polynomial in the prime field.
+#[derive(Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct Poly<E: Engine> {
+    /// The coefficients of a polynomial.
+    #[serde(with = "serde_impl::field_vec")]
+    pub(crate) coeff: Vec<E::Fr>,
+}
+
+impl<E: Engine> PartialEq for Poly<E> {
+    fn eq(&self, other: &Self) -> bool {
+        self.coeff == other.coeff
+    }
+}
+
+impl<E: Engine> Eq for Poly<E> {}
+
+/// Creates a new `Poly` with the same coefficients as another polynomial.
+impl<E: Engine> Clone for Poly<E> {
+    fn clone(&self) -> Self {
+        Poly::from(self.coeff.clone())
+    }
+}
+
+/// A debug statement where the `coeff` vector of prime field elements has been redacted.
+impl<E: Engine> Debug for Poly<E> {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.debug_struct("Poly").field("coeff", &"...").finish()
+    }
+}
+
+#[cfg_attr(feature = "cargo-clippy", allow(suspicious_op_assign_impl))]
+impl<E: Engine, B: Borrow<Poly<E>>> ops::AddAssign<B> for Poly<E> {
+    fn add_assign(&mut self, rhs: B) {
+        let len = self.coeff.len();
+        let rhs_len = rhs.borrow().coeff.len();
+        if rhs_len > len {
+            self.coeff.resize(rhs_len, E::Fr::zero());
+        }
+        for (self_c, rhs_c) in self.coeff.iter_mut().zip(&rhs.borrow().coeff) {
+            Field::add_assign(self_c, rhs_c);
+        }
+        self.remove_zeros();
+    }
+}
+
+impl<'a, E: Engine, B: Borrow<Poly<E>>> ops::Add<B> for &'a Poly<E> {
+    type Output = Poly<E>;
+
+    fn add(self, rhs: B) -> Poly<E> {
+        (*self).clone() + rhs
+    }
+}
+
+impl<E: Engine, B: Borrow<Poly<E>>> ops::Add<B> for Poly<E> {
+    type Output = Poly<E>;
+
+    fn add(mut self, rhs: B) -> Poly<E> {
+        self += rhs;
+        self
+    }
+}
+
+impl<E: Engine, B: Borrow<Poly<E>>> ops::SubAssign<B> for Poly<E> {
+    fn sub_assign(&mut self, rhs: B) {
+        let len = self.coeff.len();
+        let rhs_len = rhs.borrow().coeff.len();
+        if rhs_len > len {
+            self.coeff.resize(rhs_len, E::Fr::zero());
+        }
+        for (self_c, rhs_c) in self.coeff.iter_mut().zip(&rhs.borrow().coeff) {
+            Field::sub_assign(self_c, rhs_c);
+        }
+        self.remove_zeros();
+    }
+}
+
+impl<'a, E: Engine, B: Borrow<Poly<E>>> ops::Sub<B> for &'a Poly<E> {
+    type Output = Poly<E>;
+
+    fn sub(self, rhs: B) -> Poly<E> {
+        (*self).clone() - rhs
+    }
+}
+
+impl<E: Engine, B: Borrow<Poly<E>>> ops::Sub<B> for Poly<E> {
+    type Output = Poly<E>;
+
+    fn sub(mut self, rhs: B) -> Poly<E> {
+        self -= rhs;
+        self
+    }
+}
+
+// Clippy thinks using any `+` and `-` in a `Mul` implementation is suspicious.
+#[cfg_attr(feature = "cargo-clippy", allow(suspicious_arithmetic_impl))]
+impl<'a, E: Engine, B: Borrow<Poly<E>>> ops::Mul<B> for &'a Poly<E> {
+    type Output = Poly<E>;
+
+    fn mul(self, rhs: B) -> Self::Output {
+        let rhs = rhs.borrow();
+        if rhs.is_zero() || self.is_zero() {
+            return Poly::zero();
+        }
+        let n_coeffs = self.coeff.len() + rhs.coeff.len() - 1;
+        let mut coeffs = vec![E::Fr::zero(); n_coeffs];
+        let mut tmp = Safe::new(Box::new(E::Fr::zero()));
+        for (i, ca) in self.coeff.iter().enumerate() {
+            for (j, cb) in rhs.coeff.iter().enumerate() {
+                *tmp = *ca;
+                tmp.mul_assign(cb);
+                coeffs[i + j].add_assign(&*tmp);
+            }
+        }
+        Poly::from(coeffs)
+    }
+}
+
+impl<E: Engine, B: Borrow<Poly<E>>> ops::Mul<B> for Poly<E> {
+    type Output = Poly<E>;
+
+    fn mul(self, rhs: B) -> Self::Output {
+        &self * rhs
+    }
+}
+
+impl<E: Engine, B: Borrow<Self>> ops::MulAssign<B> for Poly<E> {
+    fn mul_assign(&mut self, rhs: B) {
+        *self = &*self * rhs;
+    }
+}
+
+impl<E: Engine> Drop for Poly<E> {
+    fn drop(&mut self) {
+        self.zero_secret();
+    }
+}
+
+/// Creates a new `Poly` instance from a vector of prime field elements representing the
+/// coefficients of the polynomial.
+impl<E: Engine> From<Vec<E::Fr>> for Poly<E> {
+    fn from(coeff: Vec<E::Fr>) -> Self {
+        Poly { coeff }
+    }
+}
+
+impl<E: Engine> ContainsSecret for Poly<E> {
+    fn secret_memory(&self) -> MemRange {
+        let ptr = self.coeff.as_ptr() as *mut u8;
+        let n_bytes = size_of_val(self.coeff.as_slice());
+        MemRange { ptr, n_bytes }
+    }
+}
+
+impl<E: Engine> Poly<E> {
+    /// Creates a random polynomial.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the `degree` is too large for the coefficients to fit into a `Vec`.
+    pub fn random<R: Rng>(degree: usize, rng: &mut R) -> Self {
+        Poly::try_random(degree, rng)
+            .unwrap_or_else(|e| panic!("Failed to create random `Poly`: {}", e))
+    }
+
+    /// Creates a random polynomial. This constructor is identical to the `Poly::random()`
+    /// constructor in every way except that this constructor will return an `Err` where
+    /// `try_random` would return an error.
+    pub fn try_random<R: Rng>(degree: usize, rng: &mut R) -> Result<Self> {
+        if degree == usize::max_value() {
+            return Err(Error::DegreeTooHigh);
+        }
+        let coeff: Vec<E::Fr> = (0..=degree).map(|_| rng.gen()).collect();
+        Ok(Poly::from(coeff))
+    }
+
+    /// Returns the polynomial with constant value `0`.
+    pub fn zero() -> Self {
+        Poly { coeff: vec![] }
+    }
+
+    /// Returns `true` if the polynomial is the constant value `0`.
+    pub fn is_zero(&self) -> bool {
+        self.coeff.iter().all(|coeff| coeff.is_zero())
+    }
+
+    /// Returns the polynomial with constant value `1`.
+    pub fn one() -> Self {
+        Poly::constant(E::Fr::one())
+    }
+
+    /// Returns the polynomial with constant value `c`.
+    pub fn constant(mut c: E::Fr) -> Self {
+        // We overwrite the field element within this method's stack frame with zeros once we have
+        // copied it onto the heap as part of the vector of polynomial coefficients.
+        let poly = Poly::from(vec![c]);
+        clear_fr(&mut c);
+        poly
+    }
+
+    /// Returns the identity function, i.e. the polynomial "`x`".
+    pub fn identity() -> Self {
+        Poly::monomial(1)
+    }
+
+    /// Returns the (monic) monomial: `x.pow(degree)`.
+    pub fn monomial(degree: usize) -> Self {
+        let coeff: Vec<E::Fr> = iter::repeat(E::Fr::zero())
+            .take(degree)
+            .chain(iter::once(E::Fr::one()))
+            .collect();
+        Poly::from(coeff)
+    }
+
+    /// Returns the unique polynomial `f` of degree `samples.len() - 1` with the given values
+    /// `(x, f(x))`.
+    pub fn interpolate<T, U, I>(samples_repr: I) -> Self
+    where
+        I: IntoIterator<Item = (T, U)>,
+        T: IntoFr<E::Fr>,
+        U: IntoFr<E::Fr>,
+    {
+        let convert = |(x, y): (T, U)| (x.into_fr(), y.into_fr());
+        let samples: Vec<(E::Fr, E::Fr)> = samples_repr.into_iter().map(convert).collect();
+        Poly::compute_interpolation(&samples)
+    }
+
+    /// Returns the degree.
+    pub fn degree(&self) -> usize {
+        self.coeff.len().saturating_sub(1)
+    }
+
+    /// Returns the value at the point `i`.
+    pub fn evaluate<T: IntoFr<E::Fr>>(&self, i: T) -> E::Fr {
+        self.evaluate_fr(i.into_fr())
+    }
+
+    /// Returns the value at the point `x`.
+    pub(crate) fn evaluate_fr(&self, x: E::Fr) -> E::Fr {
+        let mut result = match self.coeff.last() {
+            None => return E::Fr::zero(),
+            Some(c) => *c,
+        };
+        for c in self.coeff.iter().rev().skip(1) {
+            result.mul_assign(&x);
+            result.add_assign(c);
+        }
+        result
+    }
+
+    /// Returns the corresponding commitment.
+    pub fn commitment(&self) -> Commitment<E> {
+        let to_g1 = |c: &E::Fr| E::G1Affine::one().mul(*c);
+        Commitment {
+            coeff: self.coeff.iter().map(to_g1).collect(),
+        }
+    }
+
+    /// Multiplies the polynomial with the scalar `rhs`.
+    pub(crate) fn mul_assign_fr(&mut self, rhs: &E::Fr) {
+        if rhs.is_zero() {
+            self.zero_secret();
+            self.coeff.clear();
+        } else {
+            self.coeff.iter_mut().for_each(|c| c.mul_assign(rhs));
+        }
+    }
+
+    /// Removes all trailing zero coefficients.
+    pub(crate) fn remove_zeros(&mut self) {
+        let zeros = self.coeff.iter().rev().take_while(|c| c.is_zero()).count();
+        let len = self.coeff.len() - zeros;
+        self.coeff.truncate(len);
+    }
+
+    /// Returns the unique polynomial `f` of degree `samples.len() - 1` with the given values
+    /// `(x, f(x))`.
+    fn compute_interpolation(samples: &[(E::Fr, E::Fr)]) -> Self {
+        if samples.is_empty() {
+            return Poly::zero();
+        }
+        // Interpolates on the first `i` samples.
+        let mut poly = Poly::constant(samples[0].1);
+        let mut minus_s0 = samples[0].0;
+        minus_s0.negate();
+        // Is zero on the first `i` samples.
+        let mut base = Poly::from(vec![minus_s0, E::Fr::one()]);
+
+        // We update `base` so that it is always zero on all previous samples, and `poly` so that
+        // it has the correct values on the previous samples.
+        for (ref x, ref y) in &samples[1..] {
+            // Scale `base` so that its value at `x` is the difference between `y` and `poly`'s
+            // current value at `x`: Adding it to `poly` will then make it correct for `x`.
+            let mut diff = *y;
+            diff.sub_assign(&poly.evaluate_fr(*x));
+            let base_val: E::Fr = base.evaluate_fr(*x);
+            diff.mul_assign(&base_val.inverse().expect("sample points must be distinct"));
+            base.mul_assign_fr(&diff);
+            poly += &base;
+
+            // Finally, multiply `base` by X - x, so that it is zero at `x`, too, now.
+            let mut minus_x = *x;
+            minus_x.negate();
+            base *= Poly::from(vec![minus_x, E::Fr::one()]);
+        }
+        poly
+    }
+
+    /// Generates a non-redacted debug string. This method differs from
+    /// the `Debug` implementation in that it *does* leak the secret prime
+    /// field elements.
+    pub fn reveal(&self) -> String {
+        format!("Poly {{ coeff: {:?} }}", self.coeff)
+    }
+}
+
+/// A commitment to a univariate polynomial.
+#[derive(Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct Commitment<E: Engine> {
+    /// The coefficients of the polynomial.
+    #[serde(with = "serde_impl::projective_vec")]
+    pub(crate) coeff: Vec<E::G1>,
+}
+
+impl<E: Engine> Debug for Commitment<E> {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.debug_struct("Commitment")
+            .field("coeff", &self.coeff)
+            .finish()
+    }
+}
+
+impl<E: Engine> Clone for Commitment<E> {
+    fn clone(&self) -> Self {
+        Commitment {
+            coeff: self.coeff.clone(),
+        }
+    }
+}
+
+impl<E: Engine> PartialEq for Commitment<E> {
+    fn eq(&self, other: &Self) -> bool {
+        self.coeff == other.coeff
+    }
+}
+
+impl<E: Engine> Eq for Commitment<E> {}
+
+impl<E: Engine> Hash for Commitment<E> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.coeff.len().hash(state);
+        for c in &self.coeff {
+            c.into_affine().into_compressed().as_ref().hash(state);
+        }
+    }
+}
+
+impl<E: Engine, B: Borrow<Commitment<E>>> ops::AddAssign<B> for Commitment<E> {
+    fn add_assign(&mut self, rhs: B) {
+        let len = cmp::max(self.coeff.len(), rhs.borrow().coeff.len());
+        self.coeff.resize(len, E::G1::zero());
+        for (self_c, rhs_c) in self.coeff.iter_mut().zip(&rhs.borrow().coeff) {
+            self_c.add_assign(rhs_c);
+        }
+        self.remove_zeros();
+    }
+}
+
+impl<'a, E: Engine, B: Borrow<Commitment<E>>> ops::Add<B> for &'a Commitment<E> {
+    type Output = Commitment<E>;
+
+    fn add(self, rhs: B) -> Commitment<E> {
+        (*self).clone() + rhs
+    }
+}
+
+impl<E: Engine, B: Borrow<Commitment<E>>> ops::Add<B> for Commitment<E> {
+    type Output = Commitment<E>;
+
+    fn add(mut self, rhs: B) -> Commitment<E> {
+        self += rhs;
+        self
+    }
+}
+
+impl<E: Engine> Commitment<E> {
+    /// Returns the polynomial's degree.
+    pub fn degree(&self) -> usize {
+        self.coeff.len() - 1
+    }
+
+    /// Returns the `i`-th public key share.
+    pub fn evaluate<T: IntoFr<E::Fr>>(&self, i: T) -> E::G1 {
+        self.evaluate_fr(i.into_fr())
+    }
+
+    /// Returns the value at the point `x`.
+    pub(crate) fn evaluate_fr(&self, x: E::Fr) -> E::G1 {
+        evaluate_g1(&self.coeff, x)
+    }
+
+    /// Removes all trailing zero coefficients.
+    fn remove_zeros(&mut self) {
+        let zeros = self.coeff.iter().rev().take_while(|c| c.is_zero()).count();
+        let len = self.coeff.len() - zeros;
+        self.coeff.truncate(len)
+    }
+}
+
+/// A symmetric bivariate polynomial in the prime field.
+///
+/// This can be used for Verifiable Secret Sharing and Distributed Key Generation. See the `poly`
+/// module documentation for details.
+pub struct BivarPoly<E: Engine> {
+    /// The polynomial's degree in each of the two variables.
+    pub(crate) degree: usize,
+    /// The coefficients of the polynomial. Coefficient `(i, j)` for `i <= j` is in position
+    /// `j * (j + 1) / 2 + i`.
+    pub(crate) coeff: Vec<E::Fr>,
+}
+
+impl<E: Engine> Clone for BivarPoly<E> {
+    fn clone(&self) -> Self {
+        BivarPoly {
+            degree: self.degree,
+            coeff: self.coeff.clone(),
+        }
+    }
+}
+
+impl<E: Engine> Drop for BivarPoly<E> {
+    fn drop(&mut self) {
+        self.zero_secret();
+    }
+}
+
+/// A debug statement where the `coeff` vector has been redacted.
+impl<E: Engine> Debug for BivarPoly<E> {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.debug_struct("BivarPoly")
+            .field("degree", &self.degree)
+            .field("coeff", &"...")
+            .finish()
+    }
+}
+
+impl<E: Engine> ContainsSecret for BivarPoly<E> {
+    fn secret_memory(&self) -> MemRange {
+        let ptr = self.coeff.as_ptr() as *const E::Fr as *mut u8;
+        let n_bytes = size_of_val(self.coeff.as_slice());
+        MemRange { ptr, n_bytes }
+    }
+}
+impl<E: Engine> BivarPoly<E> {
+    /// Creates a random polynomial.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the degree is too high for the coefficients to fit into a `Vec`.
+    pub fn random<R: Rng>(degree: usize, rng: &mut R) -> Self {
+        BivarPoly::try_random(degree, rng).unwrap_or_else(|e| {
+            panic!(
+                "Failed to create random `BivarPoly` of degree {}: {}",
+                degree, e
+            )
+        })
+    }
+
+    /// Creates a random polynomial.
+    pub fn try_random<R: Rng>(degree: usize, rng: &mut R) -> Result<Self> {
+        let len = coeff_pos(degree, degree)
+            .and_then(|l| l.checked_add(1))
+            .ok_or(Error::DegreeTooHigh)?;
+        let poly = BivarPoly {
+            degree,
+            coeff: (0..len).map(|_| rng.gen()).collect(),
+        };
+        Ok(poly)
+    }
+
+    /// Returns the polynomial's degree; which is the same in both variables.
+    pub fn degree(&self) -> usize {
+        self.degree
+    }
+
+    /// Returns the polynomial's value at the point `(x, y)`.
+    pub fn evaluate<T: IntoFr<E::Fr>>(&self, x: T, y: T) -> E::Fr {
+        let x_pow = self.powers(x);
+        let y_pow = self.powers(y);
+        // TODO: Can we save a few multiplication steps here due to the symmetry?
+        let mut result = E::Fr::zero();
+        for (i, x_pow_i) in x_pow.into_iter().enumerate() {
+            for (j, y_pow_j) in y_pow.iter().enumerate() {
+                let index = coeff_pos(i, j).expect("polynomial degree too high");
+                let mut summand = self.coeff[index];
+                summand.mul_assign(&x_pow_i);
+                summand.mul_assign(y_pow_j);
+                result.add_assign(&summand);
+            }
+        }
+        result
+    }
+
+    /// Returns the `x`-th row, as a univariate polynomial.
+    pub fn row<T: IntoFr<E::Fr>>(&self, x: T) -> Poly<E> {
+        let x_pow = self.powers(x);
+        let coeff: Vec<E::Fr> = (0..=self.degree)
+            .map(|i| {
+                // TODO: clear these secrets from the stack.
+                let mut result = E::Fr::zero();
+                for (j, x_pow_j) in x_pow.iter().enumerate() {
+                    let index = coeff_pos(i, j).expect("polynomial degree too high");
+                    let mut summand = self.coeff[index];
+                    summand.mul_assign(x_pow_j);
+                    result.add_assign(&summand);
+                }
+                result
+            }).collect();
+        Poly::from(coeff)
+    }
+
+    /// Returns the corresponding commitment. That information can be shared publicly.
+    pub fn commitment(&self) -> BivarCommitment<E> {
+        let to_pub = |c: &E::Fr| E::G1Affine::one().mul(*c);
+        BivarCommitment {
+            degree: self.degree,
+            coeff: self.coeff.iter().map(to_pub).collect(),
+        }
+    }
+
+    /// Returns the `0`-th to `degree`-th power of `x`.
+    fn powers<T: IntoFr<E::Fr>>(&self, x: T) -> Vec<E::Fr> {
+        powers(x.into_fr(), self.degree)
+    }
+
+    /// Generates a non-redacted debug string. This method differs from the
+    /// `Debug` implementation in that it *does* leak the the struct's
+    /// internal state.
+    pub fn reveal(&self) -> String {
+        format!(
+            "BivarPoly {{ degree: {}, coeff: {:?} }}",
+            self.degree, self.coeff
+        )
+    }
+}
+
+/// A commitment to a symmetric bivariate polynomial.
+pub struct BivarCommitment<E: Engine> {
+    /// The polynomial's degree in each of the two variables.
+    pub(crate) degree: usize,
+    /// The commitments to the coefficients.
+    pub(crate) coeff: Vec<E::G1>,
+}
+
+impl<E: Engine> Debug for BivarCommitment<E> {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.debug_struct("BivarCommitment")
+            .field("degree", &self.degree)
+            .field("coeff", &self.coeff)
+            .finish()
+    }
+}
+
+impl<E: Engine> Clone for BivarCommitment<E> {
+    fn clone(&self) -> Self {
+        BivarCommitment {
+            degree: self.degree,
+            coeff: self.coeff.clone(),
+        }
+    }
+}
+
+impl<E: Engine> PartialEq for BivarCommitment<E> {
+    fn eq(&self, other: &Self) -> bool {
+        self.degree == other.degree && self.coeff == other.coeff
+    }
+}
+
+impl<E: Engine> Eq for BivarCommitment<E> {}
+
+impl<E: Engine> Hash for BivarCommitment<E> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.degree.hash(state);
+        for c in &self.coeff {
+            c.into_affine().into_compressed().as_ref().hash(state);
+        }
+    }
+}
+
+impl<E: Engine> BivarCommitment<E> {
+    /// Returns the polynomial's degree: It is the same in both variables.
+    pub fn degree(&self) -> usize {
+        self.degree
+    }
+
+    /// Returns the commitment's value at the point `(x, y)`.
+    pub fn evaluate<T: IntoFr<E::Fr>>(&self, x: T, y: T) -> E::G1 {
+        bivar_evaluate_g1(self.degree, &self.coeff, x.into_fr(), y.into_fr())
+    }
+
+    /// Returns the `x`-th row, as a commitment to a univariate polynomial.
+    pub fn row<T: IntoFr<E::Fr>>(&self, x: T) -> Commitment<E> {
+        Commitment {
+            coeff: bivar_row_g1(self.degree, &self.coeff, x.into_fr()),
+        }
+    }
+}
+
+/// Returns the value at `x` of the polynomial with the given coefficients in `G1`.
+///
+/// If `x` is small, e.g. a share index, this uses Horner's method, since a multiplication with `x`
+/// only takes a few group operations. Otherwise, it uses a multi-scalar multiplication with the
+/// powers of `x`.
+pub(crate) fn evaluate_g1<C: CurveProjective>(coeff: &[C], x: C::Scalar) -> C {
+    if coeff.is_empty() {
+        return C::zero();
+    }
+    if !is_small(x) {
+        return multiexp(coeff, &powers(x, coeff.len() - 1));
+    }
+    let mut result = coeff[coeff.len() - 1];
+    for c in coeff.iter().rev().skip(1) {
+        result.mul_assign(x);
+        result.add_assign(c);
+    }
+    result
+}
+
+/// Returns the value at `(x, y)` of the symmetric bivariate polynomial of the given degree, with
+/// the given coefficients in `G1`.
+pub(crate) fn bivar_evaluate_g1<C: CurveProjective>(
+    degree: usize,
+    coeff: &[C],
+    x: C::Scalar,
+    y: C::Scalar,
+) -> C {
+    if is_small(x) && is_small(y) {
+        return evaluate_g1(&bivar_row_g1(degree, coeff, x), y);
+    }
+    let x_pow = powers(x, degree);
+    let y_pow = powers(y, degree);
+    // Due to the symmetry, coefficient `(i, j)` is multiplied with both `x^i y^j` and `x^j y^i`.
+    let mut scalars = Vec::with_capacity(coeff.len());
+    for (j, y_pow_j) in y_pow.iter().enumerate() {
+        for (i, x_pow_i) in x_pow[..=j].iter().enumerate() {
+            let mut scalar = *x_pow_i;
+            scalar.mul_assign(y_pow_j);
+            if i != j {
+                let mut mirrored = x_pow[j];
+                mirrored.mul_assign(&y_pow[i]);
+                scalar.add_assign(&mirrored);
+            }
+            scalars.push(scalar);
+        }
+    }
+    // The coefficients with `i <= j` are stored in the same order as the scalars.
+    multiexp(&coeff[..scalars.len()], &scalars)
+}
+
+/// Returns the coefficients of the `x`-th row of the symmetric bivariate polynomial of the given
+/// degree, with the given coefficients in `G1`.
+pub(crate) fn bivar_row_g1<C: CurveProjective>(degree: usize, coeff: &[C], x: C::Scalar) -> Vec<C> {
+    (0..=degree)
+        .map(|i| {
+            let column: Vec<C> = (0..=degree)
+                .map(|j| coeff[coeff_pos(i, j).expect("polynomial degree too high")])
+                .collect();
+            evaluate_g1(&column, x)
+        }).collect()
+}
+
+/// Returns `true` if `x` has at most `SMALL_SCALAR_BITS` bits.
+fn is_small<F: PrimeField>(x: F) -> bool {
+    x.into_repr().num_bits() <= SMALL_SCALAR_BITS
+}
+
+/// Returns the `0`-th to `degree`-th power of `x`.
+fn powers<F: PrimeField>(x: F, degree: usize) -> Vec<F> {
+    let mut x_pow_i = F::one();
+    iter::once(x_pow_i)
+        .chain((0..degree).map(|_| {
+            x_pow_i.mul_assign(&x);
+            x_pow_i
+        })).collect()
+}
+
+/// Returns the position of coefficient `(i, j)` in the vector describing a symmetric bivariate
+/// polynomial. If `i` or `j` are too large to represent the position as a `usize`, `None` is
+/// returned.
+pub(crate) fn coeff_pos(i: usize, j: usize) -> Option<usize> {
+    // Since the polynomial is symmetric, we can order such that `j >= i`.
+    let (j, i) = if j >= i { (j, i) } else { (i, j) };
+    i.checked_add(j.checked_mul(j.checked_add(1)?)? / 2)
+}
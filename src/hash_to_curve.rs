@@ -0,0 +1,676 @@
+//! Hashing to the groups of BLS12-381, as specified by the IETF hash-to-curve suites
+//! `BLS12381G1_XMD:SHA-256_SSWU_RO_` and `BLS12381G2_XMD:SHA-256_SSWU_RO_`.
+//!
+//! In contrast to `hash_g2`, which seeds a random number generator with the message digest, the
+//! functions in this module follow the
+//! [hash-to-curve draft](https://datatracker.ietf.org/doc/draft-irtf-cfrg-hash-to-curve/), so
+//! their output can be reproduced by other BLS implementations. The message is expanded with
+//! SHA-256 into two field elements, each of which is mapped to an isogenous curve with the
+//! simplified SWU map and then to the target curve. The sum of the two points is finally
+//! multiplied by the effective cofactor, to make sure the result lies in the prime order subgroup.
+//!
+//! Every function takes a _domain separation tag_ (DST): Hashing the same message with different
+//! tags results in unrelated points, so different protocols, or different message types within
+//! the same protocol, should use different tags.
+
+use pairing::bls12_381::{Fq, Fq2, FqRepr, G1Uncompressed, G2Uncompressed};
+use pairing::{
+    CurveAffine, CurveProjective, EncodedPoint, Field, PrimeField, PrimeFieldRepr, SqrtField,
+};
+use sha2::{Digest, Sha256};
+
+use super::{G1, G2};
+
+/// The output size of SHA-256, in bytes.
+const HASH_SIZE: usize = 32;
+/// The input block size of SHA-256, in bytes.
+const BLOCK_SIZE: usize = 64;
+/// The size of a big-endian representation of an element of `Fq`, in bytes.
+const FQ_SIZE: usize = 48;
+/// The number of uniform bytes that are reduced to an element of `Fq`: `ceil((381 + 128) / 8)`.
+const FQ_EXPAND_SIZE: usize = 64;
+/// Domain separation tags longer than this are hashed before use.
+const MAX_DST_SIZE: usize = 255;
+/// The prefix used when hashing overlong domain separation tags.
+const OVERSIZE_DST_PREFIX: &[u8] = b"H2C-OVERSIZE-DST-";
+
+/// The effective cofactor of `G1`, `1 - x`, where `x` is the BLS12-381 curve parameter.
+const G1_H_EFF: [u64; 1] = [0xd201_0000_0001_0001];
+
+/// The effective cofactor of `G2`, as little-endian 64-bit limbs.
+const G2_H_EFF: [u64; 10] = [
+    0xe802_0005_aaa9_5551,
+    0x5989_4c0a_debb_f6b4,
+    0xe954_cbc0_6689_f6a3,
+    0x2ec0_ec69_d747_7c1a,
+    0x6d82_bf01_5d12_12b0,
+    0x329c_2f17_8731_db95,
+    0x9986_ff03_1508_ffe1,
+    0x88e2_a8e9_145a_d768,
+    0x584c_6a0e_a91b_3528,
+    0x0bc6_9f08_f2ee_75b3,
+];
+
+lazy_static! {
+    /// The number `2^256` as an element of `Fq`.
+    static ref FQ_2_POW_256: Fq =
+        Fq::from_repr(FqRepr([0, 0, 0, 0, 1, 0])).expect("2^256 is less than the modulus");
+
+    /// The curve that is 11-isogenous to the curve of `G1`, and the isogeny.
+    static ref ISO_G1: IsoCurve<Fq> = IsoCurve {
+        a: fq("12190336318893619529228877361869031420615612348429846051986726275283378313155663745811710833465465981901188123677"),
+        b: fq("2906670324641927570491258158026293881577086121416628140204402091718288198173574630967936031029026176254968826637280"),
+        z: fq("11"),
+        x_num: fq_vec(&ISO11_X_NUM),
+        x_den: fq_vec(&ISO11_X_DEN),
+        y_num: fq_vec(&ISO11_Y_NUM),
+        y_den: fq_vec(&ISO11_Y_DEN),
+    };
+
+    /// The curve that is 3-isogenous to the curve of `G2`, and the isogeny.
+    static ref ISO_G2: IsoCurve<Fq2> = IsoCurve {
+        a: fq2("0", "240"),
+        b: fq2("1012", "1012"),
+        z: {
+            let mut z = fq2("2", "1");
+            z.negate();
+            z
+        },
+        x_num: fq2_vec(&ISO3_X_NUM),
+        x_den: fq2_vec(&ISO3_X_DEN),
+        y_num: fq2_vec(&ISO3_Y_NUM),
+        y_den: fq2_vec(&ISO3_Y_DEN),
+    };
+}
+
+/// Hashes the message to a point in `G1`, using the suite `BLS12381G1_XMD:SHA-256_SSWU_RO_` with
+/// the given domain separation tag.
+pub fn hash_to_g1<M: AsRef<[u8]>, D: AsRef<[u8]>>(msg: M, dst: D) -> G1 {
+    let u = hash_to_fq(msg.as_ref(), dst.as_ref(), 2);
+    let mut point = g1_from_coords(ISO_G1.map_to_curve(&u[0]));
+    point.add_assign(&g1_from_coords(ISO_G1.map_to_curve(&u[1])));
+    mul_by_limbs(&point, &G1_H_EFF)
+}
+
+/// Hashes the message to a point in `G2`, using the suite `BLS12381G2_XMD:SHA-256_SSWU_RO_` with
+/// the given domain separation tag.
+pub fn hash_to_g2<M: AsRef<[u8]>, D: AsRef<[u8]>>(msg: M, dst: D) -> G2 {
+    let u = hash_to_fq(msg.as_ref(), dst.as_ref(), 4);
+    let u0 = Fq2 { c0: u[0], c1: u[1] };
+    let u1 = Fq2 { c0: u[2], c1: u[3] };
+    let mut point = g2_from_coords(ISO_G2.map_to_curve(&u0));
+    point.add_assign(&g2_from_coords(ISO_G2.map_to_curve(&u1)));
+    mul_by_limbs(&point, &G2_H_EFF)
+}
+
+/// A field over which the simplified SWU map is defined.
+trait SswuField: SqrtField {
+    /// Returns the "sign" of the element, as defined in the hash-to-curve draft.
+    fn sgn0(&self) -> bool;
+}
+
+impl SswuField for Fq {
+    fn sgn0(&self) -> bool {
+        self.into_repr().is_odd()
+    }
+}
+
+impl SswuField for Fq2 {
+    fn sgn0(&self) -> bool {
+        self.c0.sgn0() || (self.c0.is_zero() && self.c1.sgn0())
+    }
+}
+
+/// A curve `y^2 = x^3 + a * x + b` together with an isogeny to one of the pairing groups' curves.
+struct IsoCurve<F> {
+    /// The coefficient of `x`.
+    a: F,
+    /// The constant coefficient.
+    b: F,
+    /// The non-square constant used by the simplified SWU map.
+    z: F,
+    /// The coefficients of the isogeny's rational maps, lowest degree first.
+    x_num: Vec<F>,
+    x_den: Vec<F>,
+    y_num: Vec<F>,
+    y_den: Vec<F>,
+}
+
+impl<F: SswuField> IsoCurve<F> {
+    /// Maps the field element to the affine coordinates of a point on the target curve, or `None`
+    /// if the result is the point at infinity.
+    fn map_to_curve(&self, u: &F) -> Option<(F, F)> {
+        let (x, y) = self.sswu(u);
+        self.isogeny(&x, &y)
+    }
+
+    /// Maps the field element to a point on this curve, using the simplified Shallue-van de
+    /// Woestijne-Ulas method.
+    fn sswu(&self, u: &F) -> (F, F) {
+        // `z_u2 = Z * u^2` and `tv1 = Z^2 * u^4 + Z * u^2`
+        let mut z_u2 = *u;
+        z_u2.square();
+        z_u2.mul_assign(&self.z);
+        let mut tv1 = z_u2;
+        tv1.square();
+        tv1.add_assign(&z_u2);
+        let a_inv = self.a.inverse().expect("coefficient a is nonzero");
+        let x1 = match tv1.inverse() {
+            // `x1 = -B / A * (1 + 1 / tv1)`
+            Some(mut x1) => {
+                x1.add_assign(&F::one());
+                x1.mul_assign(&self.b);
+                x1.mul_assign(&a_inv);
+                x1.negate();
+                x1
+            }
+            // Exceptional case: `x1 = B / (Z * A)`
+            None => {
+                let mut x1 = self.z.inverse().expect("Z is nonzero");
+                x1.mul_assign(&self.b);
+                x1.mul_assign(&a_inv);
+                x1
+            }
+        };
+        // Either `g(x1)` or `g(Z * u^2 * x1)` is a square.
+        let (x, mut y) = match self.rhs(&x1).sqrt() {
+            Some(y1) => (x1, y1),
+            None => {
+                let mut x2 = z_u2;
+                x2.mul_assign(&x1);
+                let y2 = self
+                    .rhs(&x2)
+                    .sqrt()
+                    .expect("g(x2) is a square if g(x1) isn't");
+                (x2, y2)
+            }
+        };
+        if u.sgn0() != y.sgn0() {
+            y.negate();
+        }
+        (x, y)
+    }
+
+    /// Returns `x^3 + a * x + b`.
+    fn rhs(&self, x: &F) -> F {
+        let mut result = *x;
+        result.square();
+        result.add_assign(&self.a);
+        result.mul_assign(x);
+        result.add_assign(&self.b);
+        result
+    }
+
+    /// Applies the isogeny to the point `(x, y)` on this curve. Returns `None` if the result is
+    /// the point at infinity.
+    fn isogeny(&self, x: &F, y: &F) -> Option<(F, F)> {
+        let x_den_inv = horner(&self.x_den, x).inverse()?;
+        let y_den_inv = horner(&self.y_den, x).inverse()?;
+        let mut x_out = horner(&self.x_num, x);
+        x_out.mul_assign(&x_den_inv);
+        let mut y_out = horner(&self.y_num, x);
+        y_out.mul_assign(&y_den_inv);
+        y_out.mul_assign(y);
+        Some((x_out, y_out))
+    }
+}
+
+/// Evaluates the polynomial with the given coefficients, lowest degree first, at `x`.
+fn horner<F: Field>(coeffs: &[F], x: &F) -> F {
+    let mut result = F::zero();
+    for c in coeffs.iter().rev() {
+        result.mul_assign(x);
+        result.add_assign(c);
+    }
+    result
+}
+
+/// Returns the `G1` element with the given affine coordinates, or zero if `coords` is `None`.
+fn g1_from_coords(coords: Option<(Fq, Fq)>) -> G1 {
+    let (x, y) = match coords {
+        None => return G1::zero(),
+        Some(coords) => coords,
+    };
+    let mut encoded = G1Uncompressed::empty();
+    encoded.as_mut().copy_from_slice(&fq_to_bytes(&[x, y]));
+    let affine = encoded.into_affine_unchecked();
+    affine
+        .expect("mapped point is on the curve")
+        .into_projective()
+}
+
+/// Returns the `G2` element with the given affine coordinates, or zero if `coords` is `None`.
+fn g2_from_coords(coords: Option<(Fq2, Fq2)>) -> G2 {
+    let (x, y) = match coords {
+        None => return G2::zero(),
+        Some(coords) => coords,
+    };
+    let mut encoded = G2Uncompressed::empty();
+    encoded
+        .as_mut()
+        .copy_from_slice(&fq_to_bytes(&[x.c1, x.c0, y.c1, y.c0]));
+    let affine = encoded.into_affine_unchecked();
+    affine
+        .expect("mapped point is on the curve")
+        .into_projective()
+}
+
+/// Returns the concatenated big-endian representations of the field elements.
+fn fq_to_bytes(elements: &[Fq]) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(elements.len() * FQ_SIZE);
+    for element in elements {
+        element
+            .into_repr()
+            .write_be(&mut bytes)
+            .expect("writing to a vector never fails");
+    }
+    bytes
+}
+
+/// Returns the product of `point` with the integer given by the little-endian 64-bit `limbs`.
+///
+/// Unlike `CurveProjective::mul_assign`, this accepts factors greater than the group order.
+fn mul_by_limbs<C: CurveProjective>(point: &C, limbs: &[u64]) -> C {
+    let mut result = C::zero();
+    for limb in limbs.iter().rev() {
+        for i in (0..64).rev() {
+            result.double();
+            if (limb >> i) & 1 == 1 {
+                result.add_assign(point);
+            }
+        }
+    }
+    result
+}
+
+/// Hashes the message to `count` elements of `Fq`.
+fn hash_to_fq(msg: &[u8], dst: &[u8], count: usize) -> Vec<Fq> {
+    expand_message_xmd(msg, dst, count * FQ_EXPAND_SIZE)
+        .chunks(FQ_EXPAND_SIZE)
+        .map(fq_from_wide_bytes)
+        .collect()
+}
+
+/// Interprets `FQ_EXPAND_SIZE` bytes as a big-endian integer, and reduces it modulo `q`.
+fn fq_from_wide_bytes(bytes: &[u8]) -> Fq {
+    let (high, low) = bytes.split_at(FQ_EXPAND_SIZE / 2);
+    let mut result = fq_from_short_bytes(high);
+    result.mul_assign(&FQ_2_POW_256);
+    result.add_assign(&fq_from_short_bytes(low));
+    result
+}
+
+/// Interprets at most 32 bytes as a big-endian integer. That is always less than the modulus, so
+/// no reduction is needed.
+fn fq_from_short_bytes(bytes: &[u8]) -> Fq {
+    let mut buf = [0u8; FQ_SIZE];
+    buf[(FQ_SIZE - bytes.len())..].copy_from_slice(bytes);
+    let mut repr = FqRepr::default();
+    repr.read_be(&buf[..])
+        .expect("buffer has the size of a representation");
+    Fq::from_repr(repr).expect("value is less than 2^256")
+}
+
+/// Expands the message and domain separation tag to `len` pseudorandom bytes, using SHA-256.
+///
+/// # Panics
+///
+/// Panics if `len` is greater than `255 * 32`.
+fn expand_message_xmd(msg: &[u8], dst: &[u8], len: usize) -> Vec<u8> {
+    let ell = len.div_ceil(HASH_SIZE);
+    assert!(
+        ell <= 255,
+        "too many bytes requested from expand_message_xmd"
+    );
+    let hashed_dst;
+    let dst = if dst.len() > MAX_DST_SIZE {
+        hashed_dst = sha256(&[OVERSIZE_DST_PREFIX, dst]);
+        &hashed_dst[..]
+    } else {
+        dst
+    };
+    let mut dst_prime = dst.to_vec();
+    dst_prime.push(dst.len() as u8);
+    let len_bytes = [(len >> 8) as u8, len as u8];
+
+    let b_0 = sha256(&[
+        &[0u8; BLOCK_SIZE][..],
+        msg,
+        &len_bytes[..],
+        &[0][..],
+        &dst_prime[..],
+    ]);
+    let mut b_i = sha256(&[&b_0[..], &[1][..], &dst_prime[..]]);
+    let mut result = Vec::with_capacity(ell * HASH_SIZE);
+    result.extend_from_slice(&b_i);
+    for i in 2..=ell {
+        let xor: Vec<u8> = b_0.iter().zip(&b_i).map(|(a, b)| a ^ b).collect();
+        b_i = sha256(&[&xor[..], &[i as u8][..], &dst_prime[..]]);
+        result.extend_from_slice(&b_i);
+    }
+    result.truncate(len);
+    result
+}
+
+/// Returns the SHA-256 digest of the concatenation of `parts`.
+fn sha256(parts: &[&[u8]]) -> Vec<u8> {
+    let mut hasher = Sha256::new();
+    for part in parts {
+        hasher.input(part);
+    }
+    hasher.result().to_vec()
+}
+
+/// Parses a decimal representation of an element of `Fq`.
+fn fq(s: &str) -> Fq {
+    Fq::from_str(s).expect("valid field element constant")
+}
+
+/// Parses the decimal representations of the two coefficients of an element of `Fq2`.
+fn fq2(c0: &str, c1: &str) -> Fq2 {
+    Fq2 {
+        c0: fq(c0),
+        c1: fq(c1),
+    }
+}
+
+fn fq_vec(consts: &[&str]) -> Vec<Fq> {
+    consts.iter().map(|s| fq(s)).collect()
+}
+
+fn fq2_vec(consts: &[(&str, &str)]) -> Vec<Fq2> {
+    consts.iter().map(|(c0, c1)| fq2(c0, c1)).collect()
+}
+
+// The coefficients of the isogeny maps, lowest degree first, as listed in the hash-to-curve draft.
+const ISO11_X_NUM: [&str; 12] = [
+    "2712959285290305970661081772124144179193819192423276218370281158706191519995889425075952244140278856085036081760695",
+    "3564859427549639835253027846704205725951033235539816243131874237388832081954622352624080767121604606753339903542203",
+    "2051387046688339481714726479723076305756384619135044672831882917686431912682625619320120082313093891743187631791280",
+    "3612713941521031012780325893181011392520079402153354595775735142359240110423346445050803899623018402874731133626465",
+    "2247053637822768981792833880270996398470828564809439728372634811976089874056583714987807553397615562273407692740057",
+    "3415427104483187489859740871640064348492611444552862448295571438270821994900526625562705192993481400731539293415811",
+    "2067521456483432583860405634125513059912765526223015704616050604591207046392807563217109432457129564962571408764292",
+    "3650721292069012982822225637849018828271936405382082649291891245623305084633066170122780668657208923883092359301262",
+    "1239271775787030039269460763652455868148971086016832054354147730155061349388626624328773377658494412538595239256855",
+    "3479374185711034293956731583912244564891370843071137483962415222733470401948838363051960066766720884717833231600798",
+    "2492756312273161536685660027440158956721981129429869601638362407515627529461742974364729223659746272460004902959995",
+    "1058488477413994682556770863004536636444795456512795473806825292198091015005841418695586811009326456605062948114985",
+];
+
+const ISO11_X_DEN: [&str; 11] = [
+    "1353092447850172218905095041059784486169131709710991428415161466575141675351394082965234118340787683181925558786844",
+    "2822220997908397120956501031591772354860004534930174057793539372552395729721474912921980407622851861692773516917759",
+    "1717937747208385987946072944131378949849282930538642983149296304709633281382731764122371874602115081850953846504985",
+    "501624051089734157816582944025690868317536915684467868346388760435016044027032505306995281054569109955275640941784",
+    "3025903087998593826923738290305187197829899948335370692927241015584233559365859980023579293766193297662657497834014",
+    "2224140216975189437834161136818943039444741035168992629437640302964164227138031844090123490881551522278632040105125",
+    "1146414465848284837484508420047674663876992808692209238763293935905506532411661921697047880549716175045414621825594",
+    "3179090966864399634396993677377903383656908036827452986467581478509513058347781039562481806409014718357094150199902",
+    "1549317016540628014674302140786462938410429359529923207442151939696344988707002602944342203885692366490121021806145",
+    "1442797143427491432630626390066422021593505165588630398337491100088557278058060064930663878153124164818522816175370",
+    "1",
+];
+
+const ISO11_Y_NUM: [&str; 16] = [
+    "1393399195776646641963150658816615410692049723305861307490980409834842911816308830479576739332720113414154429643571",
+    "2968610969752762946134106091152102846225411740689724909058016729455736597929366401532929068084731548131227395540630",
+    "122933100683284845219599644396874530871261396084070222155796123161881094323788483360414289333111221370374027338230",
+    "303251954782077855462083823228569901064301365507057490567314302006681283228886645653148231378803311079384246777035",
+    "1353972356724735644398279028378555627591260676383150667237975415318226973994509601413730187583692624416197017403099",
+    "3443977503653895028417260979421240655844034880950251104724609885224259484262346958661845148165419691583810082940400",
+    "718493410301850496156792713845282235942975872282052335612908458061560958159410402177452633054233549648465863759602",
+    "1466864076415884313141727877156167508644960317046160398342634861648153052436926062434809922037623519108138661903145",
+    "1536886493137106337339531461344158973554574987550750910027365237255347020572858445054025958480906372033954157667719",
+    "2171468288973248519912068884667133903101171670397991979582205855298465414047741472281361964966463442016062407908400",
+    "3915937073730221072189646057898966011292434045388986394373682715266664498392389619761133407846638689998746172899634",
+    "3802409194827407598156407709510350851173404795262202653149767739163117554648574333789388883640862266596657730112910",
+    "1707589313757812493102695021134258021969283151093981498394095062397393499601961942449581422761005023512037430861560",
+    "349697005987545415860583335313370109325490073856352967581197273584891698473628451945217286148025358795756956811571",
+    "885704436476567581377743161796735879083481447641210566405057346859953524538988296201011389016649354976986251207243",
+    "3370924952219000111210625390420697640496067348723987858345031683392215988129398381698161406651860675722373763741188",
+];
+
+const ISO11_Y_DEN: [&str; 16] = [
+    "3396434800020507717552209507749485772788165484415495716688989613875369612529138640646200921379825018840894888371137",
+    "3907278185868397906991868466757978732688957419873771881240086730384895060595583602347317992689443299391009456758845",
+    "854914566454823955479427412036002165304466268547334760894270240966182605542146252771872707010378658178126128834546",
+    "3496628876382137961119423566187258795236027183112131017519536056628828830323846696121917502443333849318934945158166",
+    "1828256966233331991927609917644344011503610008134915752990581590799656305331275863706710232159635159092657073225757",
+    "1362317127649143894542621413133849052553333099883364300946623208643344298804722863920546222860227051989127113848748",
+    "3443845896188810583748698342858554856823966611538932245284665132724280883115455093457486044009395063504744802318172",
+    "3484671274283470572728732863557945897902920439975203610275006103818288159899345245633896492713412187296754791689945",
+    "3755735109429418587065437067067640634211015783636675372165599470771975919172394156249639331555277748466603540045130",
+    "3459661102222301807083870307127272890283709299202626530836335779816726101522661683404130556379097384249447658110805",
+    "742483168411032072323733249644347333168432665415341249073150659015707795549260947228694495111018381111866512337576",
+    "1662231279858095762833829698537304807741442669992646287950513237989158777254081548205552083108208170765474149568658",
+    "1668238650112823419388205992952852912407572045257706138925379268508860023191233729074751042562151098884528280913356",
+    "369162719928976119195087327055926326601627748362769544198813069133429557026740823593067700396825489145575282378487",
+    "2164195715141237148945939585099633032390257748382945597506236650132835917087090097395995817229686247227784224263055",
+    "1",
+];
+
+const ISO3_X_NUM: [(&str, &str); 4] = [
+    (
+        "889424345604814976315064405719089812568196182208668418962679585805340366775741747653930584250892369786198727235542",
+        "889424345604814976315064405719089812568196182208668418962679585805340366775741747653930584250892369786198727235542",
+    ),
+    (
+        "0",
+        "2668273036814444928945193217157269437704588546626005256888038757416021100327225242961791752752677109358596181706522",
+    ),
+    (
+        "2668273036814444928945193217157269437704588546626005256888038757416021100327225242961791752752677109358596181706526",
+        "1334136518407222464472596608578634718852294273313002628444019378708010550163612621480895876376338554679298090853261",
+    ),
+    (
+        "3557697382419259905260257622876359250272784728834673675850718343221361467102966990615722337003569479144794908942033",
+        "0",
+    ),
+];
+
+const ISO3_X_DEN: [(&str, &str); 3] = [
+    (
+        "0",
+        "4002409555221667393417789825735904156556882819939007885332058136124031650490837864442687629129015664037894272559715",
+    ),
+    (
+        "12",
+        "4002409555221667393417789825735904156556882819939007885332058136124031650490837864442687629129015664037894272559775",
+    ),
+    (
+        "1",
+        "0",
+    ),
+];
+
+const ISO3_Y_NUM: [(&str, &str); 4] = [
+    (
+        "3261222600550988246488569487636662646083386001431784202863158481286248011511053074731078808919938689216061999863558",
+        "3261222600550988246488569487636662646083386001431784202863158481286248011511053074731078808919938689216061999863558",
+    ),
+    (
+        "0",
+        "889424345604814976315064405719089812568196182208668418962679585805340366775741747653930584250892369786198727235518",
+    ),
+    (
+        "2668273036814444928945193217157269437704588546626005256888038757416021100327225242961791752752677109358596181706524",
+        "1334136518407222464472596608578634718852294273313002628444019378708010550163612621480895876376338554679298090853263",
+    ),
+    (
+        "2816510427748580758331037284777117739799287910327449993381818688383577828123182200904113516794492504322962636245776",
+        "0",
+    ),
+];
+
+const ISO3_Y_DEN: [(&str, &str); 4] = [
+    (
+        "4002409555221667393417789825735904156556882819939007885332058136124031650490837864442687629129015664037894272559355",
+        "4002409555221667393417789825735904156556882819939007885332058136124031650490837864442687629129015664037894272559355",
+    ),
+    (
+        "0",
+        "4002409555221667393417789825735904156556882819939007885332058136124031650490837864442687629129015664037894272559571",
+    ),
+    (
+        "18",
+        "4002409555221667393417789825735904156556882819939007885332058136124031650490837864442687629129015664037894272559769",
+    ),
+    (
+        "1",
+        "0",
+    ),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::{expand_message_xmd, hash_to_g1, hash_to_g2};
+
+    use pairing::{CurveAffine, CurveProjective, EncodedPoint};
+
+    /// The domain separation tags used by the test vectors in the hash-to-curve draft.
+    const G1_DST: &str = "QUUX-V01-CS02-with-BLS12381G1_XMD:SHA-256_SSWU_RO_";
+    const G2_DST: &str = "QUUX-V01-CS02-with-BLS12381G2_XMD:SHA-256_SSWU_RO_";
+
+    // Test vectors from the hash-to-curve draft: The message is given as a prefix, followed by a
+    // string repeated a number of times, and the result as its uncompressed encoding.
+    const G1_VECTORS: &[((&str, &str, usize), &str)] = &[
+        (
+            ("", "", 0),
+            concat!(
+                "052926add2207b76ca4fa57a8734416c8dc95e24501772c814278700eed6d1e4e8cf62d9c09db0fac349612b759e79a1",
+                "08ba738453bfed09cb546dbb0783dbb3a5f1f566ed67bb6be0e8c67e2e81a4cc68ee29813bb7994998f3eae0c9c6a265",
+            ),
+        ),
+        (
+            ("abc", "", 0),
+            concat!(
+                "03567bc5ef9c690c2ab2ecdf6a96ef1c139cc0b2f284dca0a9a7943388a49a3aee664ba5379a7655d3c68900be2f6903",
+                "0b9c15f3fe6e5cf4211f346271d7b01c8f3b28be689c8429c85b67af215533311f0b8dfaaa154fa6b88176c229f2885d",
+            ),
+        ),
+        (
+            ("abcdef0123456789", "", 0),
+            concat!(
+                "11e0b079dea29a68f0383ee94fed1b940995272407e3bb916bbf268c263ddd57a6a27200a784cbc248e84f357ce82d98",
+                "03a87ae2caf14e8ee52e51fa2ed8eefe80f02457004ba4d486d6aa1f517c0889501dc7413753f9599b099ebcbbd2d709",
+            ),
+        ),
+        (
+            ("q128_", "q", 128),
+            concat!(
+                "15f68eaa693b95ccb85215dc65fa81038d69629f70aeee0d0f677cf22285e7bf58d7cb86eefe8f2e9bc3f8cb84fac488",
+                "1807a1d50c29f430b8cafc4f8638dfeeadf51211e1602a5f184443076715f91bb90a48ba1e370edce6ae1062f5e6dd38",
+            ),
+        ),
+        (
+            ("a512_", "a", 512),
+            concat!(
+                "082aabae8b7dedb0e78aeb619ad3bfd9277a2f77ba7fad20ef6aabdc6c31d19ba5a6d12283553294c1825c4b3ca2dcfe",
+                "05b84ae5a942248eea39e1d91030458c40153f3b654ab7872d779ad1e942856a20c438e8d99bc8abfbf74729ce1f7ac8",
+            ),
+        ),
+    ];
+    const G2_VECTORS: &[((&str, &str, usize), &str)] = &[
+        (
+            ("", "", 0),
+            concat!(
+                "05cb8437535e20ecffaef7752baddf98034139c38452458baeefab379ba13dff5bf5dd71b72418717047f5b0f37da03d",
+                "0141ebfbdca40eb85b87142e130ab689c673cf60f1a3e98d69335266f30d9b8d4ac44c1038e9dcdd5393faf5c41fb78a",
+                "12424ac32561493f3fe3c260708a12b7c620e7be00099a974e259ddc7d1f6395c3c811cdd19f1e8dbf3e9ecfdcbab8d6",
+                "0503921d7f6a12805e72940b963c0cf3471c7b2a524950ca195d11062ee75ec076daf2d4bc358c4b190c0c98064fdd92",
+            ),
+        ),
+        (
+            ("abc", "", 0),
+            concat!(
+                "139cddbccdc5e91b9623efd38c49f81a6f83f175e80b06fc374de9eb4b41dfe4ca3a230ed250fbe3a2acf73a41177fd8",
+                "02c2d18e033b960562aae3cab37a27ce00d80ccd5ba4b7fe0e7a210245129dbec7780ccc7954725f4168aff2787776e6",
+                "00aa65dae3c8d732d10ecd2c50f8a1baf3001578f71c694e03866e9f3d49ac1e1ce70dd94a733534f106d4cec0eddd16",
+                "1787327b68159716a37440985269cf584bcb1e621d3a7202be6ea05c4cfe244aeb197642555a0645fb87bf7466b2ba48",
+            ),
+        ),
+        (
+            ("abcdef0123456789", "", 0),
+            concat!(
+                "190d119345b94fbd15497bcba94ecf7db2cbfd1e1fe7da034d26cbba169fb3968288b3fafb265f9ebd380512a71c3f2c",
+                "121982811d2491fde9ba7ed31ef9ca474f0e1501297f68c298e9f4c0028add35aea8bb83d53c08cfc007c1e005723cd0",
+                "0bb5e7572275c567462d91807de765611490205a941a5a6af3b1691bfe596c31225d3aabdf15faff860cb4ef17c7c3be",
+                "05571a0f8d3c08d094576981f4a3b8eda0a8e771fcdcc8ecceaf1356a6acf17574518acb506e435b639353c2e14827c8",
+            ),
+        ),
+        (
+            ("q128_", "q", 128),
+            concat!(
+                "0934aba516a52d8ae479939a91998299c76d39cc0c035cd18813bec433f587e2d7a4fef038260eef0cef4d02aae3eb91",
+                "19a84dd7248a1066f737cc34502ee5555bd3c19f2ecdb3c7d9e24dc65d4e25e50d83f0f77105e955d78f4762d33c17da",
+                "09bcccfa036b4847c9950780733633f13619994394c23ff0b32fa6b795844f4a0673e20282d07bc69641cee04f5e5662",
+                "14f81cd421617428bc3b9fe25afbb751d934a00493524bc4e065635b0555084dd54679df1536101b2c979c0152d09192",
+            ),
+        ),
+        (
+            ("a512_", "a", 512),
+            concat!(
+                "11fca2ff525572795a801eed17eb12785887c7b63fb77a42be46ce4a34131d71f7a73e95fee3f812aea3de78b4d01569",
+                "01a6ba2f9a11fa5598b2d8ace0fbe0a0eacb65deceb476fbbcb64fd24557c2f4b18ecfc5663e54ae16a84f5ab7f62534",
+                "03a47f8e6d1763ba0cad63d6114c0accbef65707825a511b251a660a9b3994249ae4e63fac38b23da0c398689ee2ab52",
+                "0b6798718c8aed24bc19cb27f866f1c9effcdbf92397ad6448b5c9db90d2b9da6cbabf48adc1adf59a1a28344e79d57e",
+            ),
+        ),
+    ];
+
+    /// Decodes a hexadecimal string.
+    fn from_hex(hex: &str) -> Vec<u8> {
+        (0..hex.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&hex[i..(i + 2)], 16).expect("valid hex string"))
+            .collect()
+    }
+
+    fn check_vectors<C, H>(dst: &str, vectors: &[((&str, &str, usize), &str)], hash: H)
+    where
+        C: CurveProjective,
+        H: Fn(&[u8], &[u8]) -> C,
+    {
+        for &((prefix, repeated, count), expected_hex) in vectors {
+            let msg = format!("{}{}", prefix, repeated.repeat(count));
+            let mut encoded = <C::Affine as CurveAffine>::Uncompressed::empty();
+            encoded.as_mut().copy_from_slice(&from_hex(expected_hex));
+            let expected = encoded.into_affine().expect("valid test vector");
+            let actual = hash(msg.as_bytes(), dst.as_bytes()).into_affine();
+            assert_eq!(expected, actual, "wrong hash for message {:?}", msg);
+        }
+    }
+
+    #[test]
+    fn test_hash_to_g1_vectors() {
+        check_vectors(G1_DST, G1_VECTORS, |msg, dst| hash_to_g1(msg, dst));
+    }
+
+    #[test]
+    fn test_hash_to_g2_vectors() {
+        check_vectors(G2_DST, G2_VECTORS, |msg, dst| hash_to_g2(msg, dst));
+    }
+
+    #[test]
+    fn test_domain_separation() {
+        let msg = b"Totally real news";
+        assert_eq!(hash_to_g2(msg, "DST-A"), hash_to_g2(msg, "DST-A"));
+        assert_ne!(hash_to_g2(msg, "DST-A"), hash_to_g2(msg, "DST-B"));
+        assert_ne!(hash_to_g1(msg, "DST-A"), hash_to_g1(msg, "DST-B"));
+    }
+
+    #[test]
+    fn test_expand_message_xmd() {
+        let long_dst = [b'x'; 300];
+        assert_eq!(100, expand_message_xmd(b"abc", b"DST", 100).len());
+        assert_eq!(
+            expand_message_xmd(b"abc", &long_dst[..], 64),
+            expand_message_xmd(b"abc", &long_dst[..], 64)
+        );
+        assert_ne!(
+            expand_message_xmd(b"abc", b"DST", 64)[..32],
+            expand_message_xmd(b"abd", b"DST", 64)[..32]
+        );
+    }
+}
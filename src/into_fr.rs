@@ -1,9 +1,21 @@
 use super::Fr;
-use pairing::{Field, PrimeField};
+use error::ShareIndex;
+use pairing::{Field, PrimeField, PrimeFieldRepr};
 
-/// A conversion into an element of the field `Fr`.
-pub trait IntoFr: Copy {
-    fn into_fr(self) -> Fr;
+/// A conversion into an element of the prime field `F`, by default `Fr`.
+pub trait IntoFr<F: PrimeField = Fr>: Copy {
+    fn into_fr(self) -> F;
+
+    /// Returns the index as it is reported in errors. Unless it is an integer, this is the field
+    /// element's big-endian representation.
+    fn into_share_index(self) -> ShareIndex {
+        let mut bytes = Vec::new();
+        self.into_fr()
+            .into_repr()
+            .write_be(&mut bytes)
+            .expect("writing to a vector never fails");
+        ShareIndex::Field(bytes)
+    }
 }
 
 impl IntoFr for Fr {
@@ -12,44 +24,75 @@ impl IntoFr for Fr {
     }
 }
 
-impl IntoFr for u64 {
-    fn into_fr(self) -> Fr {
-        Fr::from_repr(self.into()).expect("modulus is greater than u64::MAX")
+impl<F: PrimeField> IntoFr<F> for u64 {
+    fn into_fr(self) -> F {
+        F::from_repr(self.into()).expect("modulus is greater than u64::MAX")
+    }
+
+    fn into_share_index(self) -> ShareIndex {
+        ShareIndex::Int(i128::from(self))
     }
 }
 
-impl IntoFr for usize {
-    fn into_fr(self) -> Fr {
+impl<F: PrimeField> IntoFr<F> for usize {
+    fn into_fr(self) -> F {
         (self as u64).into_fr()
     }
+
+    fn into_share_index(self) -> ShareIndex {
+        ShareIndex::Int(self as i128)
+    }
 }
 
-impl IntoFr for i32 {
-    fn into_fr(self) -> Fr {
+impl<F: PrimeField> IntoFr<F> for i32 {
+    fn into_fr(self) -> F {
         if self >= 0 {
             (self as u64).into_fr()
         } else {
-            let mut result = ((-self) as u64).into_fr();
+            let mut result: F = ((-self) as u64).into_fr();
             result.negate();
             result
         }
     }
+
+    fn into_share_index(self) -> ShareIndex {
+        ShareIndex::Int(i128::from(self))
+    }
 }
 
-impl IntoFr for i64 {
-    fn into_fr(self) -> Fr {
+impl<F: PrimeField> IntoFr<F> for i64 {
+    fn into_fr(self) -> F {
         if self >= 0 {
             (self as u64).into_fr()
         } else {
-            let mut result = ((-self) as u64).into_fr();
+            let mut result: F = ((-self) as u64).into_fr();
             result.negate();
             result
         }
     }
+
+    fn into_share_index(self) -> ShareIndex {
+        ShareIndex::Int(i128::from(self))
+    }
 }
 
-impl<'a, T: IntoFr> IntoFr for &'a T {
-    fn into_fr(self) -> Fr {
+impl<'a, F: PrimeField, T: IntoFr<F>> IntoFr<F> for &'a T {
+    fn into_fr(self) -> F {
         (*self).into_fr()
     }
+
+    fn into_share_index(self) -> ShareIndex {
+        (*self).into_share_index()
+    }
+}
+
+/// Interprets the bytes as a big-endian integer, and reduces it modulo `r`.
+pub(crate) fn fr_from_bytes_mod_r(bytes: &[u8]) -> Fr {
+    let base: Fr = 256u64.into_fr();
+    let mut result = Fr::zero();
+    for byte in bytes {
+        result.mul_assign(&base);
+        result.add_assign(&u64::from(*byte).into_fr());
+    }
+    result
 }
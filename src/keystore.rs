@@ -0,0 +1,444 @@
+//! Password-encrypted storage of secret keys and secret key shares.
+//!
+//! The format is similar to [EIP-2335](https://eips.ethereum.org/EIPS/eip-2335): The key is
+//! encrypted with ChaCha20-Poly1305, under a key derived from the password with scrypt. The
+//! keystore contains the public key, and for a secret key share also its index, the threshold and
+//! a hash of the `PublicKeySet`. All of this metadata is authenticated as associated data, so it
+//! can't be modified without invalidating the keystore, and the decrypted key is checked against
+//! the public key.
+//!
+//! The `Keystore` implements `Serialize` and `Deserialize`, so it can be written to disk in any
+//! format that `serde` supports.
+
+use byteorder::{BigEndian, ByteOrder};
+use chacha20poly1305::aead::{Aead, KeyInit, Payload};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use rand::Rng;
+use scrypt::{scrypt, Params};
+use tiny_keccak::sha3_256;
+
+use generic;
+use {PublicKey, PublicKeySet, SecretBytes, SecretKey, SecretKeyShare};
+
+/// The version of the keystore format that is created by this implementation.
+pub const KEYSTORE_VERSION: u32 = 1;
+
+/// The length of the random salt for the key derivation, in bytes.
+const SALT_LEN: usize = 32;
+/// The length of the derived encryption key, in bytes.
+const KEY_LEN: usize = 32;
+/// The length of the ChaCha20-Poly1305 nonce, in bytes.
+const NONCE_LEN: usize = 12;
+/// The maximum binary logarithm of scrypt's `n`: four times the default `n`.
+const MAX_LOG_N: u8 = 20;
+/// The maximum scrypt block size: four times the default `r`.
+const MAX_R: u32 = 32;
+/// The maximum `2^log_n * r`: four times the default, i.e. at most 1 GiB of memory.
+const MAX_COST: u64 = 4 << (18 + 3);
+/// The maximum scrypt parallelization parameter: four times the default `p`.
+const MAX_P: u32 = 4;
+
+/// A keystore error.
+#[derive(Clone, Copy, Eq, PartialEq, Debug, Fail)]
+pub enum KeystoreError {
+    #[fail(display = "Unsupported keystore version: {}", _0)]
+    UnsupportedVersion(u32),
+    #[fail(display = "Invalid key derivation parameters")]
+    InvalidKdfParams,
+    #[fail(display = "Decryption failed: wrong password or modified keystore")]
+    DecryptionFailed,
+    #[fail(display = "The decrypted bytes are not a valid secret key")]
+    InvalidSecretKey,
+    #[fail(display = "The secret key doesn't match the expected public key")]
+    KeyMismatch,
+}
+
+/// A keystore result.
+pub type Result<T> = ::std::result::Result<T, KeystoreError>;
+
+/// The parameters of the scrypt key derivation function. The memory and time needed to derive
+/// the key grow linearly with `2^log_n * r`.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct KdfParams {
+    /// The binary logarithm of the CPU/memory cost parameter `n`.
+    pub log_n: u8,
+    /// The block size.
+    pub r: u32,
+    /// The parallelization parameter.
+    pub p: u32,
+}
+
+/// The default parameters are the ones used by EIP-2335: `n = 2^18`, `r = 8` and `p = 1`.
+impl Default for KdfParams {
+    fn default() -> Self {
+        KdfParams {
+            log_n: 18,
+            r: 8,
+            p: 1,
+        }
+    }
+}
+
+/// The metadata of an encrypted secret key share.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct ShareInfo {
+    /// The index of the secret key share.
+    pub index: u64,
+    /// The threshold of the key set.
+    pub threshold: u64,
+    /// The SHA3-256 hash of the public key set's byte representation.
+    pub pk_set_hash: Vec<u8>,
+}
+
+/// A secret key or secret key share, encrypted under a password.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct Keystore {
+    /// The version of the format.
+    version: u32,
+    /// The key derivation parameters.
+    kdf: KdfParams,
+    /// The random salt for the key derivation.
+    salt: Vec<u8>,
+    /// The random nonce for the encryption.
+    nonce: Vec<u8>,
+    /// The encrypted key, including the authentication tag.
+    ciphertext: Vec<u8>,
+    /// The public key, or the public key share, matching the encrypted key.
+    public_key: PublicKey,
+    /// The metadata of the share, or `None` if this is a plain secret key.
+    share: Option<ShareInfo>,
+}
+
+impl Keystore {
+    /// Encrypts the secret key under the password.
+    ///
+    /// Returns `KeystoreError::InvalidKdfParams` if the parameters are not supported by scrypt, or
+    /// are more than four times the default ones.
+    pub fn encrypt_secret_key<P, R>(
+        sk: &SecretKey,
+        password: P,
+        params: KdfParams,
+        rng: &mut R,
+    ) -> Result<Keystore>
+    where
+        P: AsRef<[u8]>,
+        R: Rng,
+    {
+        Keystore::encrypt(sk, None, password.as_ref(), params, rng)
+    }
+
+    /// Encrypts the secret key share with the given index under the password. The threshold and
+    /// a hash of the public key set are stored together with it.
+    ///
+    /// Returns `KeystoreError::KeyMismatch` if the share doesn't match the `index`-th public key
+    /// share, and `KeystoreError::InvalidKdfParams` if the parameters are invalid, as in
+    /// `encrypt_secret_key`.
+    pub fn encrypt_secret_key_share<P, R>(
+        sk_share: &SecretKeyShare,
+        index: usize,
+        pk_set: &PublicKeySet,
+        password: P,
+        params: KdfParams,
+        rng: &mut R,
+    ) -> Result<Keystore>
+    where
+        P: AsRef<[u8]>,
+        R: Rng,
+    {
+        if sk_share.public_key_share() != pk_set.public_key_share(index) {
+            return Err(KeystoreError::KeyMismatch);
+        }
+        let share = ShareInfo {
+            index: index as u64,
+            threshold: pk_set.threshold() as u64,
+            pk_set_hash: pk_set_hash(pk_set),
+        };
+        Keystore::encrypt(&sk_share.0, Some(share), password.as_ref(), params, rng)
+    }
+
+    /// Decrypts the secret key.
+    ///
+    /// Returns `KeystoreError::UnsupportedVersion` if the keystore has an unknown format version,
+    /// `KeystoreError::InvalidKdfParams` if the key derivation parameters are invalid, as in
+    /// `encrypt_secret_key`,
+    /// `KeystoreError::DecryptionFailed` if the password is wrong or the keystore has been
+    /// modified, `KeystoreError::InvalidSecretKey` if the decrypted bytes are not a secret key, and
+    /// `KeystoreError::KeyMismatch` if the key doesn't match the stored public key.
+    pub fn decrypt_secret_key<P: AsRef<[u8]>>(&self, password: P) -> Result<SecretKey> {
+        if self.version != KEYSTORE_VERSION {
+            return Err(KeystoreError::UnsupportedVersion(self.version));
+        }
+        if self.nonce.len() != NONCE_LEN {
+            return Err(KeystoreError::DecryptionFailed);
+        }
+        let key = derive_key(password.as_ref(), &self.salt, self.kdf)?;
+        let cipher = ChaCha20Poly1305::new(Key::from_slice(&key));
+        let payload = Payload {
+            msg: &self.ciphertext,
+            aad: &self.associated_data(),
+        };
+        let nonce = Nonce::from_slice(&self.nonce);
+        let bytes = cipher.decrypt(nonce, payload);
+        let bytes = SecretBytes(bytes.map_err(|_| KeystoreError::DecryptionFailed)?);
+        let sk = SecretKey::from_bytes(&bytes).map_err(|_| KeystoreError::InvalidSecretKey)?;
+        if sk.public_key() != self.public_key {
+            return Err(KeystoreError::KeyMismatch);
+        }
+        Ok(sk)
+    }
+
+    /// Decrypts the secret key share.
+    ///
+    /// Returns the same errors as `decrypt_secret_key`.
+    pub fn decrypt_secret_key_share<P: AsRef<[u8]>>(&self, password: P) -> Result<SecretKeyShare> {
+        Ok(generic::SecretKeyShare(self.decrypt_secret_key(password)?))
+    }
+
+    /// Returns the version of the keystore format.
+    pub fn version(&self) -> u32 {
+        self.version
+    }
+
+    /// Returns the public key, or the public key share, matching the encrypted key.
+    pub fn public_key(&self) -> &PublicKey {
+        &self.public_key
+    }
+
+    /// Returns the metadata of the share, or `None` if this is a plain secret key.
+    pub fn share_info(&self) -> Option<&ShareInfo> {
+        self.share.as_ref()
+    }
+
+    /// Returns `true` if the keystore contains a secret key share that belongs to the given
+    /// public key set: The threshold and hash must match, and the stored public key share must
+    /// be the one with the stored index.
+    pub fn matches(&self, pk_set: &PublicKeySet) -> bool {
+        match self.share {
+            None => false,
+            Some(ref share) => {
+                share.threshold == pk_set.threshold() as u64
+                    && share.pk_set_hash == pk_set_hash(pk_set)
+                    && (pk_set.public_key_share(share.index).0) == self.public_key
+            }
+        }
+    }
+
+    /// Encrypts the secret key with the given share metadata.
+    fn encrypt<R: Rng>(
+        sk: &SecretKey,
+        share: Option<ShareInfo>,
+        password: &[u8],
+        kdf: KdfParams,
+        rng: &mut R,
+    ) -> Result<Keystore> {
+        let salt: Vec<u8> = rng.gen_iter().take(SALT_LEN).collect();
+        let nonce: Vec<u8> = rng.gen_iter().take(NONCE_LEN).collect();
+        let key = derive_key(password, &salt, kdf)?;
+        let mut keystore = Keystore {
+            version: KEYSTORE_VERSION,
+            kdf,
+            salt,
+            nonce,
+            ciphertext: Vec::new(),
+            public_key: sk.public_key(),
+            share,
+        };
+        let cipher = ChaCha20Poly1305::new(Key::from_slice(&key));
+        let bytes = sk.to_bytes_secret();
+        let payload = Payload {
+            msg: &bytes,
+            aad: &keystore.associated_data(),
+        };
+        let nonce = Nonce::from_slice(&keystore.nonce);
+        let ciphertext = cipher.encrypt(nonce, payload);
+        keystore.ciphertext = ciphertext.expect("encrypting a secret key never fails");
+        Ok(keystore)
+    }
+
+    /// Returns the metadata that is authenticated together with the encrypted key.
+    fn associated_data(&self) -> Vec<u8> {
+        let mut buf = [0u8; 8];
+        let mut data = Vec::new();
+        BigEndian::write_u32(&mut buf[..4], self.version);
+        data.extend_from_slice(&buf[..4]);
+        data.push(self.kdf.log_n);
+        BigEndian::write_u32(&mut buf[..4], self.kdf.r);
+        data.extend_from_slice(&buf[..4]);
+        BigEndian::write_u32(&mut buf[..4], self.kdf.p);
+        data.extend_from_slice(&buf[..4]);
+        data.extend_from_slice(&self.salt);
+        data.extend(self.public_key.to_bytes());
+        if let Some(ref share) = self.share {
+            BigEndian::write_u64(&mut buf, share.index);
+            data.extend_from_slice(&buf);
+            BigEndian::write_u64(&mut buf, share.threshold);
+            data.extend_from_slice(&buf);
+            data.extend_from_slice(&share.pk_set_hash);
+        }
+        data
+    }
+}
+
+/// Returns the SHA3-256 hash of the public key set's byte representation.
+fn pk_set_hash(pk_set: &PublicKeySet) -> Vec<u8> {
+    sha3_256(&pk_set.to_bytes()).to_vec()
+}
+
+/// Derives the encryption key from the password.
+///
+/// Returns `KeystoreError::InvalidKdfParams` if the parameters exceed the maximums, so that an
+/// untrusted keystore can't make us spend arbitrary memory and time on the key derivation.
+fn derive_key(password: &[u8], salt: &[u8], kdf: KdfParams) -> Result<SecretBytes> {
+    if kdf.log_n > MAX_LOG_N
+        || kdf.r > MAX_R
+        || kdf.p > MAX_P
+        || (u64::from(kdf.r) << kdf.log_n) > MAX_COST
+    {
+        return Err(KeystoreError::InvalidKdfParams);
+    }
+    let params = Params::new(kdf.log_n, kdf.r, kdf.p, KEY_LEN)
+        .map_err(|_| KeystoreError::InvalidKdfParams)?;
+    let mut key = SecretBytes(vec![0; KEY_LEN]);
+    scrypt(password, salt, &params, &mut key.0).expect("key length is valid");
+    Ok(key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use bincode;
+    use rand;
+
+    use SecretKeySet;
+
+    /// Cheap parameters, so that the tests run quickly.
+    const TEST_PARAMS: KdfParams = KdfParams {
+        log_n: 4,
+        r: 8,
+        p: 1,
+    };
+
+    #[test]
+    fn test_keystore() {
+        let mut rng = rand::thread_rng();
+        let sk = SecretKey::random();
+        let keystore = Keystore::encrypt_secret_key(&sk, "pa55w0rd", TEST_PARAMS, &mut rng)
+            .expect("encrypt secret key");
+        assert_eq!(sk.public_key(), *keystore.public_key());
+        assert_eq!(None, keystore.share_info());
+        let decrypted = keystore.decrypt_secret_key("pa55w0rd").expect("decrypt");
+        assert_eq!(sk, decrypted);
+
+        let ser_keystore = bincode::serialize(&keystore).expect("serialize keystore");
+        let de_keystore: Keystore =
+            bincode::deserialize(&ser_keystore).expect("deserialize keystore");
+        assert_eq!(
+            sk,
+            de_keystore.decrypt_secret_key("pa55w0rd").expect("decrypt")
+        );
+
+        let result = keystore.decrypt_secret_key("password");
+        assert_eq!(Some(KeystoreError::DecryptionFailed), result.err());
+        let mut wrong_keystore = keystore.clone();
+        wrong_keystore.version = 2;
+        let result = wrong_keystore.decrypt_secret_key("pa55w0rd");
+        assert_eq!(Some(KeystoreError::UnsupportedVersion(2)), result.err());
+        let mut wrong_keystore = keystore.clone();
+        wrong_keystore.public_key = SecretKey::random().public_key();
+        let result = wrong_keystore.decrypt_secret_key("pa55w0rd");
+        assert_eq!(Some(KeystoreError::DecryptionFailed), result.err());
+
+        let params = KdfParams {
+            r: 0,
+            ..TEST_PARAMS
+        };
+        let result = Keystore::encrypt_secret_key(&sk, "pa55w0rd", params, &mut rng);
+        assert_eq!(Some(KeystoreError::InvalidKdfParams), result.err());
+    }
+
+    #[test]
+    fn test_keystore_limits() {
+        let mut rng = rand::thread_rng();
+        let sk = SecretKey::random();
+        let keystore = Keystore::encrypt_secret_key(&sk, "pa55w0rd", TEST_PARAMS, &mut rng)
+            .expect("encrypt secret key");
+
+        // Parameters that are too expensive are rejected, both when encrypting and decrypting.
+        let too_expensive = vec![
+            KdfParams {
+                log_n: 21,
+                r: 1,
+                p: 1,
+            },
+            KdfParams {
+                log_n: 4,
+                r: 33,
+                p: 1,
+            },
+            KdfParams {
+                log_n: 4,
+                r: 8,
+                p: 5,
+            },
+            KdfParams {
+                log_n: 20,
+                r: 16,
+                p: 1,
+            },
+            KdfParams {
+                log_n: 255,
+                r: u32::MAX,
+                p: u32::MAX,
+            },
+        ];
+        for params in too_expensive {
+            let result = Keystore::encrypt_secret_key(&sk, "pa55w0rd", params, &mut rng);
+            assert_eq!(Some(KeystoreError::InvalidKdfParams), result.err());
+            let mut wrong_keystore = keystore.clone();
+            wrong_keystore.kdf = params;
+            let result = wrong_keystore.decrypt_secret_key("pa55w0rd");
+            assert_eq!(Some(KeystoreError::InvalidKdfParams), result.err());
+        }
+
+        // A nonce with the wrong length is rejected.
+        let mut wrong_keystore = keystore.clone();
+        wrong_keystore.nonce.push(0);
+        let result = wrong_keystore.decrypt_secret_key("pa55w0rd");
+        assert_eq!(Some(KeystoreError::DecryptionFailed), result.err());
+    }
+
+    #[test]
+    fn test_keystore_share() {
+        let mut rng = rand::thread_rng();
+        let sk_set = SecretKeySet::random(2, &mut rng);
+        let pk_set = sk_set.public_keys();
+        let sk_share = sk_set.secret_key_share(3);
+        let keystore =
+            Keystore::encrypt_secret_key_share(&sk_share, 3, &pk_set, "pw", TEST_PARAMS, &mut rng)
+                .expect("encrypt secret key share");
+        let expected = ShareInfo {
+            index: 3,
+            threshold: 2,
+            pk_set_hash: pk_set_hash(&pk_set),
+        };
+        assert_eq!(Some(&expected), keystore.share_info());
+        assert!(keystore.matches(&pk_set));
+        assert!(!keystore.matches(&SecretKeySet::random(2, &mut rng).public_keys()));
+        let decrypted = keystore.decrypt_secret_key_share("pw").expect("decrypt");
+        assert_eq!(sk_share, decrypted);
+
+        // The metadata is authenticated.
+        let mut wrong_keystore = keystore.clone();
+        if let Some(ref mut share) = wrong_keystore.share {
+            share.threshold = 1;
+        }
+        let result = wrong_keystore.decrypt_secret_key_share("pw");
+        assert_eq!(Some(KeystoreError::DecryptionFailed), result.err());
+
+        // The share must match the index.
+        let result =
+            Keystore::encrypt_secret_key_share(&sk_share, 2, &pk_set, "pw", TEST_PARAMS, &mut rng);
+        assert_eq!(Some(KeystoreError::KeyMismatch), result.err());
+    }
+}
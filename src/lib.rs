@@ -14,10 +14,12 @@
 #[cfg(test)]
 extern crate bincode;
 extern crate byteorder;
+extern crate chacha20poly1305;
 extern crate errno;
 #[macro_use]
 extern crate failure;
 extern crate hex_fmt;
+extern crate hkdf;
 extern crate init_with;
 #[macro_use]
 extern crate lazy_static;
@@ -25,42 +27,49 @@ extern crate lazy_static;
 extern crate log;
 extern crate memsec;
 extern crate rand;
-#[macro_use]
-extern crate rand_derive;
+extern crate scrypt;
 extern crate serde;
 #[macro_use]
 extern crate serde_derive;
+extern crate sha2;
 extern crate tiny_keccak;
 
 pub extern crate pairing;
 
+mod derive;
 mod into_fr;
+mod multiexp;
 mod secret;
+#[cfg(test)]
+mod test_utils;
 
+pub mod dkg;
 pub mod error;
+pub mod generic;
+pub mod keystore;
+pub mod min_sig;
+pub mod node_id;
 pub mod poly;
+pub mod pvss;
+pub mod recovery;
+pub mod refresh;
+pub mod reshare;
 pub mod serde_impl;
-
-use std::fmt;
-use std::hash::{Hash, Hasher};
-use std::ptr::copy_nonoverlapping;
+pub mod weighted;
 
 use byteorder::{BigEndian, ByteOrder};
-use hex_fmt::HexFmt;
 use init_with::InitWith;
-use pairing::{CurveAffine, CurveProjective, Engine, Field};
-use rand::{ChaChaRng, OsRng, Rand, Rng, SeedableRng};
+use pairing::CurveProjective;
+use rand::{ChaChaRng, SeedableRng};
 use tiny_keccak::sha3_256;
 
 use error::{Error, Result};
 use into_fr::IntoFr;
-use poly::{Commitment, Poly};
-use secret::{clear_fr, ContainsSecret, MemRange, FR_SIZE};
 
 #[cfg(not(feature = "use-insecure-test-only-mock-crypto"))]
 pub use pairing::bls12_381::{Bls12 as PEngine, Fr, G1Affine, G2Affine, G1, G2};
 
-#[cfg(feature = "use-insecure-test-only-mock-crypto")]
+#[cfg(any(test, feature = "use-insecure-test-only-mock-crypto"))]
 mod mock;
 
 #[cfg(feature = "use-insecure-test-only-mock-crypto")]
@@ -69,568 +78,289 @@ pub use mock::{
     Ms8Projective as G1, Ms8Projective as G2,
 };
 
+#[cfg(not(feature = "use-insecure-test-only-mock-crypto"))]
+pub mod hash_to_curve;
+
+#[cfg(feature = "use-insecure-test-only-mock-crypto")]
+pub use mock::hash_to_curve;
+
+pub use hash_to_curve::{hash_to_g1, hash_to_g2};
+pub use secret::SecretBytes;
+
 /// The number of words (`u32`) in a ChaCha RNG seed.
 const CHACHA_RNG_SEED_SIZE: usize = 8;
 
-const ERR_OS_RNG: &str = "could not initialize the OS random number generator";
+/// The domain separation tag for proofs of possession, as specified in the BLS signature draft.
+const POP_DST: &[u8] = b"BLS_POP_BLS12381G2_XMD:SHA-256_SSWU_RO_POP_";
 
 /// A public key.
-#[derive(Deserialize, Serialize, Copy, Clone, PartialEq, Eq)]
-pub struct PublicKey(#[serde(with = "serde_impl::projective")] G1);
-
-impl Hash for PublicKey {
-    fn hash<H: Hasher>(&self, state: &mut H) {
-        self.0.into_affine().into_compressed().as_ref().hash(state);
-    }
-}
-
-impl fmt::Debug for PublicKey {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        let uncomp = self.0.into_affine().into_uncompressed();
-        write!(f, "PublicKey({:0.10})", HexFmt(uncomp))
-    }
-}
+pub type PublicKey = generic::PublicKey<PEngine>;
+/// A public key share.
+pub type PublicKeyShare = generic::PublicKeyShare<PEngine>;
+/// A signature.
+pub type Signature = generic::Signature<PEngine>;
+/// A signature share.
+pub type SignatureShare = generic::SignatureShare<PEngine>;
+/// An aggregate of signatures by different keys, on distinct messages.
+pub type AggregateSignature = generic::AggregateSignature<PEngine>;
+/// A secret key.
+pub type SecretKey = generic::SecretKey<PEngine>;
+/// A secret key share.
+pub type SecretKeyShare = generic::SecretKeyShare<PEngine>;
+/// An encrypted message, together with an unencrypted label that is bound to it.
+pub type Ciphertext = generic::Ciphertext<PEngine>;
+/// A decryption share. A threshold of decryption shares can be used to decrypt a message.
+pub type DecryptionShare = generic::DecryptionShare<PEngine>;
+/// A public key and an associated set of public key shares.
+pub type PublicKeySet = generic::PublicKeySet<PEngine>;
+/// A secret key and an associated set of secret key shares.
+pub type SecretKeySet = generic::SecretKeySet<PEngine>;
 
 impl PublicKey {
-    /// Returns `true` if the signature matches the element of `G2`.
-    pub fn verify_g2<H: Into<G2Affine>>(&self, sig: &Signature, hash: H) -> bool {
-        PEngine::pairing(self.0, hash) == PEngine::pairing(G1Affine::one(), sig.0)
-    }
-
-    /// Returns `true` if the signature matches the message.
+    /// Returns `true` if the signature matches the message and domain separation tag.
     ///
-    /// This is equivalent to `verify_g2(sig, hash_g2(msg))`.
-    pub fn verify<M: AsRef<[u8]>>(&self, sig: &Signature, msg: M) -> bool {
-        self.verify_g2(sig, hash_g2(msg))
+    /// This is equivalent to `verify_g2(sig, hash_to_g2(msg, dst))`.
+    pub fn verify_with_dst<M, D>(&self, sig: &Signature, msg: M, dst: D) -> bool
+    where
+        M: AsRef<[u8]>,
+        D: AsRef<[u8]>,
+    {
+        self.verify_g2(sig, hash_to_g2(msg, dst))
     }
 
-    /// Encrypts the message using the OS random number generator.
+    /// Returns `true` if the proof of possession was created by the secret key corresponding to
+    /// this public key.
     ///
-    /// Uses the `OsRng` by default. To pass in a custom random number generator, use
-    /// `encrypt_with_rng()`.
-    pub fn encrypt<M: AsRef<[u8]>>(&self, msg: M) -> Ciphertext {
-        self.encrypt_with_rng(&mut OsRng::new().expect(ERR_OS_RNG), msg)
-    }
-
-    /// Encrypts the message.
-    pub fn encrypt_with_rng<R: Rng, M: AsRef<[u8]>>(&self, rng: &mut R, msg: M) -> Ciphertext {
-        let r: Fr = rng.gen();
-        let u = G1Affine::one().mul(r);
-        let v: Vec<u8> = {
-            let g = self.0.into_affine().mul(r);
-            xor_with_hash(g, msg.as_ref())
-        };
-        let w = hash_g1_g2(u, &v).into_affine().mul(r);
-        Ciphertext(u, v, w)
-    }
-
-    /// Returns a byte string representation of the public key.
-    pub fn to_bytes(&self) -> Vec<u8> {
-        self.0.into_affine().into_compressed().as_ref().to_vec()
-    }
-}
-
-/// A public key share.
-#[derive(Deserialize, Serialize, Clone, PartialEq, Eq, Hash)]
-pub struct PublicKeyShare(PublicKey);
-
-impl fmt::Debug for PublicKeyShare {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        let uncomp = (self.0).0.into_affine().into_uncompressed();
-        write!(f, "PublicKeyShare({:0.10})", HexFmt(uncomp))
+    /// The identity element is never accepted as a public key: Otherwise the identity element
+    /// would be a valid proof for it, and it could be added to any aggregate without changing it.
+    pub fn verify_pop(&self, pop: &ProofOfPossession) -> bool {
+        !self.0.is_zero() && self.verify_g2(&pop.0, hash_to_g2(self.to_bytes(), POP_DST))
+    }
+
+    /// Verifies a batch of signatures that use the domain separation tag `dst`. Returns
+    /// `Error::InvalidSignatures` with the indices of the invalid entries, if there are any.
+    pub fn verify_batch_with_dst<M, D>(
+        items: &[(PublicKey, Signature, M)],
+        dst: D,
+    ) -> Result<()>
+    where
+        M: AsRef<[u8]>,
+        D: AsRef<[u8]>,
+    {
+        let items = items.iter().map(|(pk, sig, msg)| (pk.0, sig.0, msg));
+        let dst = dst.as_ref();
+        generic::verify_batch::<PEngine, _, _, _>(items, |msg| hash_to_g2(msg, dst))
     }
 }
 
 impl PublicKeyShare {
-    /// Returns `true` if the signature matches the element of `G2`.
-    pub fn verify_g2<H: Into<G2Affine>>(&self, sig: &SignatureShare, hash: H) -> bool {
-        self.0.verify_g2(&sig.0, hash)
-    }
-
-    /// Returns `true` if the signature matches the message.
+    /// Returns `true` if the signature matches the message and domain separation tag.
     ///
-    /// This is equivalent to `verify_g2(sig, hash_g2(msg))`.
-    pub fn verify<M: AsRef<[u8]>>(&self, sig: &SignatureShare, msg: M) -> bool {
-        self.verify_g2(sig, hash_g2(msg))
-    }
-
-    /// Returns `true` if the decryption share matches the ciphertext.
-    pub fn verify_decryption_share(&self, share: &DecryptionShare, ct: &Ciphertext) -> bool {
-        let Ciphertext(ref u, ref v, ref w) = *ct;
-        let hash = hash_g1_g2(*u, v);
-        PEngine::pairing(share.0, hash) == PEngine::pairing((self.0).0, *w)
-    }
-
-    /// Returns a byte string representation of the public key share.
-    pub fn to_bytes(&self) -> Vec<u8> {
-        self.0.to_bytes()
-    }
-}
-
-/// A signature.
-// Note: Random signatures can be generated for testing.
-#[derive(Deserialize, Serialize, Clone, PartialEq, Eq, Rand)]
-pub struct Signature(#[serde(with = "serde_impl::projective")] G2);
-
-impl fmt::Debug for Signature {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        let uncomp = self.0.into_affine().into_uncompressed();
-        write!(f, "Signature({:0.10})", HexFmt(uncomp))
-    }
-}
-
-impl Hash for Signature {
-    fn hash<H: Hasher>(&self, state: &mut H) {
-        self.0.into_affine().into_compressed().as_ref().hash(state);
-    }
-}
-
-impl Signature {
-    pub fn parity(&self) -> bool {
-        let uncomp = self.0.into_affine().into_uncompressed();
-        let xor_bytes: u8 = uncomp.as_ref().iter().fold(0, |result, byte| result ^ byte);
-        let parity = 0 != xor_bytes.count_ones() % 2;
-        debug!("Signature: {:0.10}, parity: {}", HexFmt(uncomp), parity);
-        parity
+    /// This is equivalent to `verify_g2(sig, hash_to_g2(msg, dst))`.
+    pub fn verify_with_dst<M, D>(&self, sig: &SignatureShare, msg: M, dst: D) -> bool
+    where
+        M: AsRef<[u8]>,
+        D: AsRef<[u8]>,
+    {
+        self.verify_g2(sig, hash_to_g2(msg, dst))
     }
-}
 
-/// A signature share.
-// Note: Random signature shares can be generated for testing.
-#[derive(Deserialize, Serialize, Clone, PartialEq, Eq, Rand, Hash)]
-pub struct SignatureShare(pub Signature);
-
-impl fmt::Debug for SignatureShare {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        let uncomp = (self.0).0.into_affine().into_uncompressed();
-        write!(f, "SignatureShare({:0.10})", HexFmt(uncomp))
+    /// Verifies a batch of signature shares that use the domain separation tag `dst`. Returns
+    /// `Error::InvalidSignatures` with the indices of the invalid entries, if there are any.
+    pub fn verify_batch_with_dst<M, D>(
+        items: &[(PublicKeyShare, SignatureShare, M)],
+        dst: D,
+    ) -> Result<()>
+    where
+        M: AsRef<[u8]>,
+        D: AsRef<[u8]>,
+    {
+        let items = items
+            .iter()
+            .map(|(pk, sig, msg)| ((pk.0).0, (sig.0).0, msg));
+        let dst = dst.as_ref();
+        generic::verify_batch::<PEngine, _, _, _>(items, |msg| hash_to_g2(msg, dst))
     }
 }
 
-/// A secret key; wraps a single prime field element. The field element is
-/// heap allocated to avoid any stack copying that result when passing
-/// `SecretKey`s between stack frames.
-#[derive(PartialEq, Eq)]
-pub struct SecretKey(Box<Fr>);
-
-/// Creates a `SecretKey` containing the zero prime field element.
-impl Default for SecretKey {
-    fn default() -> Self {
-        let mut fr = Fr::zero();
-        SecretKey::from_mut(&mut fr)
+impl AggregateSignature {
+    /// Returns `true` if the aggregate consists of a signature of each message by the
+    /// corresponding key, using the domain separation tag `dst`.
+    ///
+    /// Returns `false` if `items` is empty or contains two equal messages.
+    pub fn verify_with_dst<M, D>(&self, items: &[(PublicKey, M)], dst: D) -> bool
+    where
+        M: AsRef<[u8]>,
+        D: AsRef<[u8]>,
+    {
+        let dst = dst.as_ref();
+        !generic::has_duplicate_messages(items)
+            && self.verify_grouped(items, |msg| hash_to_g2(msg, dst))
     }
-}
 
-/// Creates a random `SecretKey` from a given RNG. If you do not need to specify your own RNG, you
-/// should use `SecretKey::random()` as your constructor instead.
-impl Rand for SecretKey {
-    fn rand<R: Rng>(rng: &mut R) -> Self {
-        let mut fr = Fr::rand(rng);
-        SecretKey::from_mut(&mut fr)
-    }
-}
-
-/// Creates a new `SecretKey` by cloning another `SecretKey`'s prime field element.
-impl Clone for SecretKey {
-    fn clone(&self) -> Self {
-        let mut fr = *self.0;
-        SecretKey::from_mut(&mut fr)
+    /// Returns `true` if the aggregate consists of a signature of each message by the
+    /// corresponding key, using the domain separation tag `dst`. Messages may appear several
+    /// times.
+    ///
+    /// *WARNING* This is only secure if the `ProofOfPossession` of every key has been verified.
+    pub fn verify_allow_duplicates_with_dst<M, D>(&self, items: &[(PublicKey, M)], dst: D) -> bool
+    where
+        M: AsRef<[u8]>,
+        D: AsRef<[u8]>,
+    {
+        let dst = dst.as_ref();
+        self.verify_grouped(items, |msg| hash_to_g2(msg, dst))
     }
 }
 
-/// Zeroes out the memory allocated from the `SecretKey`'s field element.
-impl Drop for SecretKey {
-    fn drop(&mut self) {
-        self.zero_secret();
-    }
-}
-/// A debug statement where the secret prime field element is redacted.
-impl fmt::Debug for SecretKey {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        f.debug_tuple("SecretKey").field(&"...").finish()
+/// A proof that the owner of a public key knows the corresponding secret key.
+///
+/// It is a signature of the public key itself, using a dedicated domain separation tag, so it
+/// can't be confused with a regular signature. Verifying the proofs of possession of all keys
+/// before aggregating them prevents rogue key attacks. After that, multisignatures on the same
+/// message can be verified with only two pairings: A `Signature::aggregate` of the signatures
+/// is valid for the `PublicKey::aggregate` of the keys.
+#[derive(Deserialize, Serialize, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct ProofOfPossession(Signature);
+
+impl ProofOfPossession {
+    /// Returns a byte string representation of the proof of possession.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.0.to_bytes()
     }
-}
 
-impl ContainsSecret for SecretKey {
-    fn secret_memory(&self) -> MemRange {
-        let ptr = &*self.0 as *const Fr as *mut u8;
-        let n_bytes = *FR_SIZE;
-        MemRange { ptr, n_bytes }
+    /// Decodes a proof of possession from its byte string representation.
+    ///
+    /// Returns an error if the length is wrong, or if the bytes don't encode a point in the
+    /// prime order subgroup.
+    pub fn from_bytes<B: AsRef<[u8]>>(bytes: B) -> Result<Self> {
+        Ok(ProofOfPossession(Signature::from_bytes(bytes)?))
     }
 }
 
 impl SecretKey {
-    /// Creates a new `SecretKey` from a mutable reference to a field element. This constructor
-    /// takes a reference to avoid any unnecessary stack copying/moving of secrets (i.e. the field
-    /// element). The field element is copied bytewise onto the heap, the resulting `Box` is
-    /// stored in the returned `SecretKey`.
+    /// Derives a master secret key from the seed, as specified in EIP-2333. The seed must contain
+    /// at least 32 bytes of entropy.
     ///
-    /// *WARNING* this constructor will overwrite the referenced `Fr` element with zeros after it
-    /// has been copied onto the heap.
-    pub fn from_mut(fr: &mut Fr) -> Self {
-        let fr_ptr = fr as *mut Fr;
-        let mut boxed_fr = Box::new(Fr::zero());
-        unsafe {
-            copy_nonoverlapping(fr_ptr, &mut *boxed_fr as *mut Fr, 1);
+    /// Returns `Error::InvalidLength` if the seed is shorter than 32 bytes.
+    pub fn from_seed<S: AsRef<[u8]>>(seed: S) -> Result<Self> {
+        let seed = seed.as_ref();
+        if seed.len() < derive::MIN_SEED_LEN {
+            return Err(Error::InvalidLength {
+                expected: derive::MIN_SEED_LEN,
+                actual: seed.len(),
+            });
         }
-        clear_fr(fr_ptr);
-        SecretKey(boxed_fr)
-    }
-
-    /// Creates a new random instance of `SecretKey`. If you want to use/define your own random
-    /// number generator, you should use the constructor: `SecretKey::rand()`. If you do not need
-    /// to specify your own RNG, you should use the `SecretKey::random()` constructor, which uses
-    /// [`rand::thead_rng()`](https://docs.rs/rand/0.4.3/rand/fn.thread_rng.html) internally as
-    /// its RNG.
-    pub fn random() -> Self {
-        let mut rng = rand::thread_rng();
-        SecretKey::rand(&mut rng)
+        let mut fr = derive::derive_master_sk(seed);
+        Ok(SecretKey::from_mut(&mut fr))
     }
 
-    /// Returns the matching public key.
-    pub fn public_key(&self) -> PublicKey {
-        PublicKey(G1Affine::one().mul(*self.0))
+    /// Derives the child key with the given index, as specified in EIP-2333. The child key
+    /// doesn't reveal anything about this key or its other children.
+    pub fn derive_child(&self, index: u32) -> SecretKey {
+        let mut fr = derive::derive_child_sk(&self.0, index);
+        SecretKey::from_mut(&mut fr)
     }
 
-    /// Signs the given element of `G2`.
-    pub fn sign_g2<H: Into<G2Affine>>(&self, hash: H) -> Signature {
-        Signature(hash.into().mul(*self.0))
+    /// Derives the descendant key with the given path of child indices, e.g.
+    /// `[12381, 3600, 0, 0]` for the EIP-2334 path `m/12381/3600/0/0`.
+    pub fn derive_path<I: IntoIterator<Item = u32>>(&self, path: I) -> SecretKey {
+        path.into_iter()
+            .fold(self.clone(), |sk, index| sk.derive_child(index))
     }
 
-    /// Signs the given message.
+    /// Signs the given message, using the domain separation tag `dst`. The signature can only be
+    /// verified with the same tag, so it can't be replayed in a context that uses a different one.
     ///
-    /// This is equivalent to `sign_g2(hash_g2(msg))`.
-    pub fn sign<M: AsRef<[u8]>>(&self, msg: M) -> Signature {
-        self.sign_g2(hash_g2(msg))
-    }
-
-    /// Returns the decrypted text, or `None`, if the ciphertext isn't valid.
-    pub fn decrypt(&self, ct: &Ciphertext) -> Option<Vec<u8>> {
-        if !ct.verify() {
-            return None;
-        }
-        let Ciphertext(ref u, ref v, _) = *ct;
-        let g = u.into_affine().mul(*self.0);
-        Some(xor_with_hash(g, v))
+    /// This is equivalent to `sign_g2(hash_to_g2(msg, dst))`.
+    pub fn sign_with_dst<M: AsRef<[u8]>, D: AsRef<[u8]>>(&self, msg: M, dst: D) -> Signature {
+        self.sign_g2(hash_to_g2(msg, dst))
     }
 
-    /// Generates a non-redacted debug string. This method differs from
-    /// the `Debug` implementation in that it *does* leak the secret prime
-    /// field element.
-    pub fn reveal(&self) -> String {
-        let uncomp = self.public_key().0.into_affine().into_uncompressed();
-        format!("SecretKey({:0.10})", HexFmt(uncomp))
-    }
-}
-
-/// A secret key share.
-#[derive(Clone, PartialEq, Eq, Rand, Default)]
-pub struct SecretKeyShare(SecretKey);
-
-impl fmt::Debug for SecretKeyShare {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        f.debug_tuple("SecretKeyShare").field(&"...").finish()
+    /// Returns a proof that the owner of the public key knows this secret key.
+    pub fn proof_of_possession(&self) -> ProofOfPossession {
+        let hash = hash_to_g2(self.public_key().to_bytes(), POP_DST);
+        ProofOfPossession(self.sign_g2(hash))
     }
 }
 
 impl SecretKeyShare {
-    /// Creates a new `SecretKeyShare` from a mutable reference to a field element. This
-    /// constructor takes a reference to avoid any unnecessary stack copying/moving of secrets
-    /// field elements. The field element will be copied bytewise onto the heap, the resulting
-    /// `Box` is stored in the `SecretKey` which is then wrapped in a `SecretKeyShare`.
+    /// Signs the given message, using the domain separation tag `dst`.
     ///
-    /// *WARNING* this constructor will overwrite the pointed to `Fr` element with zeros once it
-    /// has been copied into a new `SecretKeyShare`.
-    pub fn from_mut(fr: &mut Fr) -> Self {
-        SecretKeyShare(SecretKey::from_mut(fr))
-    }
-
-    /// Returns the matching public key share.
-    pub fn public_key_share(&self) -> PublicKeyShare {
-        PublicKeyShare(self.0.public_key())
-    }
-
-    /// Signs the given element of `G2`.
-    pub fn sign_g2<H: Into<G2Affine>>(&self, hash: H) -> SignatureShare {
-        SignatureShare(self.0.sign_g2(hash))
-    }
-
-    /// Signs the given message.
-    pub fn sign<M: AsRef<[u8]>>(&self, msg: M) -> SignatureShare {
-        SignatureShare(self.0.sign(msg))
-    }
-
-    /// Returns a decryption share, or `None`, if the ciphertext isn't valid.
-    pub fn decrypt_share(&self, ct: &Ciphertext) -> Option<DecryptionShare> {
-        if !ct.verify() {
-            return None;
-        }
-        Some(self.decrypt_share_no_verify(ct))
-    }
-
-    /// Returns a decryption share, without validating the ciphertext.
-    pub fn decrypt_share_no_verify(&self, ct: &Ciphertext) -> DecryptionShare {
-        DecryptionShare(ct.0.into_affine().mul(*(self.0).0))
-    }
-
-    /// Generates a non-redacted debug string. This method differs from
-    /// the `Debug` implementation in that it *does* leak the secret prime
-    /// field element.
-    pub fn reveal(&self) -> String {
-        let uncomp = self.0.public_key().0.into_affine().into_uncompressed();
-        format!("SecretKeyShare({:0.10})", HexFmt(uncomp))
-    }
-}
-
-/// An encrypted message.
-#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
-pub struct Ciphertext(
-    #[serde(with = "serde_impl::projective")] G1,
-    Vec<u8>,
-    #[serde(with = "serde_impl::projective")] G2,
-);
-
-impl Hash for Ciphertext {
-    fn hash<H: Hasher>(&self, state: &mut H) {
-        let Ciphertext(ref u, ref v, ref w) = *self;
-        u.into_affine().into_compressed().as_ref().hash(state);
-        v.hash(state);
-        w.into_affine().into_compressed().as_ref().hash(state);
-    }
-}
-
-impl Ciphertext {
-    /// Returns `true` if this is a valid ciphertext. This check is necessary to prevent
-    /// chosen-ciphertext attacks.
-    pub fn verify(&self) -> bool {
-        let Ciphertext(ref u, ref v, ref w) = *self;
-        let hash = hash_g1_g2(*u, v);
-        PEngine::pairing(G1Affine::one(), *w) == PEngine::pairing(*u, hash)
-    }
-}
-
-/// A decryption share. A threshold of decryption shares can be used to decrypt a message.
-#[derive(Clone, Deserialize, Serialize, Debug, PartialEq, Eq, Rand)]
-pub struct DecryptionShare(#[serde(with = "serde_impl::projective")] G1);
-
-impl Hash for DecryptionShare {
-    fn hash<H: Hasher>(&self, state: &mut H) {
-        self.0.into_affine().into_compressed().as_ref().hash(state);
-    }
-}
-
-/// A public key and an associated set of public key shares.
-#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
-pub struct PublicKeySet {
-    /// The coefficients of a polynomial whose value at `0` is the "master key", and value at
-    /// `i + 1` is key share number `i`.
-    commit: Commitment,
-}
-
-impl Hash for PublicKeySet {
-    fn hash<H: Hasher>(&self, state: &mut H) {
-        self.commit.hash(state);
-    }
-}
-
-impl From<Commitment> for PublicKeySet {
-    fn from(commit: Commitment) -> PublicKeySet {
-        PublicKeySet { commit }
+    /// All shares that are combined into a signature must use the same tag, and the combined
+    /// signature must be verified with `PublicKey::verify_with_dst` and that tag.
+    pub fn sign_with_dst<M, D>(&self, msg: M, dst: D) -> SignatureShare
+    where
+        M: AsRef<[u8]>,
+        D: AsRef<[u8]>,
+    {
+        generic::SignatureShare(self.0.sign_with_dst(msg, dst))
     }
 }
 
 impl PublicKeySet {
-    /// Returns the threshold `t`: any set of `t + 1` signature shares can be combined into a full
-    /// signature.
-    pub fn threshold(&self) -> usize {
-        self.commit.degree()
-    }
-
-    /// Returns the public key.
-    pub fn public_key(&self) -> PublicKey {
-        PublicKey(self.commit.coeff[0])
-    }
-
-    /// Returns the `i`-th public key share.
-    pub fn public_key_share<T: IntoFr>(&self, i: T) -> PublicKeyShare {
-        let value = self.commit.evaluate(into_fr_plus_1(i));
-        PublicKeyShare(PublicKey(value))
-    }
-
-    /// Combines the shares into a signature that can be verified with the main public key.
-    pub fn combine_signatures<'a, T, I>(&self, shares: I) -> Result<Signature>
+    /// Verifies the shares and combines them into a signature of `msg` with the domain separation
+    /// tag `dst`.
+    ///
+    /// Like `combine_signatures`, this only uses the first `threshold() + 1` shares, but it
+    /// returns `Error::InvalidShares` if any of them doesn't match the message and tag, e.g.
+    /// because it was signed with a different tag.
+    pub fn combine_signatures_with_dst<'a, T, I, M, D>(
+        &self,
+        shares: I,
+        msg: M,
+        dst: D,
+    ) -> Result<Signature>
     where
         I: IntoIterator<Item = (T, &'a SignatureShare)>,
         T: IntoFr,
+        M: AsRef<[u8]>,
+        D: AsRef<[u8]>,
     {
-        let samples = shares.into_iter().map(|(i, share)| (i, &(share.0).0));
-        Ok(Signature(interpolate(self.commit.degree(), samples)?))
+        self.combine_signatures_checked_g2(shares, hash_to_g2(msg, dst))
     }
 
-    /// Combines the shares to decrypt the ciphertext.
-    pub fn decrypt<'a, T, I>(&self, shares: I, ct: &Ciphertext) -> Result<Vec<u8>>
+    /// Verifies the shares and combines the valid ones into a signature of `msg` with the domain
+    /// separation tag `dst`. Returns the signature and the indices of the invalid shares.
+    pub fn combine_signatures_verified_with_dst<'a, T, I, M, D>(
+        &self,
+        shares: I,
+        msg: M,
+        dst: D,
+    ) -> Result<(Signature, Vec<T>)>
     where
-        I: IntoIterator<Item = (T, &'a DecryptionShare)>,
+        I: IntoIterator<Item = (T, &'a SignatureShare)>,
         T: IntoFr,
+        M: AsRef<[u8]>,
+        D: AsRef<[u8]>,
     {
-        let samples = shares.into_iter().map(|(i, share)| (i, &share.0));
-        let g = interpolate(self.commit.degree(), samples)?;
-        Ok(xor_with_hash(g, &ct.1))
-    }
-}
-
-/// A secret key and an associated set of secret key shares.
-pub struct SecretKeySet {
-    /// The coefficients of a polynomial whose value at `0` is the "master key", and value at
-    /// `i + 1` is key share number `i`.
-    poly: Poly,
-}
-
-impl From<Poly> for SecretKeySet {
-    fn from(poly: Poly) -> SecretKeySet {
-        SecretKeySet { poly }
+        self.combine_signatures_verified_g2(shares, hash_to_g2(msg, dst))
     }
 }
 
-impl SecretKeySet {
-    /// Creates a set of secret key shares, where any `threshold + 1` of them can collaboratively
-    /// sign and decrypt. This constuctor is identical to the `SecretKey::try_random()` in every
-    /// way except that this constructor panics if the other returns an error.
-    ///
-    /// # Panic
-    ///
-    /// Panics if the `threshold` is too large for the coefficients to fit into a `Vec`.
-    pub fn random<R: Rng>(threshold: usize, rng: &mut R) -> Self {
-        SecretKeySet::try_random(threshold, rng)
-            .unwrap_or_else(|e| panic!("Failed to create random `SecretKeySet`: {}", e))
-    }
-
-    /// Creates a set of secret key shares, where any `threshold + 1` of them can collaboratively
-    /// sign and decrypt. This constuctor is identical to the `SecretKey::random()` in every
-    /// way except that this constructor returns an `Err` where the `random` would panic.
-    pub fn try_random<R: Rng>(threshold: usize, rng: &mut R) -> Result<Self> {
-        Poly::try_random(threshold, rng).map(SecretKeySet::from)
-    }
-
-    /// Returns the threshold `t`: any set of `t + 1` signature shares can be combined into a full
-    /// signature.
-    pub fn threshold(&self) -> usize {
-        self.poly.degree()
-    }
-
-    /// Returns the `i`-th secret key share.
-    pub fn secret_key_share<T: IntoFr>(&self, i: T) -> SecretKeyShare {
-        let mut fr = self.poly.evaluate(into_fr_plus_1(i));
-        SecretKeyShare::from_mut(&mut fr)
-    }
-
-    /// Returns the corresponding public key set. That information can be shared publicly.
-    pub fn public_keys(&self) -> PublicKeySet {
-        PublicKeySet {
-            commit: self.poly.commitment(),
-        }
-    }
-
-    /// Returns the secret master key.
-    #[cfg(test)]
-    fn secret_key(&self) -> SecretKey {
-        let mut fr = self.poly.evaluate(0);
-        SecretKey::from_mut(&mut fr)
-    }
+/// Returns a hash of the given message in `G1`.
+///
+/// This uses a random number generator seeded with the message's SHA3 digest, which is specific
+/// to this crate. For a hash that other BLS implementations can reproduce, use `hash_to_g1`.
+pub fn hash_g1<M: AsRef<[u8]>>(msg: M) -> G1 {
+    generic::hash_g1::<PEngine, M>(msg)
 }
 
 /// Returns a hash of the given message in `G2`.
+///
+/// This uses a random number generator seeded with the message's SHA3 digest, which is specific
+/// to this crate. For a hash that other BLS implementations can reproduce, use `hash_to_g2`.
 pub fn hash_g2<M: AsRef<[u8]>>(msg: M) -> G2 {
-    let digest = sha3_256(msg.as_ref());
-    let seed = <[u32; CHACHA_RNG_SEED_SIZE]>::init_with_indices(|i| {
-        BigEndian::read_u32(&digest.as_ref()[(4 * i)..(4 * i + 4)])
-    });
-    let mut rng = ChaChaRng::from_seed(&seed);
-    rng.gen()
+    generic::hash_g2::<PEngine, M>(msg)
 }
 
-/// Returns a hash of the group element and message, in the second group.
-fn hash_g1_g2<M: AsRef<[u8]>>(g1: G1, msg: M) -> G2 {
-    // If the message is large, hash it, otherwise copy it.
-    // TODO: Benchmark and optimize the threshold.
-    let mut msg = if msg.as_ref().len() > 64 {
-        sha3_256(msg.as_ref()).to_vec()
-    } else {
-        msg.as_ref().to_vec()
-    };
-    msg.extend(g1.into_affine().into_compressed().as_ref());
-    hash_g2(&msg)
-}
-
-/// Returns the bitwise xor of `bytes` with a sequence of pseudorandom bytes determined by `g1`.
-fn xor_with_hash(g1: G1, bytes: &[u8]) -> Vec<u8> {
-    let digest = sha3_256(g1.into_affine().into_compressed().as_ref());
+/// Returns a random number generator seeded with the SHA3 digest of `bytes`.
+fn rng_from_hash(bytes: &[u8]) -> ChaChaRng {
+    let digest = sha3_256(bytes);
     let seed = <[u32; CHACHA_RNG_SEED_SIZE]>::init_with_indices(|i| {
         BigEndian::read_u32(&digest.as_ref()[(4 * i)..(4 * i + 4)])
     });
-    let mut rng = ChaChaRng::from_seed(&seed);
-    let xor = |(a, b): (u8, &u8)| a ^ b;
-    rng.gen_iter().zip(bytes).map(xor).collect()
-}
-
-use std::borrow::Borrow;
-
-/// Given a list of `t + 1` samples `(i - 1, f(i) * g)` for a polynomial `f` of degree `t`, and a
-/// group generator `g`, returns `f(0) * g`.
-fn interpolate<C, B, T, I>(t: usize, items: I) -> Result<C>
-where
-    C: CurveProjective<Scalar = Fr>,
-    I: IntoIterator<Item = (T, B)>,
-    T: IntoFr,
-    B: Borrow<C>,
-{
-    let samples: Vec<_> = items
-        .into_iter()
-        .take(t + 1)
-        .map(|(i, sample)| (into_fr_plus_1(i), sample))
-        .collect();
-    if samples.len() <= t {
-        return Err(Error::NotEnoughShares);
-    }
-
-    if t == 0 {
-        return Ok(*samples[0].1.borrow());
-    }
-
-    // Compute the products `x_prod[i]` of all but the `i`-th entry.
-    let mut x_prod: Vec<C::Scalar> = Vec::with_capacity(t);
-    let mut tmp = C::Scalar::one();
-    x_prod.push(tmp);
-    for (x, _) in samples.iter().take(t) {
-        tmp.mul_assign(x);
-        x_prod.push(tmp);
-    }
-    tmp = C::Scalar::one();
-    for (i, (x, _)) in samples[1..].iter().enumerate().rev() {
-        tmp.mul_assign(x);
-        x_prod[i].mul_assign(&tmp);
-    }
-
-    let mut result = C::zero();
-    for (mut l0, (x, sample)) in x_prod.into_iter().zip(&samples) {
-        // Compute the value at 0 of the Lagrange polynomial that is `0` at the other data
-        // points but `1` at `x`.
-        let mut denom = C::Scalar::one();
-        for (x0, _) in samples.iter().filter(|(x0, _)| x0 != x) {
-            let mut diff = *x0;
-            diff.sub_assign(x);
-            denom.mul_assign(&diff);
-        }
-        l0.mul_assign(&denom.inverse().ok_or(Error::DuplicateEntry)?);
-        result.add_assign(&sample.borrow().into_affine().mul(l0));
-    }
-    Ok(result)
-}
-
-fn into_fr_plus_1<I: IntoFr>(x: I) -> Fr {
-    let mut result = Fr::one();
-    result.add_assign(&x.into_fr());
-    result
+    ChaChaRng::from_seed(&seed)
 }
 
 #[cfg(test)]
@@ -639,8 +369,14 @@ mod tests {
 
     use std::collections::BTreeMap;
 
+    use pairing::CurveProjective;
     use rand::{self, random, Rng};
 
+    use error::ShareIndex;
+    use generic::{hash_g1_g2, interpolate, point_to_bytes, xor_with_hash};
+    use poly::Poly;
+    use secret::fr_bytes;
+
     #[test]
     fn test_interpolate() {
         let mut rng = rand::thread_rng();
@@ -653,7 +389,7 @@ mod tests {
                 x += rng.gen_range(1, 5);
                 values.push((x - 1, comm.evaluate(x)));
             }
-            let actual = interpolate(deg, values).expect("wrong number of values");
+            let actual: G1 = interpolate(deg, values).expect("wrong number of values");
             assert_eq!(comm.evaluate(0), actual);
         }
     }
@@ -670,6 +406,159 @@ mod tests {
         assert!(!pk0.verify(&sk0.sign(msg1), msg0)); // Wrong message.
     }
 
+    #[test]
+    fn test_hash_to_curve_sig() {
+        let sk0: SecretKey = random();
+        let sk1: SecretKey = random();
+        let pk0 = sk0.public_key();
+        let dst = b"TEST-SIG_BLS12381G2_XMD:SHA-256_SSWU_RO_";
+        let hash0 = hash_to_g2(b"Real news", &dst[..]);
+        let hash1 = hash_to_g2(b"Fake news", &dst[..]);
+        let other_dst_hash = hash_to_g2(b"Real news", b"OTHER-DST");
+        assert!(pk0.verify_g2(&sk0.sign_g2(hash0), hash0));
+        assert!(!pk0.verify_g2(&sk1.sign_g2(hash0), hash0)); // Wrong key.
+        assert!(!pk0.verify_g2(&sk0.sign_g2(hash1), hash0)); // Wrong message.
+        assert!(!pk0.verify_g2(&sk0.sign_g2(other_dst_hash), hash0)); // Wrong DST.
+    }
+
+    #[test]
+    fn test_aggregate_sig() {
+        let sks: Vec<SecretKey> = (0..4).map(|_| random()).collect();
+        let msgs = ["Real news", "Fake news", "Old news", "Breaking news"];
+        let items: Vec<_> = sks.iter().map(|sk| sk.public_key()).zip(&msgs).collect();
+        let sigs: Vec<_> = sks
+            .iter()
+            .zip(&msgs)
+            .map(|(sk, msg)| sk.sign(msg))
+            .collect();
+        let agg_sig = AggregateSignature::aggregate(&sigs);
+        assert!(agg_sig.verify(&items));
+
+        // The order doesn't matter.
+        let mut items_rev = items.clone();
+        items_rev.reverse();
+        assert!(agg_sig.verify(&items_rev));
+
+        // A wrong message or a missing signature doesn't validate.
+        let mut items_wrong = items.clone();
+        items_wrong[1].1 = &"Other news";
+        assert!(!agg_sig.verify(&items_wrong));
+        assert!(!AggregateSignature::aggregate(&sigs[1..]).verify(&items));
+        assert!(!agg_sig.verify::<&str>(&[]));
+
+        // Duplicate messages are rejected.
+        let dup_sigs = [sks[0].sign(msgs[0]), sks[1].sign(msgs[0])];
+        let dup_items = [
+            (sks[0].public_key(), msgs[0]),
+            (sks[1].public_key(), msgs[0]),
+        ];
+        assert!(!AggregateSignature::aggregate(&dup_sigs).verify(&dup_items));
+
+        // The same works with domain separation tags.
+        let dst = "AGGREGATE-V01";
+        let sigs: Vec<_> = sks
+            .iter()
+            .zip(&msgs)
+            .map(|(sk, msg)| sk.sign_with_dst(msg, dst))
+            .collect();
+        let agg_sig = AggregateSignature::aggregate(&sigs);
+        assert!(agg_sig.verify_with_dst(&items, dst));
+        assert!(!agg_sig.verify(&items));
+    }
+
+    #[test]
+    fn test_proof_of_possession() {
+        let sks: Vec<SecretKey> = (0..4).map(|_| random()).collect();
+        let pks: Vec<PublicKey> = sks.iter().map(SecretKey::public_key).collect();
+        let pops: Vec<_> = sks.iter().map(SecretKey::proof_of_possession).collect();
+        for (pk, pop) in pks.iter().zip(&pops) {
+            assert!(pk.verify_pop(pop));
+            // The proof is not a regular signature of the key.
+            assert!(!pk.verify(&pop.0, pk.to_bytes()));
+        }
+        assert!(!pks[0].verify_pop(&pops[1]));
+
+        // The identity element is rejected, even though the pairing check would pass.
+        let zero_pk = generic::PublicKey(G1::zero());
+        let zero_pop = ProofOfPossession(generic::Signature(G2::zero()));
+        assert!(zero_pk.verify_g2(&zero_pop.0, hash_to_g2(zero_pk.to_bytes(), POP_DST)));
+        assert!(!zero_pk.verify_pop(&zero_pop));
+
+        // A multisignature on a single message can be verified with the aggregated keys.
+        let msg = "Totally real news";
+        let sigs: Vec<_> = sks.iter().map(|sk| sk.sign(msg)).collect();
+        let multi_sig = Signature::aggregate(&sigs);
+        assert!(PublicKey::aggregate(&pks).verify(&multi_sig, msg));
+        assert!(!PublicKey::aggregate(&pks[1..]).verify(&multi_sig, msg));
+
+        // With verified keys, aggregates may contain several signatures of the same message.
+        let other_msg = "Other news";
+        let mut items: Vec<_> = pks.iter().map(|pk| (*pk, msg)).collect();
+        items.push((pks[0], other_msg));
+        let mut all_sigs = sigs.clone();
+        all_sigs.push(sks[0].sign(other_msg));
+        let agg_sig = AggregateSignature::aggregate(&all_sigs);
+        assert!(!agg_sig.verify(&items));
+        assert!(agg_sig.verify_allow_duplicates(&items));
+        items[1].1 = other_msg;
+        assert!(!agg_sig.verify_allow_duplicates(&items));
+    }
+
+    #[test]
+    fn test_verify_batch() {
+        let sks: Vec<SecretKey> = (0..10).map(|_| random()).collect();
+        let msgs = ["Real news", "Fake news", "Old news"];
+        let mut items: Vec<_> = sks
+            .iter()
+            .enumerate()
+            .map(|(i, sk)| {
+                let msg = msgs[i % msgs.len()];
+                (sk.public_key(), sk.sign(msg), msg)
+            }).collect();
+        assert_eq!(Ok(()), PublicKey::verify_batch(&items));
+        assert_eq!(Ok(()), PublicKey::verify_batch(&items[..0]));
+
+        // Invalid signatures are identified.
+        items[3].2 = "Wrong news";
+        items[7].1 = sks[6].sign(items[7].2);
+        let expected = Error::InvalidSignatures {
+            indices: vec![3, 7],
+        };
+        assert_eq!(Err(expected), PublicKey::verify_batch(&items));
+
+        let dst = "BATCH-V01";
+        let dst_items: Vec<_> = sks
+            .iter()
+            .map(|sk| (sk.public_key(), sk.sign_with_dst(msgs[0], dst), msgs[0]))
+            .collect();
+        assert_eq!(Ok(()), PublicKey::verify_batch_with_dst(&dst_items, dst));
+        let expected = Error::InvalidSignatures {
+            indices: (0..10).collect(),
+        };
+        assert_eq!(Err(expected), PublicKey::verify_batch(&dst_items));
+    }
+
+    #[test]
+    fn test_verify_batch_shares() {
+        let mut rng = rand::thread_rng();
+        let sk_set = SecretKeySet::random(3, &mut rng);
+        let pk_set = sk_set.public_keys();
+        let msg = "Totally real news";
+        let mut items: Vec<_> = (0..20)
+            .map(|i| {
+                let sig = sk_set.secret_key_share(i).sign(msg);
+                (pk_set.public_key_share(i), sig, msg)
+            }).collect();
+        assert_eq!(Ok(()), PublicKeyShare::verify_batch(&items));
+
+        items[5].1 = sk_set.secret_key_share(5).sign("Fake news");
+        items[6].0 = pk_set.public_key_share(7);
+        let expected = Error::InvalidSignatures {
+            indices: vec![5, 6],
+        };
+        assert_eq!(Err(expected), PublicKeyShare::verify_batch(&items));
+    }
+
     #[test]
     fn test_threshold_sig() {
         let mut rng = rand::thread_rng();
@@ -739,12 +628,83 @@ mod tests {
         assert_ne!(msg[..], decrypted_eve[..]);
 
         // Eve tries to trick Bob into decrypting `msg` xor `v`, but it doesn't validate.
-        let Ciphertext(u, v, w) = ciphertext;
-        let fake_ciphertext = Ciphertext(u, vec![0; v.len()], w);
+        let generic::Ciphertext(u, v, w, label) = ciphertext;
+        let fake_ciphertext = generic::Ciphertext(u, vec![0; v.len()], w, label);
         assert!(!fake_ciphertext.verify());
         assert_eq!(None, sk_bob.decrypt(&fake_ciphertext));
     }
 
+    #[test]
+    fn test_enc_with_label() {
+        let sk_bob: SecretKey = random();
+        let pk_bob = sk_bob.public_key();
+        let msg = b"Muffins in the canteen today! Don't tell Eve!";
+        let ciphertext = pk_bob.encrypt_with_label(&msg[..], b"canteen news");
+        assert!(ciphertext.verify());
+        assert_eq!(b"canteen news", ciphertext.label());
+        let decrypted = sk_bob.decrypt(&ciphertext).expect("invalid ciphertext");
+        assert_eq!(msg[..], decrypted[..]);
+
+        // Eve tries to pass the message off as belonging to a different context.
+        let generic::Ciphertext(u, v, w, _) = ciphertext;
+        let relabeled = generic::Ciphertext(u, v, w, b"weather report".to_vec());
+        assert!(!relabeled.verify());
+        assert_eq!(None, sk_bob.decrypt(&relabeled));
+    }
+
+    #[test]
+    fn test_sig_with_dst() {
+        let mut rng = rand::thread_rng();
+        let sk_set = SecretKeySet::random(2, &mut rng);
+        let pk_set = sk_set.public_keys();
+        let msg = "Totally real news";
+        let (dst0, dst1) = ("NEWS-V01", "GOSSIP-V01");
+
+        let sk: SecretKey = random();
+        let sig = sk.sign_with_dst(msg, dst0);
+        assert!(sk.public_key().verify_with_dst(&sig, msg, dst0));
+        assert!(!sk.public_key().verify_with_dst(&sig, msg, dst1)); // Wrong DST.
+        assert!(!sk.public_key().verify(&sig, msg)); // No DST.
+
+        let sigs: BTreeMap<_, _> = (0..3)
+            .map(|i| (i, sk_set.secret_key_share(i).sign_with_dst(msg, dst0)))
+            .collect();
+        for (i, sig) in &sigs {
+            assert!(pk_set.public_key_share(*i).verify_with_dst(sig, msg, dst0));
+            assert!(!pk_set.public_key_share(*i).verify_with_dst(sig, msg, dst1));
+        }
+        let sig = pk_set.combine_signatures(&sigs).expect("signatures match");
+        assert!(pk_set.public_key().verify_with_dst(&sig, msg, dst0));
+        assert!(!pk_set.public_key().verify_with_dst(&sig, msg, dst1));
+
+        let checked_sig = pk_set
+            .combine_signatures_with_dst(&sigs, msg, dst0)
+            .expect("valid shares");
+        assert_eq!(sig, checked_sig);
+
+        // Shares signed with different tags are rejected.
+        let mut mixed_sigs = sigs.clone();
+        mixed_sigs.insert(0, sk_set.secret_key_share(0).sign_with_dst(msg, dst1));
+        let expected = Error::InvalidShares {
+            indices: vec![ShareIndex::Int(0)],
+            valid: 2,
+            needed: 3,
+        };
+        assert_eq!(
+            Err(expected),
+            pk_set.combine_signatures_with_dst(&mixed_sigs, msg, dst0)
+        );
+        let expected = Error::InvalidShares {
+            indices: vec![ShareIndex::Int(1), ShareIndex::Int(2)],
+            valid: 1,
+            needed: 3,
+        };
+        assert_eq!(
+            Err(expected),
+            pk_set.combine_signatures_with_dst(&mixed_sigs, msg, dst1)
+        );
+    }
+
     #[test]
     fn test_random_extreme_thresholds() {
         let mut rng = rand::thread_rng();
@@ -753,6 +713,65 @@ mod tests {
         assert!(SecretKeySet::try_random(usize::max_value(), &mut rng).is_err());
     }
 
+    #[test]
+    fn test_combine_signatures_verified() {
+        let mut rng = rand::thread_rng();
+        let sk_set = SecretKeySet::random(3, &mut rng);
+        let pk_set = sk_set.public_keys();
+        let msg = "Totally real news";
+
+        // Shares 1 and 4 sign the wrong message, share 6 uses a wrong key.
+        let sigs: Vec<(usize, SignatureShare)> = (0..8)
+            .map(|i| {
+                let sig = match i {
+                    1 | 4 => sk_set.secret_key_share(i).sign("Fake news"),
+                    6 => sk_set.secret_key_share(0).sign(msg),
+                    _ => sk_set.secret_key_share(i).sign(msg),
+                };
+                (i, sig)
+            }).collect();
+        let shares = sigs.iter().map(|(i, sig)| (*i, sig));
+        let (sig, faulty) = pk_set
+            .combine_signatures_verified(shares, msg)
+            .expect("enough valid shares");
+        assert!(pk_set.public_key().verify(&sig, msg));
+        assert_eq!(vec![1, 4], faulty);
+
+        // With only five shares, there are not enough valid ones.
+        let shares = sigs.iter().take(5).map(|(i, sig)| (*i, sig));
+        let expected = Error::InvalidShares {
+            indices: vec![ShareIndex::Int(1), ShareIndex::Int(4)],
+            valid: 3,
+            needed: 4,
+        };
+        assert_eq!(
+            Err(expected),
+            pk_set.combine_signatures_verified(shares, msg)
+        );
+        let shares = sigs.iter().take(3).map(|(i, sig)| (*i, sig));
+        let expected = Error::InvalidShares {
+            indices: vec![ShareIndex::Int(1)],
+            valid: 2,
+            needed: 4,
+        };
+        assert_eq!(
+            Err(expected),
+            pk_set.combine_signatures_verified(shares, msg)
+        );
+
+        // The shares are invalid for a different domain separation tag.
+        let shares = sigs.iter().map(|(i, sig)| (*i, sig));
+        let expected = Error::InvalidShares {
+            indices: (0..8).map(ShareIndex::Int).collect(),
+            valid: 0,
+            needed: 4,
+        };
+        assert_eq!(
+            Err(expected),
+            pk_set.combine_signatures_verified_with_dst(shares, msg, "OTHER-V01")
+        );
+    }
+
     #[test]
     fn test_threshold_enc() {
         let mut rng = rand::thread_rng();
@@ -786,6 +805,91 @@ mod tests {
         assert_eq!(msg[..], decrypted[..]);
     }
 
+    #[test]
+    fn test_decrypt_verified() {
+        let mut rng = rand::thread_rng();
+        let sk_set = SecretKeySet::random(2, &mut rng);
+        let pk_set = sk_set.public_keys();
+        let msg = b"Totally real news";
+        let ciphertext = pk_set.public_key().encrypt(&msg[..]);
+        let other_ciphertext = pk_set.public_key().encrypt(&msg[..]);
+
+        // Share 2 is for a different ciphertext, and share 5 has the wrong index.
+        let shares: Vec<(usize, DecryptionShare)> = (0..6)
+            .map(|i| {
+                let (j, ct) = match i {
+                    2 => (i, &other_ciphertext),
+                    5 => (1, &ciphertext),
+                    _ => (i, &ciphertext),
+                };
+                let dec_share = sk_set.secret_key_share(j).decrypt_share_no_verify(ct);
+                (i, dec_share)
+            }).collect();
+
+        // The decryption share of the wrong ciphertext decrypts to garbage.
+        let first_shares = shares.iter().take(3).map(|(i, share)| (*i, share));
+        let decrypted = pk_set
+            .decrypt(first_shares, &ciphertext)
+            .expect("no duplicate indices");
+        assert_ne!(msg[..], decrypted[..]);
+
+        let (decrypted, faulty) = pk_set
+            .decrypt_verified(shares.iter().map(|(i, share)| (*i, share)), &ciphertext)
+            .expect("enough valid shares");
+        assert_eq!(msg[..], decrypted[..]);
+        assert_eq!(vec![2], faulty);
+
+        // Without shares 3 and 4, there are only two valid ones left.
+        let remaining_shares = shares
+            .iter()
+            .filter(|(i, _)| *i != 3 && *i != 4)
+            .map(|(i, share)| (*i, share));
+        let expected = Error::InvalidShares {
+            indices: vec![ShareIndex::Int(2), ShareIndex::Int(5)],
+            valid: 2,
+            needed: 3,
+        };
+        assert_eq!(
+            Err(expected),
+            pk_set.decrypt_verified(remaining_shares, &ciphertext)
+        );
+
+        // An invalid ciphertext is rejected before the shares are checked.
+        let mut bad_ciphertext = ciphertext.clone();
+        bad_ciphertext.1[0] ^= 1;
+        let shares = shares.iter().map(|(i, share)| (*i, share));
+        assert_eq!(
+            Err(Error::InvalidCiphertext),
+            pk_set.decrypt_verified(shares, &bad_ciphertext)
+        );
+    }
+
+    #[test]
+    fn test_combine_errors() {
+        let mut rng = rand::thread_rng();
+        let sk_set = SecretKeySet::random(2, &mut rng);
+        let pk_set = sk_set.public_keys();
+        let msg = "Totally real news";
+        let sigs: BTreeMap<_, _> = (0..3)
+            .map(|i| (i, sk_set.secret_key_share(i).sign(msg)))
+            .collect();
+
+        let expected = Error::NotEnoughShares {
+            supplied: 2,
+            needed: 3,
+        };
+        assert_eq!(
+            Err(expected),
+            pk_set.combine_signatures(sigs.iter().take(2))
+        );
+
+        let shares = vec![(0, &sigs[&0]), (1, &sigs[&1]), (0, &sigs[&0])];
+        let expected = Error::DuplicateEntry {
+            index: ShareIndex::Int(0),
+        };
+        assert_eq!(Err(expected), pk_set.combine_signatures(shares));
+    }
+
     /// Some basic sanity checks for the `hash_g2` function.
     #[test]
     fn test_hash_g2() {
@@ -808,11 +912,25 @@ mod tests {
         let msg_end1: Vec<u8> = msg.iter().chain(b"end1").cloned().collect();
         let g0 = rng.gen();
         let g1 = rng.gen();
-
-        assert_eq!(hash_g1_g2(g0, &msg), hash_g1_g2(g0, &msg));
-        assert_ne!(hash_g1_g2(g0, &msg), hash_g1_g2(g0, &msg_end0));
-        assert_ne!(hash_g1_g2(g0, &msg_end0), hash_g1_g2(g0, &msg_end1));
-        assert_ne!(hash_g1_g2(g0, &msg), hash_g1_g2(g1, &msg));
+        let label = b"label";
+
+        let hash = hash_g1_g2::<PEngine, _, _>(g0, &msg, label);
+        assert_eq!(hash, hash_g1_g2::<PEngine, _, _>(g0, &msg, label));
+        assert_ne!(hash, hash_g1_g2::<PEngine, _, _>(g0, &msg_end0, label));
+        assert_ne!(hash, hash_g1_g2::<PEngine, _, _>(g1, &msg, label));
+        assert_ne!(hash, hash_g1_g2::<PEngine, _, _>(g0, &msg, b""));
+        let hash_end0 = hash_g1_g2::<PEngine, _, _>(g0, &msg_end0, label);
+        assert_ne!(hash_end0, hash_g1_g2::<PEngine, _, _>(g0, &msg_end1, label));
+        let hash_ab = hash_g1_g2::<PEngine, _, _>(g0, b"ab", b"");
+        assert_ne!(hash_ab, hash_g1_g2::<PEngine, _, _>(g0, b"b", b"a"));
+
+        // Without a label, the hash is the same as before labels were introduced.
+        let mut data = msg_end0[..50].to_vec();
+        data.extend(generic::point_to_bytes(&g0));
+        assert_eq!(
+            hash_g2(&data),
+            hash_g1_g2::<PEngine, _, _>(g0, &msg_end0[..50], b"")
+        );
     }
 
     /// Some basic sanity checks for the `hash_bytes` function.
@@ -821,7 +939,7 @@ mod tests {
         let mut rng = rand::thread_rng();
         let g0 = rng.gen();
         let g1 = rng.gen();
-        let xwh = xor_with_hash;
+        let xwh = xor_with_hash::<G1>;
         assert_eq!(xwh(g0, &[0; 5]), xwh(g0, &[0; 5]));
         assert_ne!(xwh(g0, &[0; 5]), xwh(g1, &[0; 5]));
         assert_eq!(5, xwh(g0, &[0; 5]).len());
@@ -843,4 +961,109 @@ mod tests {
         let deser_sig = bincode::deserialize(&ser_sig).expect("deserialize signature");
         assert_eq!(sig, deser_sig);
     }
+
+    #[test]
+    fn test_bytes() {
+        let mut rng = rand::thread_rng();
+        let sk_set = SecretKeySet::random(2, &mut rng);
+        let pk_set = sk_set.public_keys();
+        let sk_share = sk_set.secret_key_share(1);
+        let pk_share = pk_set.public_key_share(1);
+        let pk = pk_set.public_key();
+        let sig = sk_share.sign("Please sign here: ______");
+        let ct = pk.encrypt_with_label(b"Secret", b"Label");
+        let dec_share = sk_share.decrypt_share(&ct).expect("valid ciphertext");
+        let pop = SecretKey::random().proof_of_possession();
+
+        let decoded = PublicKey::from_bytes(pk.to_bytes());
+        assert_eq!(pk, decoded.expect("public key"));
+        let decoded = PublicKeyShare::from_bytes(pk_share.to_bytes());
+        assert_eq!(pk_share, decoded.expect("public key share"));
+        let decoded = SignatureShare::from_bytes(sig.to_bytes());
+        assert_eq!(sig, decoded.expect("signature share"));
+        let decoded = Signature::from_bytes(sig.0.to_bytes());
+        assert_eq!(sig.0, decoded.expect("signature"));
+        let agg_sig = AggregateSignature::aggregate(vec![sig.0.clone()]);
+        let decoded = AggregateSignature::from_bytes(agg_sig.to_bytes());
+        assert_eq!(agg_sig, decoded.expect("aggregate signature"));
+        let decoded = ProofOfPossession::from_bytes(pop.to_bytes());
+        assert_eq!(pop, decoded.expect("proof of possession"));
+        let decoded = DecryptionShare::from_bytes(dec_share.to_bytes());
+        assert_eq!(dec_share, decoded.expect("decryption share"));
+        let decoded = PublicKeySet::from_bytes(pk_set.to_bytes()).expect("public key set");
+        assert_eq!(pk_set, decoded);
+        let decoded = Ciphertext::from_bytes(ct.to_bytes()).expect("ciphertext");
+        assert_eq!(ct, decoded);
+        assert!(decoded.verify());
+
+        // Wrong lengths are rejected.
+        let pk_bytes = pk.to_bytes();
+        let expected = Error::InvalidLength {
+            expected: pk_bytes.len(),
+            actual: pk_bytes.len() - 1,
+        };
+        assert_eq!(Some(expected), PublicKey::from_bytes(&pk_bytes[1..]).err());
+        let mut pk_set_bytes = pk_set.to_bytes();
+        pk_set_bytes.pop();
+        assert!(PublicKeySet::from_bytes(&pk_set_bytes).is_err());
+        assert!(PublicKeySet::from_bytes(b"").is_err());
+        let ct_bytes = ct.to_bytes();
+        assert!(Ciphertext::from_bytes(&ct_bytes[..(ct_bytes.len() - 7)]).is_err());
+
+        // The identity element is not a valid public key, but it is a valid signature.
+        let zero_bytes = point_to_bytes(&G1::zero());
+        assert!(PublicKey::from_bytes(&zero_bytes).is_err());
+        assert!(DecryptionShare::from_bytes(&zero_bytes).is_ok());
+        let zero_sig = Signature::from_bytes(point_to_bytes(&G2::zero()));
+        assert_eq!(generic::Signature(G2::zero()), zero_sig.expect("identity signature"));
+    }
+
+    #[test]
+    fn test_secret_bytes() {
+        let sk = SecretKey::random();
+        let bytes = sk.to_bytes_secret();
+        assert_eq!(fr_bytes::<Fr>(), bytes.len());
+        assert_eq!(sk, SecretKey::from_bytes(&bytes).expect("valid secret key"));
+        let sk_share: SecretKeyShare = random();
+        let decoded = SecretKeyShare::from_bytes(sk_share.to_bytes_secret());
+        assert_eq!(sk_share, decoded.expect("valid secret key share"));
+        let zero = SecretKey::default();
+        let decoded = SecretKey::from_bytes(zero.to_bytes_secret());
+        assert_eq!(zero, decoded.expect("zero secret key"));
+
+        let expected = Error::InvalidLength {
+            expected: bytes.len(),
+            actual: bytes.len() + 1,
+        };
+        let mut long_bytes = bytes.to_vec();
+        long_bytes.push(0);
+        assert_eq!(Some(expected), SecretKey::from_bytes(&long_bytes).err());
+    }
+
+    #[cfg(not(feature = "use-insecure-test-only-mock-crypto"))]
+    #[test]
+    fn test_invalid_bytes() {
+        // Changing the last byte of the `x` coordinate results in a point that is either not on
+        // the curve, or not in the prime order subgroup.
+        let mut pk_bytes = SecretKey::random().public_key().to_bytes();
+        *pk_bytes.last_mut().expect("nonempty") ^= 1;
+        match PublicKey::from_bytes(&pk_bytes) {
+            Err(Error::InvalidPoint(_)) => (),
+            result => panic!("Unexpected result: {:?}", result),
+        }
+        let mut sig_bytes = SecretKey::random().sign("msg").to_bytes();
+        *sig_bytes.last_mut().expect("nonempty") ^= 1;
+        match Signature::from_bytes(&sig_bytes) {
+            Err(Error::InvalidPoint(_)) => (),
+            result => panic!("Unexpected result: {:?}", result),
+        }
+        // The encoding must have the compression flag set.
+        let zero_bytes = vec![0u8; pk_bytes.len()];
+        assert!(PublicKey::from_bytes(&zero_bytes).is_err());
+        // A secret key's representation must be less than the field's modulus.
+        match SecretKey::from_bytes(vec![0xff; fr_bytes::<Fr>()]) {
+            Err(Error::InvalidFieldElement(_)) => (),
+            result => panic!("Unexpected result: {:?}", result),
+        }
+    }
 }
@@ -0,0 +1,260 @@
+//! The "minimal signature" variant with BLS12-381, i.e. with 48-byte signatures in `G1` and
+//! 96-byte public keys in `G2`.
+//!
+//! The types in this module are aliases for the ones in `generic::min_sig` with the engine
+//! `PEngine`. A `SecretKey`, `SecretKeyShare` or `SecretKeySet` of the main variant can be
+//! converted into the corresponding type of this module with `From`, e.g. to use a key that was
+//! derived with `SecretKey::from_seed`.
+
+use error::Result;
+use generic;
+use into_fr::IntoFr;
+use {hash_to_g1, PEngine};
+
+/// A public key in `G2`.
+pub type PublicKey = generic::min_sig::PublicKey<PEngine>;
+/// A public key share in `G2`.
+pub type PublicKeyShare = generic::min_sig::PublicKeyShare<PEngine>;
+/// A signature in `G1`.
+pub type Signature = generic::min_sig::Signature<PEngine>;
+/// A signature share in `G1`.
+pub type SignatureShare = generic::min_sig::SignatureShare<PEngine>;
+/// A secret key that signs in `G1`.
+pub type SecretKey = generic::min_sig::SecretKey<PEngine>;
+/// A secret key share that signs in `G1`.
+pub type SecretKeyShare = generic::min_sig::SecretKeyShare<PEngine>;
+/// A public key in `G2` and an associated set of public key shares.
+pub type PublicKeySet = generic::min_sig::PublicKeySet<PEngine>;
+/// A secret key and an associated set of secret key shares, that sign in `G1`.
+pub type SecretKeySet = generic::min_sig::SecretKeySet<PEngine>;
+
+impl PublicKey {
+    /// Returns `true` if the signature matches the message and domain separation tag.
+    ///
+    /// This is equivalent to `verify_g1(sig, hash_to_g1(msg, dst))`.
+    pub fn verify_with_dst<M, D>(&self, sig: &Signature, msg: M, dst: D) -> bool
+    where
+        M: AsRef<[u8]>,
+        D: AsRef<[u8]>,
+    {
+        self.verify_g1(sig, hash_to_g1(msg, dst))
+    }
+}
+
+impl PublicKeyShare {
+    /// Returns `true` if the signature matches the message and domain separation tag.
+    ///
+    /// This is equivalent to `verify_g1(sig, hash_to_g1(msg, dst))`.
+    pub fn verify_with_dst<M, D>(&self, sig: &SignatureShare, msg: M, dst: D) -> bool
+    where
+        M: AsRef<[u8]>,
+        D: AsRef<[u8]>,
+    {
+        self.verify_g1(sig, hash_to_g1(msg, dst))
+    }
+}
+
+impl SecretKey {
+    /// Signs the given message, using the domain separation tag `dst`.
+    ///
+    /// This is equivalent to `sign_g1(hash_to_g1(msg, dst))`.
+    pub fn sign_with_dst<M: AsRef<[u8]>, D: AsRef<[u8]>>(&self, msg: M, dst: D) -> Signature {
+        self.sign_g1(hash_to_g1(msg, dst))
+    }
+}
+
+impl SecretKeyShare {
+    /// Signs the given message, using the domain separation tag `dst`.
+    ///
+    /// All shares that are combined into a signature must use the same tag, and the combined
+    /// signature must be verified with `PublicKey::verify_with_dst` and that tag.
+    pub fn sign_with_dst<M, D>(&self, msg: M, dst: D) -> SignatureShare
+    where
+        M: AsRef<[u8]>,
+        D: AsRef<[u8]>,
+    {
+        generic::min_sig::SignatureShare(self.0.sign_with_dst(msg, dst))
+    }
+}
+
+impl PublicKeySet {
+    /// Verifies the shares and combines the valid ones into a signature of `msg` with the domain
+    /// separation tag `dst`. Returns the signature and the indices of the invalid shares.
+    pub fn combine_signatures_verified_with_dst<'a, T, I, M, D>(
+        &self,
+        shares: I,
+        msg: M,
+        dst: D,
+    ) -> Result<(Signature, Vec<T>)>
+    where
+        I: IntoIterator<Item = (T, &'a SignatureShare)>,
+        T: IntoFr,
+        M: AsRef<[u8]>,
+        D: AsRef<[u8]>,
+    {
+        self.combine_signatures_verified_g1(shares, hash_to_g1(msg, dst))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::collections::BTreeMap;
+
+    use rand::{self, random};
+
+    use error::{Error, ShareIndex};
+
+    #[test]
+    fn test_simple_sig() {
+        let sk0: SecretKey = random();
+        let sk1: SecretKey = random();
+        let pk0 = sk0.public_key();
+        let msg0 = b"Real news";
+        let msg1 = b"Fake news";
+        assert!(pk0.verify(&sk0.sign(msg0), msg0));
+        assert!(!pk0.verify(&sk1.sign(msg0), msg0)); // Wrong key.
+        assert!(!pk0.verify(&sk0.sign(msg1), msg0)); // Wrong message.
+
+        let dst = b"TEST-SIG_BLS12381G1_XMD:SHA-256_SSWU_RO_";
+        let sig = sk0.sign_with_dst(msg0, &dst[..]);
+        assert!(pk0.verify_with_dst(&sig, msg0, &dst[..]));
+        assert!(!pk0.verify_with_dst(&sig, msg0, b"OTHER-DST")); // Wrong DST.
+        assert!(!pk0.verify(&sig, msg0)); // No DST.
+
+        // The secret key can be converted to and from the main variant.
+        let main_sk: ::SecretKey = sk0.clone().into();
+        assert_eq!(sk0, SecretKey::from(main_sk));
+    }
+
+    #[test]
+    fn test_threshold_sig() {
+        let mut rng = rand::thread_rng();
+        let sk_set = SecretKeySet::random(3, &mut rng);
+        let pk_set = sk_set.public_keys();
+        assert_eq!(3, pk_set.threshold());
+        assert_eq!(sk_set.secret_key().public_key(), pk_set.public_key());
+        let msg = "Totally real news";
+
+        // The threshold is 3, so 4 signature shares will suffice to recreate the share.
+        let sigs: BTreeMap<_, _> = [5, 8, 7, 10]
+            .iter()
+            .map(|&i| (i, sk_set.secret_key_share(i).sign(msg)))
+            .collect();
+        for (i, sig) in &sigs {
+            let pk_share = pk_set.public_key_share(*i);
+            assert_eq!(sk_set.secret_key_share(*i).public_key_share(), pk_share);
+            assert!(pk_share.verify(sig, msg));
+        }
+        let sig = pk_set.combine_signatures(&sigs).expect("signatures match");
+        assert!(pk_set.public_key().verify(&sig, msg));
+
+        // A different set of signatories produces the same signature.
+        let sigs2: BTreeMap<_, _> = [42, 43, 44, 45]
+            .iter()
+            .map(|&i| (i, sk_set.secret_key_share(i).sign(msg)))
+            .collect();
+        let sig2 = pk_set.combine_signatures(&sigs2).expect("signatures match");
+        assert_eq!(sig, sig2);
+    }
+
+    #[test]
+    fn test_combine_signatures_verified() {
+        let mut rng = rand::thread_rng();
+        let sk_set = SecretKeySet::random(3, &mut rng);
+        let pk_set = sk_set.public_keys();
+        let msg = "Totally real news";
+        let dst = "NEWS-V01";
+
+        // Shares 1 and 4 sign the wrong message, share 6 uses a wrong key.
+        let sigs: Vec<(usize, SignatureShare)> = (0..8)
+            .map(|i| {
+                let sig = match i {
+                    1 | 4 => sk_set.secret_key_share(i).sign_with_dst("Fake news", dst),
+                    6 => sk_set.secret_key_share(0).sign_with_dst(msg, dst),
+                    _ => sk_set.secret_key_share(i).sign_with_dst(msg, dst),
+                };
+                (i, sig)
+            }).collect();
+        let shares = sigs.iter().map(|(i, sig)| (*i, sig));
+        let (sig, faulty) = pk_set
+            .combine_signatures_verified_with_dst(shares, msg, dst)
+            .expect("enough valid shares");
+        assert!(pk_set.public_key().verify_with_dst(&sig, msg, dst));
+        assert_eq!(vec![1, 4], faulty);
+
+        // With only five shares, there are not enough valid ones.
+        let shares = sigs.iter().take(5).map(|(i, sig)| (*i, sig));
+        let expected = Error::InvalidShares {
+            indices: vec![ShareIndex::Int(1), ShareIndex::Int(4)],
+            valid: 3,
+            needed: 4,
+        };
+        assert_eq!(
+            Err(expected),
+            pk_set.combine_signatures_verified_with_dst(shares, msg, dst)
+        );
+
+        // The shares are invalid without the domain separation tag.
+        let shares = sigs.iter().map(|(i, sig)| (*i, sig));
+        let expected = Error::InvalidShares {
+            indices: (0..8).map(ShareIndex::Int).collect(),
+            valid: 0,
+            needed: 4,
+        };
+        assert_eq!(
+            Err(expected),
+            pk_set.combine_signatures_verified(shares, msg)
+        );
+    }
+
+    #[test]
+    fn test_serde() {
+        use bincode;
+
+        let mut rng = rand::thread_rng();
+        let sk_set = SecretKeySet::random(2, &mut rng);
+        let pk_set = sk_set.public_keys();
+        let sig = sk_set.secret_key_share(1).sign("Please sign here: ______");
+        let pk = pk_set.public_key();
+
+        let ser_pk = bincode::serialize(&pk).expect("serialize public key");
+        let deser_pk = bincode::deserialize(&ser_pk).expect("deserialize public key");
+        assert_eq!(pk, deser_pk);
+        let ser_sig = bincode::serialize(&sig).expect("serialize signature share");
+        let deser_sig = bincode::deserialize(&ser_sig).expect("deserialize signature share");
+        assert_eq!(sig, deser_sig);
+        let ser_pk_set = bincode::serialize(&pk_set).expect("serialize public key set");
+        let deser_pk_set = bincode::deserialize(&ser_pk_set).expect("deserialize public key set");
+        assert_eq!(pk_set, deser_pk_set);
+    }
+
+    #[test]
+    fn test_bytes() {
+        let mut rng = rand::thread_rng();
+        let sk_set = SecretKeySet::random(2, &mut rng);
+        let pk_set = sk_set.public_keys();
+        let pk_share = pk_set.public_key_share(1);
+        let pk = pk_set.public_key();
+        let sig = sk_set.secret_key_share(1).sign("Please sign here: ______");
+
+        let decoded = PublicKey::from_bytes(pk.to_bytes());
+        assert_eq!(pk, decoded.expect("public key"));
+        let decoded = PublicKeyShare::from_bytes(pk_share.to_bytes());
+        assert_eq!(pk_share, decoded.expect("public key share"));
+        let decoded = SignatureShare::from_bytes(sig.to_bytes());
+        assert_eq!(sig, decoded.expect("signature share"));
+        let decoded = PublicKeySet::from_bytes(pk_set.to_bytes());
+        assert_eq!(pk_set, decoded.expect("public key set"));
+
+        // Signatures are in `G1` and keys in `G2`, so their sizes are swapped.
+        let main_sk_share: ::SecretKeyShare = sk_set.secret_key_share(1).into();
+        let main_sig = main_sk_share.sign("Please sign here: ______");
+        assert_eq!(
+            main_sk_share.public_key_share().to_bytes().len(),
+            sig.to_bytes().len()
+        );
+        assert_eq!(main_sig.to_bytes().len(), pk_share.to_bytes().len());
+    }
+}
@@ -0,0 +1,27 @@
+//! Mock versions of the hash-to-curve functions.
+//!
+//! These don't follow any standard: They only make sure that hashing the same message with
+//! different domain separation tags yields different group elements.
+
+use byteorder::{BigEndian, ByteOrder};
+
+use super::super::{hash_g2, G1, G2};
+
+/// Hashes the message to an element of `G1`, using the given domain separation tag.
+pub fn hash_to_g1<M: AsRef<[u8]>, D: AsRef<[u8]>>(msg: M, dst: D) -> G1 {
+    hash_g2(prepend_dst(msg.as_ref(), dst.as_ref()))
+}
+
+/// Hashes the message to an element of `G2`, using the given domain separation tag.
+pub fn hash_to_g2<M: AsRef<[u8]>, D: AsRef<[u8]>>(msg: M, dst: D) -> G2 {
+    hash_g2(prepend_dst(msg.as_ref(), dst.as_ref()))
+}
+
+/// Returns the length of `dst`, followed by `dst` and `msg`.
+fn prepend_dst(msg: &[u8], dst: &[u8]) -> Vec<u8> {
+    let mut result = vec![0; 8];
+    BigEndian::write_u64(&mut result, dst.len() as u64);
+    result.extend_from_slice(dst);
+    result.extend_from_slice(msg);
+    result
+}
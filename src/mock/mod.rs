@@ -10,15 +10,17 @@
 //! enabling this in production code of any application will immediately break its cryptographic
 //! security.
 
+#[cfg(feature = "use-insecure-test-only-mock-crypto")]
+pub mod hash_to_curve;
 pub mod ms8;
 
 use std::{fmt, mem, slice};
 
-use pairing::{EncodedPoint, Field, GroupDecodingError, PrimeField};
+use pairing::{
+    CurveAffine, CurveProjective, EncodedPoint, Engine, Field, GroupDecodingError, PrimeField,
+};
 use rand;
 
-use super::{CurveAffine, CurveProjective, Engine};
-
 pub use self::ms8::Mersenne8;
 
 /// A `pairing` Engine based on `Mersenne8` prime fields.
@@ -85,7 +87,7 @@ impl Engine for Mocktography {
     // In newer versions of pairing, this must be moved to `ScalarEngine`:
     type Fr = Mersenne8;
 
-    fn miller_loop<'a, I>(_i: I) -> Self::Fqk
+    fn miller_loop<'a, I>(i: I) -> Self::Fqk
     where
         I: IntoIterator<
             Item = &'a (
@@ -94,13 +96,14 @@ impl Engine for Mocktography {
             ),
         >,
     {
-        // Unused?
-        unimplemented!()
+        // Our pairing is bilinear with respect to _addition_ in the target group, so the
+        // "product" of several pairings is their sum.
+        i.into_iter()
+            .fold(Mersenne8::zero(), |acc, &(p, q)| acc + p.pairing_with(q))
     }
 
-    fn final_exponentiation(_: &Self::Fqk) -> Option<Self::Fqk> {
-        // Unused?
-        unimplemented!()
+    fn final_exponentiation(r: &Self::Fqk) -> Option<Self::Fqk> {
+        Some(*r)
     }
 
     fn pairing<G1, G2>(p: G1, q: G2) -> Self::Fqk
@@ -293,7 +296,7 @@ mod test {
     #![cfg_attr(feature = "cargo-clippy", allow(unreadable_literal))]
 
     use super::{Mersenne8, Mocktography, Ms8Affine};
-    use Engine;
+    use pairing::Engine;
 
     #[test]
     fn example_pairings() {
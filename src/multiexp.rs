@@ -0,0 +1,113 @@
+//! Multi-scalar multiplication, i.e. computing `sum(scalars[i] * bases[i])`.
+//!
+//! This uses Pippenger's bucket method: The scalars are split into windows of `c` bits. For each
+//! window, every base is added to one of `2^c - 1` buckets, according to the scalar's bits in that
+//! window, and the buckets are summed up with their weights using only additions. That takes
+//! about `b / c * (n + 2^(c + 1))` group additions for `n` scalars of `b` bits, instead of
+//! `n * b * 3 / 2` for one scalar multiplication per term.
+
+use pairing::{CurveAffine, CurveProjective, PrimeField, PrimeFieldRepr};
+
+/// Below this number of terms, the individual scalar multiplications are faster.
+const MIN_TERMS: usize = 4;
+
+/// Returns `sum(scalars[i] * bases[i])`.
+///
+/// # Panics
+///
+/// Panics if the slices have different lengths.
+pub(crate) fn multiexp<C: CurveProjective>(bases: &[C], scalars: &[C::Scalar]) -> C {
+    assert_eq!(bases.len(), scalars.len(), "one scalar per base expected");
+    if bases.len() < MIN_TERMS {
+        let mut result = C::zero();
+        for (base, scalar) in bases.iter().zip(scalars) {
+            result.add_assign(&base.into_affine().mul(*scalar));
+        }
+        return result;
+    }
+
+    let c = window_bits(bases.len());
+    let n_windows = (<C::Scalar as PrimeField>::NUM_BITS as usize).div_ceil(c);
+    let digits: Vec<Vec<usize>> = scalars
+        .iter()
+        .map(|scalar| windows(scalar.into_repr(), c, n_windows))
+        .collect();
+    let mut normalized = bases.to_vec();
+    C::batch_normalization(&mut normalized);
+    let bases: Vec<C::Affine> = normalized.iter().map(C::into_affine).collect();
+
+    let mut buckets = vec![C::zero(); (1 << c) - 1];
+    let mut result = C::zero();
+    for window in (0..n_windows).rev() {
+        for _ in 0..c {
+            result.double();
+        }
+        for bucket in &mut buckets {
+            *bucket = C::zero();
+        }
+        for (scalar_digits, base) in digits.iter().zip(&bases) {
+            let index = scalar_digits[window];
+            if index != 0 {
+                buckets[index - 1].add_assign_mixed(base);
+            }
+        }
+        // Bucket `i` is added `i + 1` times: once for each running sum that includes it.
+        let mut running_sum = C::zero();
+        for bucket in buckets.iter().rev() {
+            running_sum.add_assign(bucket);
+            result.add_assign(&running_sum);
+        }
+    }
+    result
+}
+
+/// Returns the window size in bits for `n` terms, that approximately minimizes the number of
+/// group operations.
+fn window_bits(n: usize) -> usize {
+    if n < 32 {
+        3
+    } else {
+        (n as f64).ln().ceil() as usize
+    }
+}
+
+/// Splits `repr` into `count` windows of `width` bits each, starting with the least significant
+/// one, and returns their values.
+fn windows<R: PrimeFieldRepr>(mut repr: R, width: usize, count: usize) -> Vec<usize> {
+    (0..count)
+        .map(|_| {
+            let mut value = 0;
+            for bit in 0..width {
+                if repr.is_odd() {
+                    value |= 1 << bit;
+                }
+                repr.div2();
+            }
+            value
+        }).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use rand::{self, Rng};
+
+    use {Fr, G1};
+
+    #[test]
+    fn test_multiexp() {
+        let mut rng = rand::thread_rng();
+        for &n in &[0, 1, 3, 4, 10, 40, 100] {
+            let bases: Vec<G1> = rng.gen_iter().take(n).collect();
+            let scalars: Vec<Fr> = rng.gen_iter().take(n).collect();
+            let mut expected = G1::zero();
+            for (base, scalar) in bases.iter().zip(&scalars) {
+                let mut term = *base;
+                term.mul_assign(*scalar);
+                expected.add_assign(&term);
+            }
+            assert_eq!(expected, multiexp(&bases, &scalars));
+        }
+    }
+}
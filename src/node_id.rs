@@ -0,0 +1,184 @@
+//! Arbitrary participant identifiers.
+//!
+//! The share and combine methods of the key sets identify participants by an index `i`, and use
+//! the polynomial's value at `i + 1`. A `NodeId` is such an index, derived from the hash of an
+//! identifier's bytes, e.g. a node's serialized public key or name, so it can be passed to these
+//! methods directly instead of an integer. `NodeIds` maps all participants' identifiers to `NodeId`s, and
+//! makes sure that no two of them collide.
+
+use std::cmp::Ordering;
+use std::collections::hash_map::{self, HashMap};
+use std::hash::Hash;
+
+use pairing::{Field, PrimeField};
+use rand::Rng;
+
+use error::{Error, Result};
+use into_fr::IntoFr;
+use {rng_from_hash, serde_impl, Fr};
+
+/// The prefix of the bytes that are hashed to obtain a `NodeId`.
+const NODE_ID_DST: &[u8] = b"THRESHOLD_CRYPTO_NODE_ID_";
+
+/// A participant's index, derived from an arbitrary identifier. It is never `-1`, so the value
+/// of the polynomial at `index + 1` is never the master key.
+#[derive(Deserialize, Serialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct NodeId(#[serde(with = "serde_impl::field")] Fr);
+
+impl PartialOrd for NodeId {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for NodeId {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.into_repr().cmp(&other.0.into_repr())
+    }
+}
+
+impl IntoFr for NodeId {
+    fn into_fr(self) -> Fr {
+        self.0
+    }
+}
+
+impl NodeId {
+    /// Returns the index derived from the given identifier.
+    ///
+    /// The index only depends on the identifier's bytes, which are hashed with SHA3, so all nodes
+    /// compute the same one, independent of the platform and Rust version.
+    pub fn new<B: AsRef<[u8]>>(id: B) -> NodeId {
+        let mut data = NODE_ID_DST.to_vec();
+        data.extend_from_slice(id.as_ref());
+        let mut rng = rng_from_hash(&data);
+        let mut minus_one = Fr::one();
+        minus_one.negate();
+        loop {
+            let index: Fr = rng.gen();
+            if index != minus_one {
+                return NodeId(index);
+            }
+        }
+    }
+}
+
+/// The participants' identifiers, and the `NodeId` of each of them.
+#[derive(Clone, Debug)]
+pub struct NodeIds<N: AsRef<[u8]> + Hash + Eq> {
+    ids: HashMap<N, NodeId>,
+}
+
+impl<N: AsRef<[u8]> + Hash + Eq> NodeIds<N> {
+    /// Maps the given identifiers to their `NodeId`s.
+    ///
+    /// Returns `Error::DuplicateEntry` if an identifier appears more than once, and
+    /// `Error::IdCollision` if two different identifiers are mapped to the same index.
+    pub fn new<I: IntoIterator<Item = N>>(ids: I) -> Result<Self> {
+        let mut result = HashMap::new();
+        let mut indices: Vec<NodeId> = Vec::new();
+        for id in ids {
+            let node_id = NodeId::new(&id);
+            match result.entry(id) {
+                hash_map::Entry::Occupied(_) => {
+                    return Err(Error::DuplicateEntry {
+                        index: node_id.into_share_index(),
+                    });
+                }
+                hash_map::Entry::Vacant(entry) => {
+                    entry.insert(node_id);
+                }
+            }
+            indices.push(node_id);
+        }
+        indices.sort();
+        if indices.windows(2).any(|w| w[0] == w[1]) {
+            return Err(Error::IdCollision);
+        }
+        Ok(NodeIds { ids: result })
+    }
+
+    /// Returns the `NodeId` of the given participant, or `None` if it is unknown.
+    pub fn get(&self, id: &N) -> Option<NodeId> {
+        self.ids.get(id).cloned()
+    }
+
+    /// Returns the number of participants.
+    pub fn len(&self) -> usize {
+        self.ids.len()
+    }
+
+    /// Returns `true` if there are no participants.
+    pub fn is_empty(&self) -> bool {
+        self.ids.is_empty()
+    }
+
+    /// Returns an iterator over the participants' identifiers and `NodeId`s, in arbitrary order.
+    pub fn iter<'a>(&'a self) -> hash_map::Iter<'a, N, NodeId> {
+        self.ids.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::collections::BTreeMap;
+
+    use rand;
+
+    use {SecretKey, SecretKeySet};
+
+    #[test]
+    fn test_node_ids() {
+        let mut rng = rand::thread_rng();
+        let sk_set = SecretKeySet::random(2, &mut rng);
+        let pk_set = sk_set.public_keys();
+        let names = vec!["Alice", "Bob", "Carol", "Dave"];
+        let ids = NodeIds::new(names.clone()).expect("distinct IDs");
+        assert_eq!(4, ids.len());
+        assert_eq!(Some(NodeId::new("Bob")), ids.get(&"Bob"));
+        assert_eq!(None, ids.get(&"Eve"));
+
+        // Sign with the shares of Bob, Carol and Dave.
+        let msg = "Totally real news";
+        let sigs: BTreeMap<NodeId, _> = names[1..]
+            .iter()
+            .map(|name| {
+                let node_id = ids.get(name).expect("known ID");
+                let sk_share = sk_set.secret_key_share(node_id);
+                let sig = sk_share.sign(msg);
+                assert!(pk_set.public_key_share(node_id).verify(&sig, msg));
+                (node_id, sig)
+            }).collect();
+        let sig = pk_set.combine_signatures(&sigs).expect("enough shares");
+        assert!(pk_set.public_key().verify(&sig, msg));
+
+        // Public keys can be identifiers, too.
+        let pub_keys = (0..3).map(|_| rng.gen::<SecretKey>().public_key().to_bytes());
+        let ids = NodeIds::new(pub_keys).expect("distinct IDs");
+        let shares = ids
+            .iter()
+            .map(|(pk, node_id)| (pk, sk_set.secret_key_share(node_id)));
+        let sigs: Vec<_> = shares
+            .map(|(pk, sk_share)| (pk, sk_share.sign(msg)))
+            .collect();
+        let shares = sigs.iter().map(|(pk, sig)| (NodeId::new(pk), sig));
+        assert_eq!(
+            sig,
+            pk_set.combine_signatures(shares).expect("enough shares")
+        );
+    }
+
+    #[test]
+    fn test_duplicate_node_ids() {
+        let expected = Error::DuplicateEntry {
+            index: NodeId::new("5").into_share_index(),
+        };
+        let result = NodeIds::new(vec!["4", "5", "6", "5"]);
+        assert_eq!(Some(expected), result.err());
+        assert_eq!(NodeId::new("5"), NodeId::new(b"5"));
+        assert_eq!(NodeId::new("5"), NodeId::new(vec![b'5']));
+        assert_ne!(NodeId::new("5"), NodeId::new("05"));
+    }
+}
@@ -15,74 +15,50 @@
 //!
 //! This module defines univariate polynomials (in one variable) and _symmetric_ bivariate
 //! polynomials (in two variables) over a field `Fr`, as well as their _commitments_ in `G`.
+//!
+//! These commitments reveal `x * g`, so they are only hiding if `x` itself is random and
+//! infeasible to guess. _Pedersen commitments_ use a second generator `h`, whose discrete
+//! logarithm with respect to `g` nobody knows, and a random blinding value `r`, and publish
+//! `x * g + r * h` instead. Since for every `x` there is an `r` that results in that value, they
+//! reveal no information about `x` at all. To verify a value `f(x)`, the recipient also needs the
+//! blinding polynomial's value `r(x)`.
+//!
+//! The types in this module use the engine `PEngine`. The `generic::poly` module has versions of
+//! them for any pairing engine; only the Pedersen commitments are specific to BLS12-381.
 
-use std::borrow::Borrow;
-use std::fmt::{self, Debug, Formatter};
+use std::cmp;
 use std::hash::{Hash, Hasher};
-use std::mem::size_of_val;
-use std::{cmp, iter, ops};
+use std::ops;
 
-use super::{Fr, G1Affine, G1};
+use super::{hash_to_g1, Fr, G1Affine, PEngine, G1};
 use pairing::{CurveAffine, CurveProjective, Field};
-use rand::Rng;
 
-use error::{Error, Result};
+use generic;
+use generic::poly::{bivar_evaluate_g1, bivar_row_g1, evaluate_g1};
 use into_fr::IntoFr;
-use secret::{clear_fr, ContainsSecret, MemRange, Safe};
-
-/// A univariate polynomial in the prime field.
-#[derive(Serialize, Deserialize, PartialEq, Eq)]
-pub struct Poly {
-    /// The coefficients of a polynomial.
-    #[serde(with = "super::serde_impl::field_vec")]
-    pub(super) coeff: Vec<Fr>,
-}
 
-/// Creates a new `Poly` with the same coefficients as another polynomial.
-impl Clone for Poly {
-    fn clone(&self) -> Self {
-        Poly::from(self.coeff.clone())
-    }
-}
+/// The domain separation tag for the second generator `h` of Pedersen commitments.
+const PEDERSEN_H_DST: &[u8] = b"THRESHOLD_CRYPTO_PEDERSEN_H_BLS12381G1_XMD:SHA-256_SSWU_RO_";
 
-/// A debug statement where the `coeff` vector of prime field elements has been redacted.
-impl Debug for Poly {
-    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
-        f.debug_struct("Poly").field("coeff", &"...").finish()
-    }
+lazy_static! {
+    /// The second generator of `G1` for Pedersen commitments. It is the hash of a fixed message,
+    /// so nobody knows its discrete logarithm with respect to `G1Affine::one()`.
+    static ref PEDERSEN_H: G1 = hash_to_g1(b"pedersen generator", PEDERSEN_H_DST);
 }
 
-#[cfg_attr(feature = "cargo-clippy", allow(suspicious_op_assign_impl))]
-impl<B: Borrow<Poly>> ops::AddAssign<B> for Poly {
-    fn add_assign(&mut self, rhs: B) {
-        let len = self.coeff.len();
-        let rhs_len = rhs.borrow().coeff.len();
-        if rhs_len > len {
-            self.coeff.resize(rhs_len, Fr::zero());
-        }
-        for (self_c, rhs_c) in self.coeff.iter_mut().zip(&rhs.borrow().coeff) {
-            Field::add_assign(self_c, rhs_c);
-        }
-        self.remove_zeros();
-    }
+/// Returns the second generator `h` of `G1` that is used in Pedersen commitments.
+pub fn pedersen_generator() -> G1 {
+    *PEDERSEN_H
 }
 
-impl<'a, B: Borrow<Poly>> ops::Add<B> for &'a Poly {
-    type Output = Poly;
-
-    fn add(self, rhs: B) -> Poly {
-        (*self).clone() + rhs
-    }
-}
-
-impl<B: Borrow<Poly>> ops::Add<B> for Poly {
-    type Output = Poly;
-
-    fn add(mut self, rhs: B) -> Poly {
-        self += rhs;
-        self
-    }
-}
+/// A univariate polynomial in the prime field.
+pub type Poly = generic::poly::Poly<PEngine>;
+/// A commitment to a univariate polynomial.
+pub type Commitment = generic::poly::Commitment<PEngine>;
+/// A symmetric bivariate polynomial in the prime field.
+pub type BivarPoly = generic::poly::BivarPoly<PEngine>;
+/// A commitment to a symmetric bivariate polynomial.
+pub type BivarCommitment = generic::poly::BivarCommitment<PEngine>;
 
 impl<'a> ops::Add<Fr> for Poly {
     type Output = Poly;
@@ -102,38 +78,8 @@ impl<'a> ops::Add<u64> for Poly {
     type Output = Poly;
 
     fn add(self, rhs: u64) -> Self::Output {
-        self + rhs.into_fr()
-    }
-}
-
-impl<B: Borrow<Poly>> ops::SubAssign<B> for Poly {
-    fn sub_assign(&mut self, rhs: B) {
-        let len = self.coeff.len();
-        let rhs_len = rhs.borrow().coeff.len();
-        if rhs_len > len {
-            self.coeff.resize(rhs_len, Fr::zero());
-        }
-        for (self_c, rhs_c) in self.coeff.iter_mut().zip(&rhs.borrow().coeff) {
-            Field::sub_assign(self_c, rhs_c);
-        }
-        self.remove_zeros();
-    }
-}
-
-impl<'a, B: Borrow<Poly>> ops::Sub<B> for &'a Poly {
-    type Output = Poly;
-
-    fn sub(self, rhs: B) -> Poly {
-        (*self).clone() - rhs
-    }
-}
-
-impl<B: Borrow<Poly>> ops::Sub<B> for Poly {
-    type Output = Poly;
-
-    fn sub(mut self, rhs: B) -> Poly {
-        self -= rhs;
-        self
+        let rhs: Fr = rhs.into_fr();
+        self + rhs
     }
 }
 
@@ -152,58 +98,14 @@ impl<'a> ops::Sub<u64> for Poly {
     type Output = Poly;
 
     fn sub(self, rhs: u64) -> Self::Output {
-        self - rhs.into_fr()
-    }
-}
-
-// Clippy thinks using any `+` and `-` in a `Mul` implementation is suspicious.
-#[cfg_attr(feature = "cargo-clippy", allow(suspicious_arithmetic_impl))]
-impl<'a, B: Borrow<Poly>> ops::Mul<B> for &'a Poly {
-    type Output = Poly;
-
-    fn mul(self, rhs: B) -> Self::Output {
-        let rhs = rhs.borrow();
-        if rhs.is_zero() || self.is_zero() {
-            return Poly::zero();
-        }
-        let n_coeffs = self.coeff.len() + rhs.coeff.len() - 1;
-        let mut coeffs = vec![Fr::zero(); n_coeffs];
-        let mut tmp = Safe::new(Box::new(Fr::zero()));
-        for (i, ca) in self.coeff.iter().enumerate() {
-            for (j, cb) in rhs.coeff.iter().enumerate() {
-                *tmp = *ca;
-                tmp.mul_assign(cb);
-                coeffs[i + j].add_assign(&*tmp);
-            }
-        }
-        Poly::from(coeffs)
-    }
-}
-
-impl<B: Borrow<Poly>> ops::Mul<B> for Poly {
-    type Output = Poly;
-
-    fn mul(self, rhs: B) -> Self::Output {
-        &self * rhs
-    }
-}
-
-impl<B: Borrow<Self>> ops::MulAssign<B> for Poly {
-    fn mul_assign(&mut self, rhs: B) {
-        *self = &*self * rhs;
+        let rhs: Fr = rhs.into_fr();
+        self - rhs
     }
 }
 
 impl ops::MulAssign<Fr> for Poly {
     fn mul_assign(&mut self, rhs: Fr) {
-        if rhs.is_zero() {
-            self.zero_secret();
-            self.coeff.clear();
-        } else {
-            for c in &mut self.coeff {
-                Field::mul_assign(c, &rhs);
-            }
-        }
+        self.mul_assign_fr(&rhs);
     }
 }
 
@@ -211,12 +113,7 @@ impl<'a> ops::Mul<&'a Fr> for Poly {
     type Output = Poly;
 
     fn mul(mut self, rhs: &Fr) -> Self::Output {
-        if rhs.is_zero() {
-            self.zero_secret();
-            self.coeff.clear();
-        } else {
-            self.coeff.iter_mut().for_each(|c| c.mul_assign(rhs));
-        }
+        self.mul_assign_fr(rhs);
         self
     }
 }
@@ -250,191 +147,31 @@ impl ops::Mul<u64> for Poly {
     type Output = Poly;
 
     fn mul(self, rhs: u64) -> Self::Output {
-        self * rhs.into_fr()
-    }
-}
-
-impl Drop for Poly {
-    fn drop(&mut self) {
-        self.zero_secret();
-    }
-}
-
-/// Creates a new `Poly` instance from a vector of prime field elements representing the
-/// coefficients of the polynomial.
-impl From<Vec<Fr>> for Poly {
-    fn from(coeff: Vec<Fr>) -> Self {
-        Poly { coeff }
-    }
-}
-
-impl ContainsSecret for Poly {
-    fn secret_memory(&self) -> MemRange {
-        let ptr = self.coeff.as_ptr() as *mut u8;
-        let n_bytes = size_of_val(self.coeff.as_slice());
-        MemRange { ptr, n_bytes }
+        let rhs: Fr = rhs.into_fr();
+        self * rhs
     }
 }
 
 impl Poly {
-    /// Creates a random polynomial.
-    ///
-    /// # Panics
-    ///
-    /// Panics if the `degree` is too large for the coefficients to fit into a `Vec`.
-    pub fn random<R: Rng>(degree: usize, rng: &mut R) -> Self {
-        Poly::try_random(degree, rng)
-            .unwrap_or_else(|e| panic!("Failed to create random `Poly`: {}", e))
-    }
-
-    /// Creates a random polynomial. This constructor is identical to the `Poly::random()`
-    /// constructor in every way except that this constructor will return an `Err` where
-    /// `try_random` would return an error.
-    pub fn try_random<R: Rng>(degree: usize, rng: &mut R) -> Result<Self> {
-        if degree == usize::max_value() {
-            return Err(Error::DegreeTooHigh);
-        }
-        let coeff: Vec<Fr> = (0..=degree).map(|_| rng.gen()).collect();
-        Ok(Poly::from(coeff))
-    }
-
-    /// Returns the polynomial with constant value `0`.
-    pub fn zero() -> Self {
-        Poly { coeff: vec![] }
-    }
-
-    /// Returns `true` if the polynomial is the constant value `0`.
-    pub fn is_zero(&self) -> bool {
-        self.coeff.iter().all(|coeff| coeff.is_zero())
-    }
-
-    /// Returns the polynomial with constant value `1`.
-    pub fn one() -> Self {
-        Poly::constant(Fr::one())
-    }
-
-    /// Returns the polynomial with constant value `c`.
-    pub fn constant(c: Fr) -> Self {
-        // We create a raw pointer to the field element within this method's stack frame so we can
-        // overwrite that portion of memory with zeros once we have copied the element onto the
-        // heap as part of the vector of polynomial coefficients.
-        let fr_ptr = &c as *const Fr;
-        let poly = Poly::from(vec![c]);
-        clear_fr(fr_ptr);
-        poly
-    }
-
-    /// Returns the identity function, i.e. the polynomial "`x`".
-    pub fn identity() -> Self {
-        Poly::monomial(1)
-    }
-
-    /// Returns the (monic) monomial: `x.pow(degree)`.
-    pub fn monomial(degree: usize) -> Self {
-        let coeff: Vec<Fr> = iter::repeat(Fr::zero())
-            .take(degree)
-            .chain(iter::once(Fr::one()))
-            .collect();
-        Poly::from(coeff)
-    }
-
-    /// Returns the unique polynomial `f` of degree `samples.len() - 1` with the given values
-    /// `(x, f(x))`.
-    pub fn interpolate<T, U, I>(samples_repr: I) -> Self
-    where
-        I: IntoIterator<Item = (T, U)>,
-        T: IntoFr,
-        U: IntoFr,
-    {
-        let convert = |(x, y): (T, U)| (x.into_fr(), y.into_fr());
-        let samples: Vec<(Fr, Fr)> = samples_repr.into_iter().map(convert).collect();
-        Poly::compute_interpolation(&samples)
-    }
-
-    /// Returns the degree.
-    pub fn degree(&self) -> usize {
-        self.coeff.len().saturating_sub(1)
-    }
-
-    /// Returns the value at the point `i`.
-    pub fn evaluate<T: IntoFr>(&self, i: T) -> Fr {
-        let mut result = match self.coeff.last() {
-            None => return Fr::zero(),
-            Some(c) => *c,
-        };
-        let x = i.into_fr();
-        for c in self.coeff.iter().rev().skip(1) {
-            result.mul_assign(&x);
-            result.add_assign(c);
-        }
-        result
-    }
-
-    /// Returns the corresponding commitment.
-    pub fn commitment(&self) -> Commitment {
-        let to_g1 = |c: &Fr| G1Affine::one().mul(*c);
-        Commitment {
-            coeff: self.coeff.iter().map(to_g1).collect(),
+    /// Returns the corresponding Pedersen commitment, with the given blinding polynomial. As long
+    /// as `blinding` is random and secret, it reveals no information about this polynomial.
+    pub fn pedersen_commitment(&self, blinding: &Poly) -> PedersenCommitment {
+        PedersenCommitment {
+            coeff: pedersen_coeffs(&self.coeff, &blinding.coeff),
         }
     }
-
-    /// Removes all trailing zero coefficients.
-    fn remove_zeros(&mut self) {
-        let zeros = self.coeff.iter().rev().take_while(|c| c.is_zero()).count();
-        let len = self.coeff.len() - zeros;
-        self.coeff.truncate(len);
-    }
-
-    /// Returns the unique polynomial `f` of degree `samples.len() - 1` with the given values
-    /// `(x, f(x))`.
-    fn compute_interpolation(samples: &[(Fr, Fr)]) -> Self {
-        if samples.is_empty() {
-            return Poly::zero();
-        }
-        // Interpolates on the first `i` samples.
-        let mut poly = Poly::constant(samples[0].1);
-        let mut minus_s0 = samples[0].0;
-        minus_s0.negate();
-        // Is zero on the first `i` samples.
-        let mut base = Poly::from(vec![minus_s0, Fr::one()]);
-
-        // We update `base` so that it is always zero on all previous samples, and `poly` so that
-        // it has the correct values on the previous samples.
-        for (ref x, ref y) in &samples[1..] {
-            // Scale `base` so that its value at `x` is the difference between `y` and `poly`'s
-            // current value at `x`: Adding it to `poly` will then make it correct for `x`.
-            let mut diff = *y;
-            diff.sub_assign(&poly.evaluate(x));
-            let mut base_val = base.evaluate(x);
-            diff.mul_assign(&base_val.inverse().expect("sample points must be distinct"));
-            base *= diff;
-            poly += &base;
-
-            // Finally, multiply `base` by X - x, so that it is zero at `x`, too, now.
-            let mut minus_x = *x;
-            minus_x.negate();
-            base *= Poly::from(vec![minus_x, Fr::one()]);
-        }
-        poly
-    }
-
-    /// Generates a non-redacted debug string. This method differs from
-    /// the `Debug` implementation in that it *does* leak the secret prime
-    /// field elements.
-    pub fn reveal(&self) -> String {
-        format!("Poly {{ coeff: {:?} }}", self.coeff)
-    }
 }
 
-/// A commitment to a univariate polynomial.
+/// A Pedersen commitment to a univariate polynomial `f`, with a blinding polynomial `r`. The
+/// coefficients are `f_i * g + r_i * h`.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
-pub struct Commitment {
+pub struct PedersenCommitment {
     /// The coefficients of the polynomial.
     #[serde(with = "super::serde_impl::projective_vec")]
     pub(super) coeff: Vec<G1>,
 }
 
-impl Hash for Commitment {
+impl Hash for PedersenCommitment {
     fn hash<H: Hasher>(&self, state: &mut H) {
         self.coeff.len().hash(state);
         for c in &self.coeff {
@@ -443,209 +180,52 @@ impl Hash for Commitment {
     }
 }
 
-impl<B: Borrow<Commitment>> ops::AddAssign<B> for Commitment {
-    fn add_assign(&mut self, rhs: B) {
-        let len = cmp::max(self.coeff.len(), rhs.borrow().coeff.len());
-        self.coeff.resize(len, G1::zero());
-        for (self_c, rhs_c) in self.coeff.iter_mut().zip(&rhs.borrow().coeff) {
-            self_c.add_assign(rhs_c);
-        }
-        self.remove_zeros();
-    }
-}
-
-impl<'a, B: Borrow<Commitment>> ops::Add<B> for &'a Commitment {
-    type Output = Commitment;
-
-    fn add(self, rhs: B) -> Commitment {
-        (*self).clone() + rhs
-    }
-}
-
-impl<B: Borrow<Commitment>> ops::Add<B> for Commitment {
-    type Output = Commitment;
-
-    fn add(mut self, rhs: B) -> Commitment {
-        self += rhs;
-        self
-    }
-}
-
-impl Commitment {
+impl PedersenCommitment {
     /// Returns the polynomial's degree.
     pub fn degree(&self) -> usize {
-        self.coeff.len() - 1
+        self.coeff.len().saturating_sub(1)
     }
 
-    /// Returns the `i`-th public key share.
+    /// Returns the commitment's value at `i`, i.e. `f(i) * g + r(i) * h`.
     pub fn evaluate<T: IntoFr>(&self, i: T) -> G1 {
-        let mut result = match self.coeff.last() {
-            None => return G1::zero(),
-            Some(c) => *c,
-        };
-        let x = i.into_fr();
-        for c in self.coeff.iter().rev().skip(1) {
-            result.mul_assign(x);
-            result.add_assign(c);
-        }
-        result
-    }
-
-    /// Removes all trailing zero coefficients.
-    fn remove_zeros(&mut self) {
-        let zeros = self.coeff.iter().rev().take_while(|c| c.is_zero()).count();
-        let len = self.coeff.len() - zeros;
-        self.coeff.truncate(len)
-    }
-}
-
-/// A symmetric bivariate polynomial in the prime field.
-///
-/// This can be used for Verifiable Secret Sharing and Distributed Key Generation. See the module
-/// documentation for details.
-pub struct BivarPoly {
-    /// The polynomial's degree in each of the two variables.
-    degree: usize,
-    /// The coefficients of the polynomial. Coefficient `(i, j)` for `i <= j` is in position
-    /// `j * (j + 1) / 2 + i`.
-    coeff: Vec<Fr>,
-}
-
-impl Clone for BivarPoly {
-    fn clone(&self) -> Self {
-        BivarPoly {
-            degree: self.degree,
-            coeff: self.coeff.clone(),
-        }
-    }
-}
-
-impl Drop for BivarPoly {
-    fn drop(&mut self) {
-        self.zero_secret();
+        evaluate_g1(&self.coeff, i.into_fr())
     }
-}
 
-/// A debug statement where the `coeff` vector has been redacted.
-impl Debug for BivarPoly {
-    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
-        f.debug_struct("BivarPoly")
-            .field("degree", &self.degree)
-            .field("coeff", &"...")
-            .finish()
+    /// Returns `true` if `value` and `blinding` are the values at `i` of the committed polynomial
+    /// and the blinding polynomial, respectively.
+    pub fn verify<T: IntoFr>(&self, i: T, value: &Fr, blinding: &Fr) -> bool {
+        self.evaluate(i) == pedersen_value(value, blinding)
     }
 }
 
-impl ContainsSecret for BivarPoly {
-    fn secret_memory(&self) -> MemRange {
-        let ptr = self.coeff.as_ptr() as *const Fr as *mut u8;
-        let n_bytes = size_of_val(self.coeff.as_slice());
-        MemRange { ptr, n_bytes }
-    }
-}
 impl BivarPoly {
-    /// Creates a random polynomial.
-    ///
-    /// # Panics
-    ///
-    /// Panics if the degree is too high for the coefficients to fit into a `Vec`.
-    pub fn random<R: Rng>(degree: usize, rng: &mut R) -> Self {
-        BivarPoly::try_random(degree, rng).unwrap_or_else(|e| {
-            panic!(
-                "Failed to create random `BivarPoly` of degree {}: {}",
-                degree, e
-            )
-        })
-    }
-
-    /// Creates a random polynomial.
-    pub fn try_random<R: Rng>(degree: usize, rng: &mut R) -> Result<Self> {
-        let len = coeff_pos(degree, degree)
-            .and_then(|l| l.checked_add(1))
-            .ok_or(Error::DegreeTooHigh)?;
-        let poly = BivarPoly {
-            degree,
-            coeff: (0..len).map(|_| rng.gen()).collect(),
-        };
-        Ok(poly)
-    }
-
-    /// Returns the polynomial's degree; which is the same in both variables.
-    pub fn degree(&self) -> usize {
-        self.degree
-    }
-
-    /// Returns the polynomial's value at the point `(x, y)`.
-    pub fn evaluate<T: IntoFr>(&self, x: T, y: T) -> Fr {
-        let x_pow = self.powers(x);
-        let y_pow = self.powers(y);
-        // TODO: Can we save a few multiplication steps here due to the symmetry?
-        let mut result = Fr::zero();
-        for (i, x_pow_i) in x_pow.into_iter().enumerate() {
-            for (j, y_pow_j) in y_pow.iter().enumerate() {
-                let index = coeff_pos(i, j).expect("polynomial degree too high");
-                let mut summand = self.coeff[index];
-                summand.mul_assign(&x_pow_i);
-                summand.mul_assign(y_pow_j);
-                result.add_assign(&summand);
-            }
+    /// Returns the corresponding Pedersen commitment, with the given blinding polynomial. That
+    /// information can be shared publicly, and as long as `blinding` is random and secret, it
+    /// reveals no information about this polynomial.
+    pub fn pedersen_commitment(&self, blinding: &BivarPoly) -> BivarPedersenCommitment {
+        // The position of a coefficient doesn't depend on the degree, so the coefficients of the
+        // polynomial with the lower degree are a prefix.
+        BivarPedersenCommitment {
+            degree: cmp::max(self.degree, blinding.degree),
+            coeff: pedersen_coeffs(&self.coeff, &blinding.coeff),
         }
-        result
-    }
-
-    /// Returns the `x`-th row, as a univariate polynomial.
-    pub fn row<T: IntoFr>(&self, x: T) -> Poly {
-        let x_pow = self.powers(x);
-        let coeff: Vec<Fr> = (0..=self.degree)
-            .map(|i| {
-                // TODO: clear these secrets from the stack.
-                let mut result = Fr::zero();
-                for (j, x_pow_j) in x_pow.iter().enumerate() {
-                    let index = coeff_pos(i, j).expect("polynomial degree too high");
-                    let mut summand = self.coeff[index];
-                    summand.mul_assign(x_pow_j);
-                    result.add_assign(&summand);
-                }
-                result
-            }).collect();
-        Poly::from(coeff)
-    }
-
-    /// Returns the corresponding commitment. That information can be shared publicly.
-    pub fn commitment(&self) -> BivarCommitment {
-        let to_pub = |c: &Fr| G1Affine::one().mul(*c);
-        BivarCommitment {
-            degree: self.degree,
-            coeff: self.coeff.iter().map(to_pub).collect(),
-        }
-    }
-
-    /// Returns the `0`-th to `degree`-th power of `x`.
-    fn powers<T: IntoFr>(&self, x: T) -> Vec<Fr> {
-        powers(x, self.degree)
-    }
-
-    /// Generates a non-redacted debug string. This method differs from the
-    /// `Debug` implementation in that it *does* leak the the struct's
-    /// internal state.
-    pub fn reveal(&self) -> String {
-        format!(
-            "BivarPoly {{ degree: {}, coeff: {:?} }}",
-            self.degree, self.coeff
-        )
     }
 }
 
-/// A commitment to a symmetric bivariate polynomial.
+/// A Pedersen commitment to a symmetric bivariate polynomial `f`, with a blinding polynomial `r`.
+/// The coefficients are `f_ij * g + r_ij * h`.
+///
+/// `Serialize` and `Deserialize` are implemented in `serde_impl`, like for `BivarCommitment`:
+/// Deserialization fails if the number of coefficients doesn't match the degree.
 #[derive(Debug, Clone, Eq, PartialEq)]
-pub struct BivarCommitment {
+pub struct BivarPedersenCommitment {
     /// The polynomial's degree in each of the two variables.
     pub(crate) degree: usize,
     /// The commitments to the coefficients.
     pub(crate) coeff: Vec<G1>,
 }
 
-impl Hash for BivarCommitment {
+impl Hash for BivarPedersenCommitment {
     fn hash<H: Hasher>(&self, state: &mut H) {
         self.degree.hash(state);
         for c in &self.coeff {
@@ -654,82 +234,60 @@ impl Hash for BivarCommitment {
     }
 }
 
-impl BivarCommitment {
+impl BivarPedersenCommitment {
     /// Returns the polynomial's degree: It is the same in both variables.
     pub fn degree(&self) -> usize {
         self.degree
     }
 
-    /// Returns the commitment's value at the point `(x, y)`.
+    /// Returns the commitment's value at the point `(x, y)`, i.e. `f(x, y) * g + r(x, y) * h`.
     pub fn evaluate<T: IntoFr>(&self, x: T, y: T) -> G1 {
-        let x_pow = self.powers(x);
-        let y_pow = self.powers(y);
-        // TODO: Can we save a few multiplication steps here due to the symmetry?
-        let mut result = G1::zero();
-        for (i, x_pow_i) in x_pow.into_iter().enumerate() {
-            for (j, y_pow_j) in y_pow.iter().enumerate() {
-                let index = coeff_pos(i, j).expect("polynomial degree too high");
-                let mut summand = self.coeff[index];
-                summand.mul_assign(x_pow_i);
-                summand.mul_assign(*y_pow_j);
-                result.add_assign(&summand);
-            }
-        }
-        result
+        bivar_evaluate_g1(self.degree, &self.coeff, x.into_fr(), y.into_fr())
     }
 
-    /// Returns the `x`-th row, as a commitment to a univariate polynomial.
-    pub fn row<T: IntoFr>(&self, x: T) -> Commitment {
-        let x_pow = self.powers(x);
-        let coeff: Vec<G1> = (0..=self.degree)
-            .map(|i| {
-                let mut result = G1::zero();
-                for (j, x_pow_j) in x_pow.iter().enumerate() {
-                    let index = coeff_pos(i, j).expect("polynomial degree too high");
-                    let mut summand = self.coeff[index];
-                    summand.mul_assign(*x_pow_j);
-                    result.add_assign(&summand);
-                }
-                result
-            }).collect();
-        Commitment { coeff }
+    /// Returns the `x`-th row, as a Pedersen commitment to a univariate polynomial, with the
+    /// `x`-th row of the blinding polynomial.
+    pub fn row<T: IntoFr>(&self, x: T) -> PedersenCommitment {
+        PedersenCommitment {
+            coeff: bivar_row_g1(self.degree, &self.coeff, x.into_fr()),
+        }
     }
 
-    /// Returns the `0`-th to `degree`-th power of `x`.
-    fn powers<T: IntoFr>(&self, x: T) -> Vec<Fr> {
-        powers(x, self.degree)
+    /// Returns `true` if `value` and `blinding` are the values at `(x, y)` of the committed
+    /// polynomial and the blinding polynomial, respectively.
+    pub fn verify<T: IntoFr>(&self, x: T, y: T, value: &Fr, blinding: &Fr) -> bool {
+        self.evaluate(x, y) == pedersen_value(value, blinding)
     }
 }
 
-/// Returns the `0`-th to `degree`-th power of `x`.
-fn powers<T: IntoFr>(into_x: T, degree: usize) -> Vec<Fr> {
-    let x = into_x.into_fr();
-    let mut x_pow_i = Fr::one();
-    iter::once(x_pow_i)
-        .chain((0..degree).map(|_| {
-            x_pow_i.mul_assign(&x);
-            x_pow_i
-        })).collect()
+/// Returns the Pedersen commitment `value * g + blinding * h`.
+fn pedersen_value(value: &Fr, blinding: &Fr) -> G1 {
+    let mut result = G1Affine::one().mul(*value);
+    result.add_assign(&PEDERSEN_H.into_affine().mul(*blinding));
+    result
 }
 
-/// Returns the position of coefficient `(i, j)` in the vector describing a symmetric bivariate
-/// polynomial. If `i` or `j` are too large to represent the position as a `usize`, `None` is
-/// returned.
-pub(crate) fn coeff_pos(i: usize, j: usize) -> Option<usize> {
-    // Since the polynomial is symmetric, we can order such that `j >= i`.
-    let (j, i) = if j >= i { (j, i) } else { (i, j) };
-    i.checked_add(j.checked_mul(j.checked_add(1)?)? / 2)
+/// Returns the Pedersen commitments to the coefficients, with the given blinding coefficients. If
+/// one of them is shorter, it is padded with zeros.
+fn pedersen_coeffs(coeff: &[Fr], blinding: &[Fr]) -> Vec<G1> {
+    let zero = Fr::zero();
+    (0..cmp::max(coeff.len(), blinding.len()))
+        .map(|i| {
+            let value = coeff.get(i).unwrap_or(&zero);
+            pedersen_value(value, blinding.get(i).unwrap_or(&zero))
+        }).collect()
 }
 
 #[cfg(test)]
 mod tests {
     use std::collections::BTreeMap;
 
-    use super::{coeff_pos, BivarPoly, IntoFr, Poly};
+    use super::{pedersen_generator, BivarPoly, IntoFr, PedersenCommitment, Poly};
+    use generic::poly::coeff_pos;
 
     use super::{Fr, G1Affine};
-    use pairing::{CurveAffine, Field};
-    use rand;
+    use pairing::{CurveAffine, CurveProjective, Field};
+    use rand::{self, Rng};
 
     #[test]
     fn test_coeff_pos() {
@@ -759,12 +317,33 @@ mod tests {
         assert_eq!(Poly { coeff }, poly);
         let samples = vec![(-1, -8), (2, 40), (3, 136), (5, 628)];
         for &(x, y) in &samples {
-            assert_eq!(y.into_fr(), poly.evaluate(x));
+            let y: Fr = y.into_fr();
+            assert_eq!(y, poly.evaluate(x));
         }
         let interp = Poly::interpolate(samples);
         assert_eq!(interp, poly);
     }
 
+    #[test]
+    fn commitment_evaluate() {
+        let mut rng = rand::thread_rng();
+        for &degree in &[0, 1, 5, 40] {
+            let poly = Poly::random(degree, &mut rng);
+            let commit = poly.commitment();
+            let bi_poly = BivarPoly::random(degree, &mut rng);
+            let bi_commit = bi_poly.commitment();
+            // Large points use a multi-scalar multiplication, small ones Horner's method.
+            let (x, y): (Fr, Fr) = (rng.gen(), rng.gen());
+            let small: Fr = 12345.into_fr();
+            for &(x, y) in &[(x, y), (x, small), (small, y), (small, small)] {
+                assert_eq!(G1Affine::one().mul(poly.evaluate(x)), commit.evaluate(x));
+                let value = bi_poly.evaluate(x, y);
+                assert_eq!(G1Affine::one().mul(value), bi_commit.evaluate(x, y));
+                assert_eq!(bi_poly.row(x).commitment(), bi_commit.row(x));
+            }
+        }
+    }
+
     #[test]
     fn distributed_key_generation() {
         let mut rng = rand::thread_rng();
@@ -848,4 +427,48 @@ mod tests {
         }
         assert_eq!(sum_commit, sec_key_set.commitment());
     }
+
+    #[test]
+    fn pedersen_commitment() {
+        let mut rng = rand::thread_rng();
+        let degree = 3;
+        let bi_poly = BivarPoly::random(degree, &mut rng);
+        let bi_blinding = BivarPoly::random(degree, &mut rng);
+        let bi_commit = bi_poly.pedersen_commitment(&bi_blinding);
+        assert_eq!(degree, bi_commit.degree());
+        assert_ne!(G1Affine::one(), pedersen_generator().into_affine());
+
+        for m in 1..=5 {
+            // Node `m` receives its row and the row of the blinding polynomial, and verifies them.
+            let row_poly = bi_poly.row(m);
+            let row_blinding = bi_blinding.row(m);
+            let row_commit = bi_commit.row(m);
+            assert_eq!(row_poly.pedersen_commitment(&row_blinding), row_commit);
+            for s in 1..=5 {
+                let val = row_poly.evaluate(s);
+                let blinding = row_blinding.evaluate(s);
+                assert!(row_commit.verify(s, &val, &blinding));
+                assert!(bi_commit.verify(m, s, &val, &blinding));
+                assert_eq!(bi_commit.evaluate(m, s), row_commit.evaluate(s));
+                // A wrong value or blinding value is detected.
+                assert!(!row_commit.verify(s, &val, &val));
+                assert!(!row_commit.verify(s, &blinding, &blinding));
+            }
+        }
+
+        // Unlike the Feldman commitment, the Pedersen commitment to the constant term is not
+        // `f(0) * g`, and with a different blinding polynomial, the commitment is different.
+        let poly = Poly::random(degree, &mut rng);
+        let blinding = Poly::random(degree, &mut rng);
+        let commit = poly.pedersen_commitment(&blinding);
+        assert_ne!(poly.commitment().evaluate(0), commit.evaluate(0));
+        let other_blinding = Poly::random(degree, &mut rng);
+        assert_ne!(commit, poly.pedersen_commitment(&other_blinding));
+        let unblinded = poly.pedersen_commitment(&Poly::zero());
+        assert_eq!(poly.commitment().evaluate(2), unblinded.evaluate(2));
+
+        // A deserialized commitment can have no coefficients at all.
+        let empty = PedersenCommitment { coeff: Vec::new() };
+        assert_eq!(0, empty.degree());
+    }
 }
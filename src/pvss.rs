@@ -0,0 +1,505 @@
+//! Publicly verifiable secret sharing (PVSS).
+//!
+//! A dealer with a secret polynomial `f` of degree `t` publishes a `Dealing`: the commitment to
+//! `f` and, for each node `i` with public key `pk_i = sk_i * g`, an encryption of the share
+//! `f(i + 1)`, together with a proof that the encrypted value is the one determined by the
+//! commitment, and that node `i` can decrypt it. Anyone can verify the proofs, without any secret
+//! key, so the dealing can be published on a bulletin board and audited by everyone.
+//!
+//! Each bit `b_k` of the share is encrypted with ElGamal "in the exponent", as
+//! `(r_k * g, b_k * g + r_k * pk_i)` with a random `r_k`, together with a disjunctive
+//! Chaum-Pedersen proof that `b_k` is `0` or `1`. With `R` the sum of the `r_k * 2^k`, the
+//! weighted sums of the ciphertexts' parts are `U = R * g` and `V = f(i + 1) * g + R * pk_i`,
+//! where `f(i + 1) * g` is the commitment's value at `i + 1`. So another Chaum-Pedersen proof,
+//! that `U` and `V - f(i + 1) * g` have the same discrete logarithm with respect to `g` and
+//! `pk_i`, respectively, shows that the bits add up to the share.
+//!
+//! Node `i` combines the bits' ciphertexts into chunks of `CHUNK_BITS` bits, decrypts each chunk
+//! `m_j` to `m_j * g`, and finds the small discrete logarithm `m_j` with the baby-step giant-step
+//! algorithm. Since the proofs show that every chunk is smaller than `2^CHUNK_BITS`, every share
+//! that passes verification can be decrypted.
+
+use std::collections::HashMap;
+
+use pairing::{CurveAffine, CurveProjective, Field, PrimeField, PrimeFieldRepr};
+use rand::Rng;
+
+use error::{Error, Result};
+use into_fr::IntoFr;
+use poly::{Commitment, Poly};
+use secret::{clear_copy, clear_fr};
+use {rng_from_hash, Fr, G1Affine, PublicKey, SecretKey, SecretKeyShare, G1};
+
+/// The number of bits of each chunk of a share that is decrypted at once.
+const CHUNK_BITS: usize = 16;
+
+/// A label that is hashed into the challenge of every proof that the bits add up to the share.
+const PROOF_LABEL: &[u8] = b"threshold_crypto PVSS DLEQ proof";
+
+/// A label that is hashed into the challenge of every proof that a bit is `0` or `1`.
+const BIT_PROOF_LABEL: &[u8] = b"threshold_crypto PVSS bit proof";
+
+lazy_static! {
+    /// The baby steps for decrypting a chunk: The compressed point `k * g` maps to `k`, for every
+    /// `k` with half the bits of a chunk.
+    static ref BABY_STEPS: HashMap<Vec<u8>, u64> = {
+        let g = G1Affine::one().into_projective();
+        let mut point = G1::zero();
+        let mut steps = HashMap::new();
+        for k in 0..(1 << (CHUNK_BITS / 2)) {
+            steps.insert(point.into_affine().into_compressed().as_ref().to_vec(), k);
+            point.add_assign(&g);
+        }
+        steps
+    };
+}
+
+/// A dealer's message: the commitment to its polynomial, and every node's encrypted share.
+#[derive(Deserialize, Serialize, Clone, PartialEq, Eq, Debug)]
+pub struct Dealing {
+    commit: Commitment,
+    shares: Vec<EncryptedShare>,
+}
+
+impl Dealing {
+    /// Creates a dealing of the polynomial: Share `poly.evaluate(i + 1)` is encrypted to
+    /// `pub_keys[i]`.
+    pub fn new<R: Rng>(poly: &Poly, pub_keys: &[PublicKey], rng: &mut R) -> Dealing {
+        let shares = pub_keys
+            .iter()
+            .enumerate()
+            .map(|(i, pk)| {
+                let mut value = poly.evaluate(i + 1);
+                let share = EncryptedShare::new(&value, pk, rng);
+                clear_fr(&mut value);
+                share
+            }).collect();
+        Dealing {
+            commit: poly.commitment(),
+            shares,
+        }
+    }
+
+    /// Returns the commitment to the dealer's polynomial.
+    pub fn commitment(&self) -> &Commitment {
+        &self.commit
+    }
+
+    /// Returns `true` if the dealing contains a share for each of the public keys, and all of
+    /// them are decryptable encryptions of the values determined by the commitment.
+    pub fn verify(&self, pub_keys: &[PublicKey]) -> bool {
+        self.shares.len() == pub_keys.len()
+            && (0..pub_keys.len()).all(|i| self.verify_share(i, &pub_keys[i]))
+    }
+
+    /// Returns `true` if the `i`-th share is a decryptable encryption to `pk` of the value
+    /// determined by the commitment.
+    pub fn verify_share(&self, i: usize, pk: &PublicKey) -> bool {
+        match self.shares.get(i) {
+            None => false,
+            Some(share) => share.verify(pk, &self.commit.evaluate(i + 1)),
+        }
+    }
+
+    /// Verifies and decrypts the `i`-th share, using node `i`'s secret key.
+    ///
+    /// Returns `Error::InvalidCiphertext` if the share is invalid.
+    pub fn decrypt_share(&self, i: usize, sk: &SecretKey) -> Result<SecretKeyShare> {
+        if !self.verify_share(i, &sk.public_key()) {
+            return Err(Error::InvalidCiphertext);
+        }
+        let mut value = self.shares[i].decrypt(sk).ok_or(Error::InvalidCiphertext)?;
+        if G1Affine::one().mul(value) != self.commit.evaluate(i + 1) {
+            clear_fr(&mut value);
+            return Err(Error::InvalidCiphertext);
+        }
+        Ok(SecretKeyShare::from_mut(&mut value))
+    }
+}
+
+/// A share, encrypted bit by bit to a node's public key, with a proof that it matches the
+/// dealer's commitment.
+#[derive(Deserialize, Serialize, Clone, PartialEq, Eq, Debug)]
+struct EncryptedShare {
+    /// The encrypted bits, least significant first.
+    bits: Vec<EncryptedBit>,
+    /// The proof's challenge.
+    #[serde(with = "super::serde_impl::field")]
+    challenge: Fr,
+    /// The proof's response.
+    #[serde(with = "super::serde_impl::field")]
+    response: Fr,
+}
+
+impl EncryptedShare {
+    /// Encrypts the value to the public key, and creates the proofs.
+    fn new<R: Rng>(value: &Fr, pk: &PublicKey, rng: &mut R) -> EncryptedShare {
+        let g = G1Affine::one();
+        let pk_affine = pk.0.into_affine();
+        let mut bits = to_bits(value);
+        let mut rs: Vec<Fr> = bits.iter().map(|_| rng.gen()).collect();
+        let enc_bits: Vec<EncryptedBit> = bits
+            .iter()
+            .zip(&rs)
+            .map(|(bit, r)| EncryptedBit::new(*bit, r, pk, rng))
+            .collect();
+        for bit in &mut bits {
+            clear_copy(bit);
+        }
+
+        // Prove that `u = r_sum * g` and `v = r_sum * pk`.
+        let mut r_sum = weighted_sum_fr(&rs);
+        for r in &mut rs {
+            clear_fr(r);
+        }
+        let (u, mut v) = weighted_sums(&enc_bits);
+        v.sub_assign(&g.mul(*value));
+        let mut w: Fr = rng.gen();
+        let challenge = challenge(pk, &u, &v, &g.mul(w), &pk_affine.mul(w));
+        let mut response = challenge;
+        response.mul_assign(&r_sum);
+        response.negate();
+        response.add_assign(&w);
+        clear_fr(&mut r_sum);
+        clear_fr(&mut w);
+
+        EncryptedShare {
+            bits: enc_bits,
+            challenge,
+            response,
+        }
+    }
+
+    /// Returns `true` if this is an encryption to `pk` of the value whose commitment is
+    /// `value_g1`, and every bit is `0` or `1`.
+    fn verify(&self, pk: &PublicKey, value_g1: &G1) -> bool {
+        if self.bits.len() != Fr::NUM_BITS as usize {
+            return false;
+        }
+        let (u, mut v) = weighted_sums(&self.bits);
+        v.sub_assign(value_g1);
+        let mut a1 = G1Affine::one().mul(self.response);
+        a1.add_assign(&u.into_affine().mul(self.challenge));
+        let mut a2 = pk.0.into_affine().mul(self.response);
+        a2.add_assign(&v.into_affine().mul(self.challenge));
+        challenge(pk, &u, &v, &a1, &a2) == self.challenge
+            && self.bits.iter().all(|bit| bit.verify(pk))
+    }
+
+    /// Decrypts the value, or returns `None` if a chunk is too large.
+    fn decrypt(&self, sk: &SecretKey) -> Option<Fr> {
+        let mut chunks: Vec<Fr> = Vec::with_capacity(self.bits.len() / CHUNK_BITS + 1);
+        for chunk_bits in self.bits.chunks(CHUNK_BITS) {
+            let (u, mut point) = weighted_sums(chunk_bits);
+            point.sub_assign(&u.into_affine().mul(*sk.0));
+            chunks.push(discrete_log(point)?.into_fr());
+        }
+        let chunk_base = (1u64 << CHUNK_BITS).into_fr();
+        let mut value = Fr::zero();
+        for chunk in chunks.iter_mut().rev() {
+            value.mul_assign(&chunk_base);
+            value.add_assign(chunk);
+            clear_fr(chunk);
+        }
+        Some(value)
+    }
+}
+
+/// A single bit `b` of a share, encrypted as `(r * g, b * g + r * pk)`, with a proof that `b` is
+/// `0` or `1`.
+///
+/// The proof consists of a Chaum-Pedersen proof that `randomness` and `masked - b * g` have the
+/// same discrete logarithm with respect to `g` and `pk`, for both values of `b`. The challenges
+/// must add up to the hash of the commitments, so only one of the two proofs can be simulated.
+#[derive(Deserialize, Serialize, Clone, PartialEq, Eq, Debug)]
+struct EncryptedBit {
+    /// The part `r * g` of the ciphertext.
+    #[serde(with = "super::serde_impl::projective")]
+    randomness: G1,
+    /// The part `b * g + r * pk` of the ciphertext.
+    #[serde(with = "super::serde_impl::projective")]
+    masked: G1,
+    /// The challenges of the proofs for `b = 0` and `b = 1`.
+    #[serde(with = "super::serde_impl::field_vec")]
+    challenges: Vec<Fr>,
+    /// The responses of the proofs for `b = 0` and `b = 1`.
+    #[serde(with = "super::serde_impl::field_vec")]
+    responses: Vec<Fr>,
+}
+
+impl EncryptedBit {
+    /// Encrypts the bit to the public key with randomness `r`, and creates the proof.
+    fn new<R: Rng>(bit: bool, r: &Fr, pk: &PublicKey, rng: &mut R) -> EncryptedBit {
+        let g = G1Affine::one();
+        let pk_affine = pk.0.into_affine();
+        let randomness = g.mul(*r);
+        let mut masked = pk_affine.mul(*r);
+        if bit {
+            masked.add_assign_mixed(&g);
+        }
+        let real = bit as usize;
+
+        // Simulate the proof for the other value of the bit, with a random challenge.
+        let mut challenges = vec![Fr::zero(); 2];
+        let mut responses = vec![Fr::zero(); 2];
+        challenges[1 - real] = rng.gen();
+        responses[1 - real] = rng.gen();
+        let mut commitments = vec![G1::zero(); 4];
+        let (a1, a2) = bit_proof_commitments(
+            &randomness,
+            &masked,
+            1 - real,
+            &challenges[1 - real],
+            &responses[1 - real],
+            pk,
+        );
+        commitments[2 * (1 - real)] = a1;
+        commitments[2 * (1 - real) + 1] = a2;
+
+        // Create the real proof, with the remaining challenge.
+        let mut w: Fr = rng.gen();
+        commitments[2 * real] = g.mul(w);
+        commitments[2 * real + 1] = pk_affine.mul(w);
+        let mut challenge = bit_challenge(pk, &randomness, &masked, &commitments);
+        challenge.sub_assign(&challenges[1 - real]);
+        let mut response = challenge;
+        response.mul_assign(r);
+        response.negate();
+        response.add_assign(&w);
+        clear_fr(&mut w);
+        challenges[real] = challenge;
+        responses[real] = response;
+
+        EncryptedBit {
+            randomness,
+            masked,
+            challenges,
+            responses,
+        }
+    }
+
+    /// Returns `true` if this is an encryption of `0` or `1` to `pk`.
+    fn verify(&self, pk: &PublicKey) -> bool {
+        if self.challenges.len() != 2 || self.responses.len() != 2 {
+            return false;
+        }
+        let mut commitments = Vec::with_capacity(4);
+        for bit in 0..2 {
+            let (a1, a2) = bit_proof_commitments(
+                &self.randomness,
+                &self.masked,
+                bit,
+                &self.challenges[bit],
+                &self.responses[bit],
+                pk,
+            );
+            commitments.push(a1);
+            commitments.push(a2);
+        }
+        let mut challenge_sum = self.challenges[0];
+        challenge_sum.add_assign(&self.challenges[1]);
+        bit_challenge(pk, &self.randomness, &self.masked, &commitments) == challenge_sum
+    }
+}
+
+/// Returns the commitments `s * g + c * u` and `s * pk + c * (v - bit * g)` of the proof that
+/// `(u, v)` is an encryption of `bit`, with challenge `c` and response `s`.
+fn bit_proof_commitments(u: &G1, v: &G1, bit: usize, c: &Fr, s: &Fr, pk: &PublicKey) -> (G1, G1) {
+    let g = G1Affine::one();
+    let mut v_minus_bit = *v;
+    if bit == 1 {
+        v_minus_bit.sub_assign(&g.into_projective());
+    }
+    let mut a1 = g.mul(*s);
+    a1.add_assign(&u.into_affine().mul(*c));
+    let mut a2 = pk.0.into_affine().mul(*s);
+    a2.add_assign(&v_minus_bit.into_affine().mul(*c));
+    (a1, a2)
+}
+
+/// Returns the Fiat-Shamir challenge for a proof that `(u, v)` is an encryption of `0` or `1`
+/// to `pk`, with the commitments of both proofs.
+fn bit_challenge(pk: &PublicKey, u: &G1, v: &G1, commitments: &[G1]) -> Fr {
+    let mut bytes = BIT_PROOF_LABEL.to_vec();
+    for point in [pk.0, *u, *v].iter().chain(commitments) {
+        bytes.extend(point.into_affine().into_compressed().as_ref());
+    }
+    rng_from_hash(&bytes).gen()
+}
+
+/// Returns the Fiat-Shamir challenge for a proof that `u` and `v` have the same discrete
+/// logarithm with respect to `g` and `pk`, with commitments `a1` and `a2`.
+fn challenge(pk: &PublicKey, u: &G1, v: &G1, a1: &G1, a2: &G1) -> Fr {
+    let mut bytes = PROOF_LABEL.to_vec();
+    for point in &[pk.0, *u, *v, *a1, *a2] {
+        bytes.extend(point.into_affine().into_compressed().as_ref());
+    }
+    rng_from_hash(&bytes).gen()
+}
+
+/// Returns the `Fr::NUM_BITS` bits of the value, least significant first.
+fn to_bits(value: &Fr) -> Vec<bool> {
+    let mut repr = value.into_repr();
+    (0..Fr::NUM_BITS)
+        .map(|_| {
+            let bit = repr.is_odd();
+            repr.div2();
+            bit
+        }).collect()
+}
+
+/// Returns the sums of the `bits[k].randomness * 2^k` and the `bits[k].masked * 2^k`.
+fn weighted_sums(bits: &[EncryptedBit]) -> (G1, G1) {
+    let mut u = G1::zero();
+    let mut v = G1::zero();
+    for bit in bits.iter().rev() {
+        u.double();
+        u.add_assign(&bit.randomness);
+        v.double();
+        v.add_assign(&bit.masked);
+    }
+    (u, v)
+}
+
+/// Returns the sum of the `values[k] * 2^k`.
+fn weighted_sum_fr(values: &[Fr]) -> Fr {
+    let mut result = Fr::zero();
+    for value in values.iter().rev() {
+        result.double();
+        result.add_assign(value);
+    }
+    result
+}
+
+/// Returns `m` with `m * g == point`, if `m` fits into a chunk, using the baby-step giant-step
+/// algorithm.
+fn discrete_log(mut point: G1) -> Option<u64> {
+    let steps = 1u64 << (CHUNK_BITS / 2);
+    let steps_fr: Fr = steps.into_fr();
+    let mut giant_step = G1Affine::one().mul(steps_fr);
+    giant_step.negate();
+    for i in 0..steps {
+        if let Some(k) = BABY_STEPS.get(point.into_affine().into_compressed().as_ref()) {
+            return Some(i * steps + k);
+        }
+        point.add_assign(&giant_step);
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::collections::BTreeMap;
+
+    use bincode;
+    use rand::{self, Rng};
+
+    use PublicKeySet;
+
+    #[test]
+    fn test_pvss() {
+        let mut rng = rand::thread_rng();
+        let sec_keys: Vec<SecretKey> = (0..4).map(|_| rng.gen()).collect();
+        let pub_keys: Vec<PublicKey> = sec_keys.iter().map(SecretKey::public_key).collect();
+        let poly = Poly::random(1, &mut rng);
+        let dealing = Dealing::new(&poly, &pub_keys, &mut rng);
+
+        // Anyone can verify the dealing, e.g. after reading it from a bulletin board.
+        let ser_dealing = bincode::serialize(&dealing).expect("serialize dealing");
+        let dealing: Dealing = bincode::deserialize(&ser_dealing).expect("deserialize dealing");
+        assert!(dealing.verify(&pub_keys));
+        assert!(!dealing.verify(&pub_keys[1..]));
+
+        // Each node decrypts its share, and the shares can be used for threshold signatures.
+        let pk_set = PublicKeySet::from(dealing.commitment().clone());
+        let msg = "Totally real news";
+        let mut sigs = BTreeMap::new();
+        for (i, sk) in sec_keys.iter().enumerate() {
+            let sk_share = dealing.decrypt_share(i, sk).expect("decrypt share");
+            assert_eq!(pk_set.public_key_share(i), sk_share.public_key_share());
+            assert_eq!(poly.evaluate(i + 1), *(sk_share.0).0);
+            sigs.insert(i, sk_share.sign(msg));
+        }
+        let sig = pk_set.combine_signatures(&sigs).expect("enough shares");
+        assert!(pk_set.public_key().verify(&sig, msg));
+    }
+
+    #[test]
+    fn test_pvss_invalid() {
+        let mut rng = rand::thread_rng();
+        let sec_keys: Vec<SecretKey> = (0..3).map(|_| rng.gen()).collect();
+        let pub_keys: Vec<PublicKey> = sec_keys.iter().map(SecretKey::public_key).collect();
+        let poly = Poly::random(1, &mut rng);
+        let dealing = Dealing::new(&poly, &pub_keys, &mut rng);
+        assert!(dealing.verify(&pub_keys));
+
+        // Swapped shares are detected by everyone, and can't be decrypted.
+        let mut swapped = dealing.clone();
+        swapped.shares.swap(0, 1);
+        assert!(!swapped.verify(&pub_keys));
+        assert!(!swapped.verify_share(0, &pub_keys[0]));
+        assert!(swapped.verify_share(2, &pub_keys[2]));
+        let result = swapped.decrypt_share(0, &sec_keys[0]);
+        assert_eq!(Some(Error::InvalidCiphertext), result.err());
+
+        // So is a share of a different polynomial.
+        let other_poly = Poly::random(1, &mut rng);
+        let other_dealing = Dealing::new(&other_poly, &pub_keys, &mut rng);
+        let mut wrong = dealing.clone();
+        wrong.shares[1] = other_dealing.shares[1].clone();
+        assert!(!wrong.verify(&pub_keys));
+        assert!(!wrong.verify_share(1, &pub_keys[1]));
+
+        // A share can only be decrypted by its recipient.
+        let result = dealing.decrypt_share(1, &sec_keys[0]);
+        assert_eq!(Some(Error::InvalidCiphertext), result.err());
+        assert!(!dealing.verify_share(3, &pub_keys[0]));
+    }
+
+    #[test]
+    fn test_pvss_out_of_range() {
+        let mut rng = rand::thread_rng();
+        let sk: SecretKey = rng.gen();
+        let pub_keys = vec![sk.public_key()];
+        let poly = Poly::random(1, &mut rng);
+        let dealing = Dealing::new(&poly, &pub_keys, &mut rng);
+        assert!(dealing.verify(&pub_keys));
+
+        // Adding `2 * g` to bit 15 and subtracting `g` from bit 16 leaves the weighted sums
+        // unchanged, but the first chunk can't be decrypted anymore. The bit proofs catch that.
+        let mut tampered = dealing.clone();
+        let g = G1Affine::one().into_projective();
+        let mut two_g = g;
+        two_g.double();
+        tampered.shares[0].bits[CHUNK_BITS - 1]
+            .masked
+            .add_assign(&two_g);
+        tampered.shares[0].bits[CHUNK_BITS].masked.sub_assign(&g);
+        assert_eq!(
+            weighted_sums(&dealing.shares[0].bits),
+            weighted_sums(&tampered.shares[0].bits)
+        );
+        assert!(!tampered.verify(&pub_keys));
+        let result = tampered.decrypt_share(0, &sk);
+        assert_eq!(Some(Error::InvalidCiphertext), result.err());
+        assert_eq!(None, tampered.shares[0].decrypt(&sk));
+    }
+
+    #[test]
+    fn test_bits() {
+        let mut rng = rand::thread_rng();
+        let value: Fr = rng.gen();
+        let bits = to_bits(&value);
+        assert_eq!(Fr::NUM_BITS as usize, bits.len());
+        let bits_fr: Vec<Fr> = bits.iter().map(|bit| (*bit as u64).into_fr()).collect();
+        assert_eq!(value, weighted_sum_fr(&bits_fr));
+        for m in &[0u64, 1, 255, 256, 65535] {
+            let m_fr: Fr = m.into_fr();
+            assert_eq!(Some(*m), discrete_log(G1Affine::one().mul(m_fr)));
+        }
+        let too_large: Fr = 65536u64.into_fr();
+        assert_eq!(None, discrete_log(G1Affine::one().mul(too_large)));
+    }
+}
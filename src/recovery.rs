@@ -0,0 +1,560 @@
+//! Recovery of a lost secret key share, with the help of the other nodes.
+//!
+//! If node `i` loses its secret key share `f(i + 1)`, each helper deals a random polynomial `z`
+//! of degree `t` with `z(i + 1) = 0`: It sends a `RecoveryPart`, containing the commitment to `z`
+//! and, for every node `m`, the value `z(m + 1)` encrypted to node `m`. Once the helpers agree on
+//! at least `t + 1` valid parts, each of them adds its values to its own secret key share, and
+//! sends the sum to node `i` as its `Contribution`, encrypted. The contributions are values of
+//! the sum of `f` and all `z`, which is random everywhere except at `i + 1`, where it equals
+//! `f(i + 1)`. Node `i` verifies each contribution against the public key set and the
+//! commitments, and interpolates `t + 1` of them.
+//!
+//! As in the key generation, all messages must be broadcast. A helper that didn't receive a valid
+//! value sends a `Complaint`, which the dealer must answer with a `ValueJustification` that
+//! publicly reveals the value. A dealer is disqualified if it doesn't validly answer every
+//! complaint, or if more than `t` helpers complain. Which parts qualify thus only depends on
+//! public messages, so the helpers and node `i` use the same masks. Since one of at least `t + 1`
+//! parts is from an honest helper, the contributions reveal nothing about the helpers' secret key
+//! shares.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use pairing::{CurveAffine, CurveProjective, Field};
+use rand::Rng;
+
+use dkg::{
+    check_index, check_our_key, decrypt_fr, encrypt_fr_vec, Complaint, Fault, Round, ValueDealings,
+    ValueJustification,
+};
+use error::{Error, Result};
+use generic::{into_fr_plus_1, lagrange_coeffs};
+use poly::{Commitment, Poly};
+use secret::clear_fr;
+use {Ciphertext, Fr, G1Affine, PublicKey, PublicKeySet, SecretKey, SecretKeyShare};
+
+/// A helper's message: the commitment to a random polynomial that is zero at the recovering
+/// node's index, and its value for each node, encrypted to that node.
+#[derive(Deserialize, Serialize, Clone, PartialEq, Eq, Debug)]
+pub struct RecoveryPart {
+    commit: Commitment,
+    values: Vec<Ciphertext>,
+}
+
+/// A helper's blinded secret key share, encrypted to the recovering node.
+#[derive(Deserialize, Serialize, Clone, PartialEq, Eq, Debug)]
+pub struct Contribution(Ciphertext);
+
+/// A node's state in the recovery of secret key share `lost_idx`. This is used both by the
+/// helpers and by the recovering node itself.
+pub struct Recovery {
+    /// Our node index.
+    our_idx: usize,
+    /// Our secret key, to decrypt the contributions sent to us.
+    sec_key: SecretKey,
+    /// The public key of the recovering node.
+    lost_pub_key: PublicKey,
+    /// The public key set.
+    pk_set: PublicKeySet,
+    /// The index of the lost secret key share.
+    lost_idx: usize,
+    /// Our own masking polynomial, as a helper, or `None` if we are the recovering node.
+    poly: Option<Poly>,
+    /// The helpers' parts, complaints and justifications.
+    dealings: ValueDealings,
+    /// The valid contributions, by sender. This is empty for the helpers.
+    contributions: BTreeMap<usize, Fr>,
+}
+
+impl Drop for Recovery {
+    fn drop(&mut self) {
+        for value in self.contributions.values_mut() {
+            clear_fr(value);
+        }
+    }
+}
+
+impl Recovery {
+    /// Creates a new recovery instance for node `our_idx`, with the given public keys of all
+    /// nodes, the public key set, and the index of the lost secret key share. Returns it together
+    /// with our part as a helper, or `None` if we are the recovering node.
+    ///
+    /// Returns `Error::IndexOutOfRange` if `our_idx` or `lost_idx` is not an index of `pub_keys`,
+    /// and `Error::KeyMismatch` if `sec_key` doesn't match our public key in `pub_keys`.
+    pub fn new<R: Rng>(
+        our_idx: usize,
+        sec_key: SecretKey,
+        pub_keys: &[PublicKey],
+        pk_set: PublicKeySet,
+        lost_idx: usize,
+        rng: &mut R,
+    ) -> Result<(Recovery, Option<RecoveryPart>)> {
+        check_our_key(our_idx, &sec_key, pub_keys)?;
+        check_index(lost_idx, pub_keys.len())?;
+        let threshold = pk_set.threshold();
+        let (poly, part, values_idx) = if our_idx == lost_idx {
+            (None, None, None)
+        } else {
+            let mut poly = Poly::try_random(threshold, rng)?;
+            let lost_value = poly.evaluate(lost_idx + 1);
+            poly.coeff[0].sub_assign(&lost_value);
+            let values = pub_keys
+                .iter()
+                .enumerate()
+                .map(|(m, pk)| {
+                    let label = Round::Recovery.label(&[our_idx, m]);
+                    encrypt_fr_vec(pk, &[poly.evaluate(m + 1)], &label, rng)
+                }).collect();
+            let part = RecoveryPart {
+                commit: poly.commitment(),
+                values,
+            };
+            (Some(poly), Some(part), Some(our_idx))
+        };
+        let node_num = pub_keys.len();
+        let dealings = ValueDealings::new(
+            Round::Recovery,
+            values_idx,
+            sec_key.clone(),
+            node_num,
+            node_num,
+            threshold,
+        );
+        let recovery = Recovery {
+            our_idx,
+            sec_key,
+            lost_pub_key: pub_keys[lost_idx],
+            pk_set,
+            lost_idx,
+            poly,
+            dealings,
+            contributions: BTreeMap::new(),
+        };
+        Ok((recovery, part))
+    }
+
+    /// Handles a part from the helper `dealer`. The recovering node only verifies the
+    /// commitment.
+    ///
+    /// Even if our value is invalid, the commitment is kept, so that we can complain and receive
+    /// the value in the helper's justification.
+    pub fn handle_part(
+        &mut self,
+        dealer: usize,
+        part: RecoveryPart,
+    ) -> ::std::result::Result<(), Fault> {
+        if !self.dealings.is_dealer(dealer) || dealer == self.lost_idx {
+            return Err(Fault::UnknownSender);
+        }
+        if self.dealings.has_part(dealer) {
+            return Err(Fault::DuplicatePart);
+        }
+        let RecoveryPart { commit, values } = part;
+        if commit.coeff.len() != self.pk_set.threshold() + 1 {
+            return Err(Fault::WrongDegree);
+        }
+        if !commit.evaluate(self.lost_idx + 1).is_zero() {
+            return Err(Fault::NonZeroMask);
+        }
+        self.dealings.handle_part(dealer, commit, &values)
+    }
+
+    /// Returns our complaints about all helpers whose part contained an invalid value for us.
+    /// They must be sent to all nodes once the deadline for the parts has passed.
+    pub fn complaints(&self) -> Vec<Complaint> {
+        self.dealings.complaints()
+    }
+
+    /// Handles a complaint from the helper `sender`. If it is about us, returns our
+    /// justification, which must be sent to all nodes.
+    pub fn handle_complaint(
+        &mut self,
+        sender: usize,
+        complaint: Complaint,
+    ) -> ::std::result::Result<Option<ValueJustification>, Fault> {
+        if sender == self.lost_idx {
+            return Err(Fault::UnknownSender);
+        }
+        let ours = self.poly.as_ref().map(|poly| (self.our_idx, poly));
+        self.dealings.handle_complaint(sender, complaint, ours)
+    }
+
+    /// Handles a justification from the helper `dealer`. An invalid justification disqualifies
+    /// the helper.
+    pub fn handle_justification(
+        &mut self,
+        dealer: usize,
+        justification: ValueJustification,
+    ) -> ::std::result::Result<(), Fault> {
+        self.dealings.handle_justification(dealer, justification)
+    }
+
+    /// Returns the indices of the helpers that sent a part and are not disqualified.
+    ///
+    /// This is only final after all complaints and justifications have been handled.
+    pub fn dealers(&self) -> BTreeSet<usize> {
+        self.dealings.qualified_dealers()
+    }
+
+    /// Returns the indices of the helpers that didn't validly answer all complaints, or that
+    /// more than `threshold` nodes complained about.
+    pub fn disqualified_dealers(&self) -> BTreeSet<usize> {
+        self.dealings.disqualified_dealers()
+    }
+
+    /// Returns `true` if at least `threshold + 1` helpers qualify. Then at least one of them is
+    /// honest.
+    pub fn is_ready(&self) -> bool {
+        self.dealers().len() > self.pk_set.threshold()
+    }
+
+    /// Returns a helper's contribution for the recovering node. `sk_share` must be our secret key
+    /// share. This must only be called after all complaints and justifications have been
+    /// handled, so that all nodes use the same helpers' masks.
+    ///
+    /// Returns `Error::NotEnoughShares` if fewer than `threshold + 1` helpers qualify, and
+    /// `Error::MissingValue` if we don't have a valid value from one of them.
+    pub fn contribution<R: Rng>(
+        &self,
+        sk_share: &SecretKeyShare,
+        rng: &mut R,
+    ) -> Result<Contribution> {
+        let dealers = self.dealers();
+        if dealers.len() <= self.pk_set.threshold() {
+            return Err(Error::NotEnoughShares {
+                supplied: dealers.len(),
+                needed: self.pk_set.threshold() + 1,
+            });
+        }
+        let mut value = *(sk_share.0).0;
+        for dealer in dealers {
+            match self.dealings.value(dealer) {
+                Ok(mask) => value.add_assign(mask),
+                Err(err) => {
+                    clear_fr(&mut value);
+                    return Err(err);
+                }
+            }
+        }
+        let label = Round::RecoveryContribution.label(&[self.our_idx, self.lost_idx]);
+        let ct = encrypt_fr_vec(&self.lost_pub_key, &[value], &label, rng);
+        clear_fr(&mut value);
+        Ok(Contribution(ct))
+    }
+
+    /// Handles a contribution from `sender`. This must only be called by the recovering node, and
+    /// only after all complaints and justifications have been handled.
+    pub fn handle_contribution(
+        &mut self,
+        sender: usize,
+        contribution: Contribution,
+    ) -> ::std::result::Result<(), Fault> {
+        if !self.dealings.is_dealer(sender) || sender == self.lost_idx {
+            return Err(Fault::UnknownSender);
+        }
+        if self.contributions.contains_key(&sender) {
+            return Err(Fault::DuplicateContribution);
+        }
+        let label = Round::RecoveryContribution.label(&[sender, self.lost_idx]);
+        let value = decrypt_fr(&self.sec_key, &contribution.0, &label);
+        let mut value = value.ok_or(Fault::ValueDecryption)?;
+        let mut expected = (self.pk_set.public_key_share(sender).0).0;
+        for dealer in self.dealers() {
+            if let Some(commit) = self.dealings.commitment(dealer) {
+                expected.add_assign(&commit.evaluate(sender + 1));
+            }
+        }
+        if expected != G1Affine::one().mul(value) {
+            clear_fr(&mut value);
+            return Err(Fault::ValueMismatch);
+        }
+        self.contributions.insert(sender, value);
+        clear_fr(&mut value);
+        Ok(())
+    }
+
+    /// Returns the recovered secret key share, interpolated from the `threshold + 1` valid
+    /// contributions with the lowest sender indices.
+    ///
+    /// Returns `Error::NotEnoughShares` if fewer than `threshold + 1` contributions are valid,
+    /// and `Error::KeyMismatch` if the result doesn't match the public key share.
+    pub fn recover(&self) -> Result<SecretKeyShare> {
+        let needed = self.pk_set.threshold() + 1;
+        if self.contributions.len() < needed {
+            return Err(Error::NotEnoughShares {
+                supplied: self.contributions.len(),
+                needed,
+            });
+        }
+        // Shift the points so that the lost index is at `0`.
+        let lost_x: Fr = into_fr_plus_1(self.lost_idx);
+        let xs: Vec<Fr> = self
+            .contributions
+            .keys()
+            .take(needed)
+            .map(|sender| {
+                let mut x: Fr = into_fr_plus_1(sender);
+                x.sub_assign(&lost_x);
+                x
+            }).collect();
+        let coeffs = lagrange_coeffs(&xs).expect("sender indices are distinct");
+        let mut sk_val = Fr::zero();
+        for (l, value) in coeffs.iter().zip(self.contributions.values()) {
+            let mut tmp = *value;
+            tmp.mul_assign(l);
+            sk_val.add_assign(&tmp);
+            clear_fr(&mut tmp);
+        }
+        let sk_share = SecretKeyShare::from_mut(&mut sk_val);
+        if sk_share.public_key_share() != self.pk_set.public_key_share(self.lost_idx) {
+            return Err(Error::KeyMismatch);
+        }
+        Ok(sk_share)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use rand::{self, Rng};
+
+    use test_utils::{self, run_dealings, ValueNode};
+    use SecretKeySet;
+
+    impl ValueNode for Recovery {
+        type Part = RecoveryPart;
+
+        fn handle_part(
+            &mut self,
+            dealer: usize,
+            part: RecoveryPart,
+        ) -> ::std::result::Result<(), Fault> {
+            Recovery::handle_part(self, dealer, part)
+        }
+
+        fn complaints(&self) -> Vec<Complaint> {
+            Recovery::complaints(self)
+        }
+
+        fn handle_complaint(
+            &mut self,
+            sender: usize,
+            complaint: Complaint,
+        ) -> ::std::result::Result<Option<ValueJustification>, Fault> {
+            Recovery::handle_complaint(self, sender, complaint)
+        }
+
+        fn handle_justification(
+            &mut self,
+            dealer: usize,
+            justification: ValueJustification,
+        ) -> ::std::result::Result<(), Fault> {
+            Recovery::handle_justification(self, dealer, justification)
+        }
+    }
+
+    /// Runs the recovery of share `lost_idx` of `sk_set` with `num_nodes` nodes, up to the
+    /// contributions. Each helper's part is modified by `corrupt(dealer, part, pub_keys)`, and
+    /// `justify(dealer, justification)` is sent instead of their justifications. Returns the nodes
+    /// and their secret keys.
+    fn run_recovery<F, G>(
+        sk_set: &SecretKeySet,
+        num_nodes: usize,
+        lost_idx: usize,
+        corrupt: F,
+        justify: G,
+    ) -> (Vec<Recovery>, Vec<SecretKey>)
+    where
+        F: Fn(usize, &mut RecoveryPart, &[PublicKey]),
+        G: Fn(usize, ValueJustification) -> Option<ValueJustification>,
+    {
+        let mut rng = rand::thread_rng();
+        let pk_set = sk_set.public_keys();
+        let sec_keys: Vec<SecretKey> = (0..num_nodes).map(|_| rng.gen()).collect();
+        let pub_keys: Vec<PublicKey> = sec_keys.iter().map(SecretKey::public_key).collect();
+        let mut nodes = Vec::new();
+        let mut parts = Vec::new();
+        for (idx, sec_key) in sec_keys.iter().enumerate() {
+            let (node, part) = Recovery::new(
+                idx,
+                sec_key.clone(),
+                &pub_keys,
+                pk_set.clone(),
+                lost_idx,
+                &mut rng,
+            )
+            .expect("new recovery");
+            if let Some(mut part) = part {
+                corrupt(idx, &mut part, &pub_keys);
+                parts.push((idx, part));
+            }
+            nodes.push(node);
+        }
+        run_dealings(&mut nodes, parts, justify);
+        (nodes, sec_keys)
+    }
+
+    /// Sends the contributions of the given helpers to the recovering node, and returns the
+    /// recovered secret key share.
+    fn recover(
+        nodes: &mut [Recovery],
+        sec_keys: &[SecretKey],
+        sk_set: &SecretKeySet,
+        lost_idx: usize,
+        helpers: &[usize],
+    ) -> SecretKeyShare {
+        let mut rng = rand::thread_rng();
+        for helper in helpers {
+            let sk_share = sk_set.secret_key_share(*helper);
+            let contribution = nodes[*helper]
+                .contribution(&sk_share, &mut rng)
+                .expect("contribution");
+            // The contribution doesn't reveal the helper's secret key share.
+            let label = Round::RecoveryContribution.label(&[*helper, lost_idx]);
+            let value = decrypt_fr(&sec_keys[lost_idx], &contribution.0, &label);
+            let value = value.expect("decrypt");
+            assert_ne!(*(sk_share.0).0, value);
+            nodes[lost_idx]
+                .handle_contribution(*helper, contribution)
+                .expect("valid contribution");
+        }
+        nodes[lost_idx].recover().expect("recover share")
+    }
+
+    /// Replaces node `m`'s value in `dealer`'s part with an invalid one.
+    fn corrupt_value(part: &mut RecoveryPart, dealer: usize, m: usize, pk: &PublicKey) {
+        test_utils::corrupt_value(&mut part.values, Round::Recovery, dealer, m, pk);
+    }
+
+    #[test]
+    fn test_recovery() {
+        let mut rng = rand::thread_rng();
+        let sk_set = SecretKeySet::random(2, &mut rng);
+        let lost_idx = 1;
+        let (mut nodes, sec_keys) =
+            run_recovery(&sk_set, 5, lost_idx, |_, _, _| (), |_, j| Some(j));
+        for node in &nodes {
+            assert_eq!(4, node.dealers().len());
+            assert!(node.is_ready());
+        }
+        let sk_share = recover(&mut nodes, &sec_keys, &sk_set, lost_idx, &[4, 3, 0]);
+        assert_eq!(sk_set.secret_key_share(lost_idx), sk_share);
+    }
+
+    #[test]
+    fn test_recovery_invalid_value() {
+        // Helper 0 sends an invalid value to helper 2. Helper 2 complains, and receives the
+        // correct value in the justification, so its contribution still matches.
+        let mut rng = rand::thread_rng();
+        let sk_set = SecretKeySet::random(1, &mut rng);
+        let corrupt = |dealer: usize, part: &mut RecoveryPart, pub_keys: &[PublicKey]| {
+            if dealer == 0 {
+                corrupt_value(part, dealer, 2, &pub_keys[2]);
+            }
+        };
+        let (mut nodes, sec_keys) = run_recovery(&sk_set, 4, 3, corrupt, |_, j| Some(j));
+        for node in &nodes {
+            assert_eq!(3, node.dealers().len());
+        }
+        let sk_share = recover(&mut nodes, &sec_keys, &sk_set, 3, &[0, 2]);
+        assert_eq!(sk_set.secret_key_share(3), sk_share);
+    }
+
+    #[test]
+    fn test_recovery_disqualified() {
+        // Helper 0 sends an invalid value to helper 2, and doesn't answer the complaint. Its mask
+        // is not used by anyone.
+        let mut rng = rand::thread_rng();
+        let sk_set = SecretKeySet::random(1, &mut rng);
+        let corrupt = |dealer: usize, part: &mut RecoveryPart, pub_keys: &[PublicKey]| {
+            if dealer == 0 {
+                corrupt_value(part, dealer, 2, &pub_keys[2]);
+            }
+        };
+        let justify = |dealer: usize, justification| match dealer {
+            0 => None,
+            _ => Some(justification),
+        };
+        let (mut nodes, sec_keys) = run_recovery(&sk_set, 4, 3, corrupt, justify);
+        let dealers: BTreeSet<usize> = [1, 2].iter().cloned().collect();
+        for node in &nodes {
+            assert_eq!(dealers, node.dealers());
+        }
+        let sk_share = recover(&mut nodes, &sec_keys, &sk_set, 3, &[2, 1]);
+        assert_eq!(sk_set.secret_key_share(3), sk_share);
+    }
+
+    #[test]
+    fn test_recovery_faults() {
+        let mut rng = rand::thread_rng();
+        let sk_set = SecretKeySet::random(1, &mut rng);
+        let pk_set = sk_set.public_keys();
+        let sec_keys: Vec<SecretKey> = (0..3).map(|_| rng.gen()).collect();
+        let pub_keys: Vec<PublicKey> = sec_keys.iter().map(SecretKey::public_key).collect();
+
+        // Both indices must be valid, and our secret key must match our public key.
+        let sec_key = &sec_keys[0];
+        let result = Recovery::new(3, sec_key.clone(), &pub_keys, pk_set.clone(), 2, &mut rng);
+        let expected = Error::IndexOutOfRange { index: 3, count: 3 };
+        assert_eq!(Some(expected.clone()), result.err());
+        let result = Recovery::new(0, sec_key.clone(), &pub_keys, pk_set.clone(), 3, &mut rng);
+        assert_eq!(Some(expected), result.err());
+        let result = Recovery::new(1, sec_key.clone(), &pub_keys, pk_set.clone(), 2, &mut rng);
+        assert_eq!(Some(Error::KeyMismatch), result.err());
+
+        let new_recovery = |idx: usize, lost_idx, rng: &mut rand::ThreadRng| {
+            let sec_key = sec_keys[idx].clone();
+            Recovery::new(idx, sec_key, &pub_keys, pk_set.clone(), lost_idx, rng)
+                .expect("new recovery")
+        };
+        let (mut helper, part0) = new_recovery(0, 2, &mut rng);
+        let (mut lost, no_part) = new_recovery(2, 2, &mut rng);
+        assert_eq!(None, no_part);
+
+        // The masking polynomial must be zero at the lost index.
+        let (_, wrong_part) = new_recovery(0, 1, &mut rng);
+        let wrong_part = wrong_part.expect("helper's part");
+        assert_eq!(Err(Fault::NonZeroMask), helper.handle_part(1, wrong_part));
+        // The recovering node doesn't deal.
+        let part0 = part0.expect("helper's part");
+        assert_eq!(
+            Err(Fault::UnknownSender),
+            lost.handle_part(2, part0.clone())
+        );
+        let (_, part1) = new_recovery(1, 2, &mut rng);
+        for (dealer, part) in [(0, part0), (1, part1.expect("helper's part"))] {
+            helper
+                .handle_part(dealer, part.clone())
+                .expect("valid part");
+            lost.handle_part(dealer, part).expect("valid part");
+        }
+        assert!(helper.complaints().is_empty());
+        assert!(lost.complaints().is_empty());
+        let result = lost.handle_complaint(2, Complaint { dealer: 0 });
+        assert_eq!(Err(Fault::UnknownSender), result);
+
+        // A contribution from the wrong secret key share is rejected.
+        let wrong_share = sk_set.secret_key_share(1);
+        let contribution = helper
+            .contribution(&wrong_share, &mut rng)
+            .expect("contribution");
+        let result = lost.handle_contribution(0, contribution);
+        assert_eq!(Err(Fault::ValueMismatch), result);
+
+        let sk_share = sk_set.secret_key_share(0);
+        let contribution = helper
+            .contribution(&sk_share, &mut rng)
+            .expect("contribution");
+        let result = lost.handle_contribution(2, contribution.clone());
+        assert_eq!(Err(Fault::UnknownSender), result);
+        lost.handle_contribution(0, contribution.clone())
+            .expect("valid contribution");
+        let result = lost.handle_contribution(0, contribution);
+        assert_eq!(Err(Fault::DuplicateContribution), result);
+        let expected = Error::NotEnoughShares {
+            supplied: 1,
+            needed: 2,
+        };
+        assert_eq!(Some(expected), lost.recover().err());
+    }
+}
@@ -0,0 +1,452 @@
+//! Proactive refresh of secret key shares, without changing the master key.
+//!
+//! Each node deals a random polynomial `g` of degree `t` with `g(0) = 0`: It sends a
+//! `RefreshPart`, containing the commitment to `g` and, for every node `m`, the value `g(m + 1)`
+//! encrypted to node `m`. Every node checks that the committed constant term is zero, and that
+//! its own value matches the commitment. Each node adds its values to its old secret key share,
+//! and the sum of all commitments to the old public key set, which results in a new key set with
+//! the same master key. The old shares are useless in combination with the new ones, so an
+//! attacker has to learn `t + 1` shares between two refreshes.
+//!
+//! As in the key generation, all messages must be broadcast, and a node that didn't receive a
+//! valid value sends a `Complaint`, which the dealer must answer with a `ValueJustification`
+//! that publicly reveals the value. A dealer is disqualified if it doesn't validly answer every
+//! complaint, or if more than `t` nodes complain. Which dealers qualify thus only depends on
+//! public messages, so all nodes use the same ones. At least `t + 1` of them are needed, so that
+//! at least one dealer is honest.
+
+use std::collections::BTreeSet;
+
+use pairing::{CurveProjective, Field};
+use rand::Rng;
+
+use dkg::{
+    check_our_key, encrypt_fr_vec, Complaint, Fault, Round, ValueDealings, ValueJustification,
+};
+use error::{Error, Result};
+use poly::{Commitment, Poly};
+use secret::clear_fr;
+use {Ciphertext, Fr, PublicKey, PublicKeySet, SecretKey, SecretKeyShare};
+
+/// A dealer's message: the commitment to a random polynomial with constant term zero, and its
+/// value for each node, encrypted to that node.
+#[derive(Deserialize, Serialize, Clone, PartialEq, Eq, Debug)]
+pub struct RefreshPart {
+    commit: Commitment,
+    values: Vec<Ciphertext>,
+}
+
+/// A node's state in a share refresh.
+pub struct Refresh {
+    /// Our node index, which is also the index of our secret key share.
+    our_idx: usize,
+    /// The current public key set.
+    pk_set: PublicKeySet,
+    /// Our own polynomial, as a dealer.
+    poly: Poly,
+    /// The dealers' parts, complaints and justifications.
+    dealings: ValueDealings,
+}
+
+impl Refresh {
+    /// Creates a new refresh instance for node `our_idx`, with the given public keys of all
+    /// nodes and the current public key set, and returns it together with our own part.
+    ///
+    /// Returns `Error::IndexOutOfRange` if `our_idx` is not an index of `pub_keys`, and
+    /// `Error::KeyMismatch` if `sec_key` doesn't match our public key in `pub_keys`.
+    pub fn new<R: Rng>(
+        our_idx: usize,
+        sec_key: SecretKey,
+        pub_keys: Vec<PublicKey>,
+        pk_set: PublicKeySet,
+        rng: &mut R,
+    ) -> Result<(Refresh, RefreshPart)> {
+        check_our_key(our_idx, &sec_key, &pub_keys)?;
+        let mut poly = Poly::try_random(pk_set.threshold(), rng)?;
+        poly.coeff[0] = Fr::zero();
+        let values = pub_keys
+            .iter()
+            .enumerate()
+            .map(|(m, pk)| {
+                let label = Round::Refresh.label(&[our_idx, m]);
+                encrypt_fr_vec(pk, &[poly.evaluate(m + 1)], &label, rng)
+            }).collect();
+        let part = RefreshPart {
+            commit: poly.commitment(),
+            values,
+        };
+        let node_num = pub_keys.len();
+        let threshold = pk_set.threshold();
+        let refresh = Refresh {
+            our_idx,
+            pk_set,
+            poly,
+            dealings: ValueDealings::new(
+                Round::Refresh,
+                Some(our_idx),
+                sec_key,
+                node_num,
+                node_num,
+                threshold,
+            ),
+        };
+        Ok((refresh, part))
+    }
+
+    /// Handles a part from `dealer`.
+    ///
+    /// Even if our value is invalid, the commitment is kept, so that we can complain and receive
+    /// the value in the dealer's justification.
+    pub fn handle_part(
+        &mut self,
+        dealer: usize,
+        part: RefreshPart,
+    ) -> ::std::result::Result<(), Fault> {
+        if !self.dealings.is_dealer(dealer) {
+            return Err(Fault::UnknownSender);
+        }
+        if self.dealings.has_part(dealer) {
+            return Err(Fault::DuplicatePart);
+        }
+        let RefreshPart { commit, values } = part;
+        if commit.coeff.len() != self.pk_set.threshold() + 1 {
+            return Err(Fault::WrongDegree);
+        }
+        if !commit.evaluate(0).is_zero() {
+            return Err(Fault::NonZeroConstant);
+        }
+        self.dealings.handle_part(dealer, commit, &values)
+    }
+
+    /// Returns our complaints about all dealers whose part contained an invalid value for us.
+    /// They must be sent to all nodes once the deadline for the parts has passed.
+    pub fn complaints(&self) -> Vec<Complaint> {
+        self.dealings.complaints()
+    }
+
+    /// Handles a complaint from node `sender`. If it is about us, returns our justification,
+    /// which must be sent to all nodes.
+    pub fn handle_complaint(
+        &mut self,
+        sender: usize,
+        complaint: Complaint,
+    ) -> ::std::result::Result<Option<ValueJustification>, Fault> {
+        let ours = Some((self.our_idx, &self.poly));
+        self.dealings.handle_complaint(sender, complaint, ours)
+    }
+
+    /// Handles a justification from `dealer`. An invalid justification disqualifies the dealer.
+    pub fn handle_justification(
+        &mut self,
+        dealer: usize,
+        justification: ValueJustification,
+    ) -> ::std::result::Result<(), Fault> {
+        self.dealings.handle_justification(dealer, justification)
+    }
+
+    /// Returns the indices of the dealers that sent a part and are not disqualified.
+    ///
+    /// This is only final after all complaints and justifications have been handled.
+    pub fn dealers(&self) -> BTreeSet<usize> {
+        self.dealings.qualified_dealers()
+    }
+
+    /// Returns the indices of the dealers that didn't validly answer all complaints, or that more
+    /// than `threshold` nodes complained about.
+    pub fn disqualified_dealers(&self) -> BTreeSet<usize> {
+        self.dealings.disqualified_dealers()
+    }
+
+    /// Returns `true` if at least `threshold + 1` dealers qualify. Then at least one of them is
+    /// honest.
+    pub fn is_ready(&self) -> bool {
+        self.dealers().len() > self.pk_set.threshold()
+    }
+
+    /// Returns the new public key set, and our new secret key share. `sk_share` must be our
+    /// current one.
+    ///
+    /// Returns `Error::NotEnoughShares` if fewer than `threshold + 1` dealers qualify, and
+    /// `Error::MissingValue` if we don't have a valid value from one of them.
+    pub fn generate(&self, sk_share: &SecretKeyShare) -> Result<(PublicKeySet, SecretKeyShare)> {
+        let dealers = self.dealers();
+        if dealers.len() <= self.pk_set.threshold() {
+            return Err(Error::NotEnoughShares {
+                supplied: dealers.len(),
+                needed: self.pk_set.threshold() + 1,
+            });
+        }
+        let mut commit = self.pk_set.commit.clone();
+        let mut sk_val = *(sk_share.0).0;
+        for dealer in dealers {
+            if let Some(part_commit) = self.dealings.commitment(dealer) {
+                commit += part_commit;
+            }
+            match self.dealings.value(dealer) {
+                Ok(value) => sk_val.add_assign(value),
+                Err(err) => {
+                    clear_fr(&mut sk_val);
+                    return Err(err);
+                }
+            }
+        }
+        Ok((commit.into(), SecretKeyShare::from_mut(&mut sk_val)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::collections::BTreeMap;
+
+    use rand::{self, Rng};
+
+    use test_utils::{self, run_dealings, ValueNode};
+    use SecretKeySet;
+
+    impl ValueNode for Refresh {
+        type Part = RefreshPart;
+
+        fn handle_part(
+            &mut self,
+            dealer: usize,
+            part: RefreshPart,
+        ) -> ::std::result::Result<(), Fault> {
+            Refresh::handle_part(self, dealer, part)
+        }
+
+        fn complaints(&self) -> Vec<Complaint> {
+            Refresh::complaints(self)
+        }
+
+        fn handle_complaint(
+            &mut self,
+            sender: usize,
+            complaint: Complaint,
+        ) -> ::std::result::Result<Option<ValueJustification>, Fault> {
+            Refresh::handle_complaint(self, sender, complaint)
+        }
+
+        fn handle_justification(
+            &mut self,
+            dealer: usize,
+            justification: ValueJustification,
+        ) -> ::std::result::Result<(), Fault> {
+            Refresh::handle_justification(self, dealer, justification)
+        }
+    }
+
+    /// Runs the refresh of `sk_set` with `num_nodes` nodes, where each dealer's part is modified
+    /// by `corrupt(dealer, part, pub_keys)`, and `justify(dealer, justification)` is sent instead
+    /// of their justifications.
+    fn run_refresh<F, G>(
+        num_nodes: usize,
+        sk_set: &SecretKeySet,
+        corrupt: F,
+        justify: G,
+    ) -> Vec<Refresh>
+    where
+        F: Fn(usize, &mut RefreshPart, &[PublicKey]),
+        G: Fn(usize, ValueJustification) -> Option<ValueJustification>,
+    {
+        let mut rng = rand::thread_rng();
+        let pk_set = sk_set.public_keys();
+        let sec_keys: Vec<SecretKey> = (0..num_nodes).map(|_| rng.gen()).collect();
+        let pub_keys: Vec<PublicKey> = sec_keys.iter().map(SecretKey::public_key).collect();
+        let mut nodes = Vec::new();
+        let mut parts = Vec::new();
+        for (idx, sec_key) in sec_keys.into_iter().enumerate() {
+            let (node, mut part) =
+                Refresh::new(idx, sec_key, pub_keys.clone(), pk_set.clone(), &mut rng)
+                    .expect("new refresh");
+            corrupt(idx, &mut part, &pub_keys);
+            nodes.push(node);
+            parts.push((idx, part));
+        }
+        run_dealings(&mut nodes, parts, justify);
+        nodes
+    }
+
+    /// Replaces node `m`'s value in `dealer`'s part with an invalid one.
+    fn corrupt_value(part: &mut RefreshPart, dealer: usize, m: usize, pk: &PublicKey) {
+        test_utils::corrupt_value(&mut part.values, Round::Refresh, dealer, m, pk);
+    }
+
+    /// Checks that all nodes generate matching keys for the same master key, and returns the
+    /// new public key set and secret key shares.
+    fn check_keys(
+        nodes: &[Refresh],
+        sk_set: &SecretKeySet,
+    ) -> (PublicKeySet, BTreeMap<usize, SecretKeyShare>) {
+        let mut new_pk_set = None;
+        let mut new_sk_shares = BTreeMap::new();
+        for (m, node) in nodes.iter().enumerate() {
+            assert!(node.is_ready());
+            let old_sk_share = sk_set.secret_key_share(m);
+            let (node_pk_set, sk_share) = node.generate(&old_sk_share).expect("generate keys");
+            assert_ne!(old_sk_share, sk_share);
+            assert_eq!(node_pk_set.public_key_share(m), sk_share.public_key_share());
+            if let Some(ref pk_set) = new_pk_set {
+                assert_eq!(*pk_set, node_pk_set);
+            }
+            new_sk_shares.insert(m, sk_share);
+            new_pk_set = Some(node_pk_set);
+        }
+        let new_pk_set = new_pk_set.expect("at least one node");
+        assert_eq!(sk_set.public_keys().public_key(), new_pk_set.public_key());
+        (new_pk_set, new_sk_shares)
+    }
+
+    #[test]
+    fn test_refresh() {
+        let mut rng = rand::thread_rng();
+        let sk_set = SecretKeySet::random(2, &mut rng);
+        let pk_set = sk_set.public_keys();
+        let nodes = run_refresh(5, &sk_set, |_, _, _| (), |_, j| Some(j));
+        for node in &nodes {
+            assert_eq!(5, node.dealers().len());
+            assert!(node.disqualified_dealers().is_empty());
+        }
+        let (new_pk_set, new_sk_shares) = check_keys(&nodes, &sk_set);
+        assert_ne!(pk_set, new_pk_set);
+
+        // The new shares can be combined to sign with the old master key.
+        let msg = "Totally real news";
+        let sigs: BTreeMap<_, _> = new_sk_shares
+            .iter()
+            .take(3)
+            .map(|(m, sk_share)| (*m, sk_share.sign(msg)))
+            .collect();
+        let sig = new_pk_set.combine_signatures(&sigs).expect("enough shares");
+        assert!(pk_set.public_key().verify(&sig, msg));
+
+        // Old and new shares don't match.
+        let mixed_sigs = [
+            (0, sk_set.secret_key_share(0).sign(msg)),
+            (1, new_sk_shares[&1].sign(msg)),
+            (2, new_sk_shares[&2].sign(msg)),
+        ];
+        let shares = mixed_sigs.iter().map(|(i, sig)| (*i, sig));
+        let sig = new_pk_set
+            .combine_signatures(shares)
+            .expect("enough shares");
+        assert!(!pk_set.public_key().verify(&sig, msg));
+    }
+
+    #[test]
+    fn test_refresh_invalid_value() {
+        // Dealer 2 sends an invalid value to node 0. Node 0 complains, and receives the correct
+        // value in the justification, so all nodes still use the same dealers.
+        let mut rng = rand::thread_rng();
+        let sk_set = SecretKeySet::random(1, &mut rng);
+        let corrupt = |dealer: usize, part: &mut RefreshPart, pub_keys: &[PublicKey]| {
+            if dealer == 2 {
+                corrupt_value(part, dealer, 0, &pub_keys[0]);
+            }
+        };
+        let nodes = run_refresh(4, &sk_set, corrupt, |_, j| Some(j));
+        for node in &nodes {
+            assert_eq!(4, node.dealers().len());
+            assert!(node.disqualified_dealers().is_empty());
+        }
+        check_keys(&nodes, &sk_set);
+    }
+
+    #[test]
+    fn test_refresh_disqualified() {
+        // Dealers 1 and 3 send invalid values to node 0. Dealer 1 doesn't answer the complaint,
+        // and dealer 3 sends an invalid justification.
+        let mut rng = rand::thread_rng();
+        let sk_set = SecretKeySet::random(1, &mut rng);
+        let corrupt = |dealer: usize, part: &mut RefreshPart, pub_keys: &[PublicKey]| {
+            if dealer == 1 || dealer == 3 {
+                corrupt_value(part, dealer, 0, &pub_keys[0]);
+            }
+        };
+        let justify = |dealer: usize, justification: ValueJustification| match dealer {
+            1 => None,
+            3 => {
+                let wrong_poly = Poly::monomial(1);
+                Some(ValueJustification::new(
+                    justification.complainer(),
+                    &wrong_poly,
+                ))
+            }
+            _ => Some(justification),
+        };
+        let nodes = run_refresh(4, &sk_set, corrupt, justify);
+        let disqualified: BTreeSet<usize> = [1, 3].iter().cloned().collect();
+        let qualified: BTreeSet<usize> = [0, 2].iter().cloned().collect();
+        for node in &nodes {
+            assert_eq!(disqualified, node.disqualified_dealers());
+            assert_eq!(qualified, node.dealers());
+        }
+        check_keys(&nodes, &sk_set);
+    }
+
+    #[test]
+    fn test_refresh_faults() {
+        let mut rng = rand::thread_rng();
+        let pk_set = SecretKeySet::random(1, &mut rng).public_keys();
+        let sec_keys: Vec<SecretKey> = (0..4).map(|_| rng.gen()).collect();
+        let pub_keys: Vec<PublicKey> = sec_keys.iter().map(SecretKey::public_key).collect();
+
+        // Our index must be valid, and our secret key must match our public key.
+        let new_refresh = |idx, sec_key: &SecretKey, rng: &mut rand::ThreadRng| {
+            Refresh::new(idx, sec_key.clone(), pub_keys.clone(), pk_set.clone(), rng)
+        };
+        let expected = Error::IndexOutOfRange { index: 4, count: 4 };
+        assert_eq!(Some(expected), new_refresh(4, &sec_keys[0], &mut rng).err());
+        let result = new_refresh(1, &sec_keys[0], &mut rng);
+        assert_eq!(Some(Error::KeyMismatch), result.err());
+        let mut new_part = |idx: usize| {
+            let (_, part) = new_refresh(idx, &sec_keys[idx], &mut rng).expect("new refresh");
+            part
+        };
+        let (part1, part2) = (new_part(1), new_part(2));
+        let (mut node, part) = new_refresh(0, &sec_keys[0], &mut rng).expect("new refresh");
+
+        // A polynomial with a nonzero constant term would change the master key.
+        let mut nonzero_part = part1.clone();
+        nonzero_part.commit = Poly::random(1, &mut rng).commitment();
+        let result = node.handle_part(1, nonzero_part);
+        assert_eq!(Err(Fault::NonZeroConstant), result);
+        assert_eq!(Err(Fault::UnknownSender), node.handle_part(4, part.clone()));
+
+        // Parts with invalid values for us are kept, so that we can complain about them.
+        let mut wrong_part = part1.clone();
+        let label = Round::Refresh.label(&[1, 0]);
+        wrong_part.values[0] = pub_keys[0].encrypt_with_label([0u8; 3], label);
+        assert_eq!(Err(Fault::ValueDecryption), node.handle_part(1, wrong_part));
+        let mut wrong_part = part2.clone();
+        corrupt_value(&mut wrong_part, 2, 0, &pub_keys[0]);
+        assert_eq!(Err(Fault::ValueMismatch), node.handle_part(2, wrong_part));
+        assert_eq!(Err(Fault::DuplicatePart), node.handle_part(2, part2));
+        // The values are labeled with the dealer's index, so they can't be copied.
+        assert_eq!(Err(Fault::ValueDecryption), node.handle_part(3, part1));
+        let dealers: Vec<usize> = node.complaints().iter().map(Complaint::dealer).collect();
+        assert_eq!(vec![1, 2, 3], dealers);
+
+        let justification = node
+            .handle_complaint(1, Complaint { dealer: 0 })
+            .expect("valid complaint")
+            .expect("justification");
+        assert_eq!(1, justification.complainer());
+        let result = node.handle_complaint(1, Complaint { dealer: 0 });
+        assert_eq!(Err(Fault::DuplicateComplaint), result);
+
+        // We haven't handled our own part, so we can't verify the justification.
+        let result = node.handle_justification(0, justification.clone());
+        assert_eq!(Err(Fault::InvalidJustification), result);
+        let result = node.handle_justification(3, justification);
+        assert_eq!(Err(Fault::UnexpectedJustification), result);
+        let expected: BTreeSet<usize> = [1, 2, 3].iter().cloned().collect();
+        assert_eq!(expected, node.dealers());
+
+        // Until our complaints are answered, we are missing the values from dealers 1 and 2.
+        let sk_share = SecretKeySet::random(1, &mut rng).secret_key_share(0);
+        let result = node.generate(&sk_share);
+        assert_eq!(Some(Error::MissingValue { dealer: 1 }), result.err());
+    }
+}
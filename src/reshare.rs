@@ -0,0 +1,525 @@
+//! Resharing of a master key to a new set of nodes, with a new threshold.
+//!
+//! Each old node `i`, holding secret key share `s_i` under the old public key set, deals a random
+//! polynomial `g_i` of the new degree `t'` with `g_i(0) = s_i`: It sends a `ResharePart`,
+//! containing the commitment to `g_i` and, for every new node `m`, the value `g_i(m + 1)`
+//! encrypted to node `m`. The new nodes check the committed constant term against the old public
+//! key share `i`, and their own value against the commitment.
+//!
+//! As in the key generation, all messages must be broadcast. A new node that didn't receive a
+//! valid value sends a `Complaint`, which the old node must answer with a `ValueJustification`
+//! that publicly reveals the value. An old node is disqualified if it doesn't validly answer
+//! every complaint, or if more than `t'` new nodes complain. Which old nodes qualify thus only
+//! depends on public messages, so all new nodes agree on it.
+//!
+//! Once at least `t + 1` old nodes qualify, where `t` is the old threshold, the new nodes
+//! interpolate: With the Lagrange coefficients `l_i` for the `t + 1` qualified old indices, the
+//! sum of `l_i * g_i` is a polynomial of degree `t'` whose value at `0` is the old master secret.
+//! Each new node's secret key share is its value, and the new public key set is the commitment to
+//! it.
+
+use std::collections::BTreeSet;
+
+use pairing::{CurveAffine, CurveProjective, Field};
+use rand::Rng;
+
+use dkg::{
+    check_our_key, encrypt_fr_vec, Complaint, Fault, Round, ValueDealings, ValueJustification,
+};
+use error::{Error, Result};
+use generic::{into_fr_plus_1, lagrange_coeffs};
+use poly::{Commitment, Poly};
+use secret::clear_fr;
+use {Ciphertext, Fr, PublicKey, PublicKeySet, SecretKey, SecretKeyShare, G1};
+
+/// An old node's message: the commitment to a random polynomial whose constant term is the old
+/// node's secret key share, and its value for each new node, encrypted to that node.
+#[derive(Deserialize, Serialize, Clone, PartialEq, Eq, Debug)]
+pub struct ResharePart {
+    commit: Commitment,
+    values: Vec<Ciphertext>,
+}
+
+/// An old node's state in a resharing: It only needs to answer the new nodes' complaints.
+pub struct ReshareDealer {
+    /// Our old node index, which is also the index of our old secret key share.
+    our_idx: usize,
+    /// The polynomial whose constant term is our old secret key share.
+    poly: Poly,
+    /// The number of new nodes.
+    node_num: usize,
+    /// The new threshold.
+    threshold: usize,
+    /// The new nodes that complained about us.
+    complaints: BTreeSet<usize>,
+}
+
+impl ReshareDealer {
+    /// Creates the state of the old node `our_idx`, with the given secret key share, for the new
+    /// nodes with the given public keys and the new threshold, and returns it together with our
+    /// part.
+    pub fn new<R: Rng>(
+        our_idx: usize,
+        sk_share: &SecretKeyShare,
+        pub_keys: &[PublicKey],
+        threshold: usize,
+        rng: &mut R,
+    ) -> Result<(ReshareDealer, ResharePart)> {
+        let mut poly = Poly::try_random(threshold, rng)?;
+        poly.coeff[0] = *(sk_share.0).0;
+        let values = pub_keys
+            .iter()
+            .enumerate()
+            .map(|(m, pk)| {
+                let label = Round::Reshare.label(&[our_idx, m]);
+                encrypt_fr_vec(pk, &[poly.evaluate(m + 1)], &label, rng)
+            }).collect();
+        let part = ResharePart {
+            commit: poly.commitment(),
+            values,
+        };
+        let dealer = ReshareDealer {
+            our_idx,
+            poly,
+            node_num: pub_keys.len(),
+            threshold,
+            complaints: BTreeSet::new(),
+        };
+        Ok((dealer, part))
+    }
+
+    /// Handles a complaint from the new node `sender`. If it is about us, returns our
+    /// justification, which must be sent to all new nodes.
+    ///
+    /// If more than `threshold` new nodes complain about us, we don't answer: We are disqualified
+    /// anyway, and revealing more values would reveal our secret key share.
+    pub fn handle_complaint(
+        &mut self,
+        sender: usize,
+        complaint: Complaint,
+    ) -> ::std::result::Result<Option<ValueJustification>, Fault> {
+        if sender >= self.node_num {
+            return Err(Fault::UnknownSender);
+        }
+        if complaint.dealer() != self.our_idx {
+            return Ok(None);
+        }
+        if !self.complaints.insert(sender) {
+            return Err(Fault::DuplicateComplaint);
+        }
+        if self.complaints.len() > self.threshold {
+            return Ok(None);
+        }
+        Ok(Some(ValueJustification::new(sender, &self.poly)))
+    }
+}
+
+/// A new node's state in a resharing.
+pub struct Reshare {
+    /// The old public key set.
+    old_pk_set: PublicKeySet,
+    /// The new threshold.
+    threshold: usize,
+    /// The old nodes' parts, complaints and justifications.
+    dealings: ValueDealings,
+}
+
+impl Reshare {
+    /// Creates a new resharing instance for the new node `our_idx`, with the old public key set,
+    /// the number of old nodes, the public keys of all new nodes, and the new threshold.
+    ///
+    /// Returns `Error::IndexOutOfRange` if `our_idx` is not an index of `pub_keys`, and
+    /// `Error::KeyMismatch` if `sec_key` doesn't match our public key in `pub_keys`.
+    pub fn new(
+        our_idx: usize,
+        sec_key: SecretKey,
+        old_pk_set: PublicKeySet,
+        old_node_num: usize,
+        pub_keys: &[PublicKey],
+        threshold: usize,
+    ) -> Result<Reshare> {
+        check_our_key(our_idx, &sec_key, pub_keys)?;
+        let dealings = ValueDealings::new(
+            Round::Reshare,
+            Some(our_idx),
+            sec_key,
+            old_node_num,
+            pub_keys.len(),
+            threshold,
+        );
+        Ok(Reshare {
+            old_pk_set,
+            threshold,
+            dealings,
+        })
+    }
+
+    /// Handles a part from the old node with the secret key share `dealer`.
+    ///
+    /// Even if our value is invalid, the commitment is kept, so that we can complain and receive
+    /// the value in the old node's justification.
+    pub fn handle_part(
+        &mut self,
+        dealer: usize,
+        part: ResharePart,
+    ) -> ::std::result::Result<(), Fault> {
+        if !self.dealings.is_dealer(dealer) {
+            return Err(Fault::UnknownSender);
+        }
+        if self.dealings.has_part(dealer) {
+            return Err(Fault::DuplicatePart);
+        }
+        let ResharePart { commit, values } = part;
+        if commit.coeff.len() != self.threshold + 1 {
+            return Err(Fault::WrongDegree);
+        }
+        if commit.evaluate(0) != (self.old_pk_set.public_key_share(dealer).0).0 {
+            return Err(Fault::ConstantMismatch);
+        }
+        self.dealings.handle_part(dealer, commit, &values)
+    }
+
+    /// Returns our complaints about all old nodes whose part contained an invalid value for us.
+    /// They must be sent to all old and new nodes once the deadline for the parts has passed.
+    pub fn complaints(&self) -> Vec<Complaint> {
+        self.dealings.complaints()
+    }
+
+    /// Handles a complaint from the new node `sender`.
+    pub fn handle_complaint(
+        &mut self,
+        sender: usize,
+        complaint: Complaint,
+    ) -> ::std::result::Result<(), Fault> {
+        self.dealings.handle_complaint(sender, complaint, None)?;
+        Ok(())
+    }
+
+    /// Handles a justification from the old node `dealer`. An invalid justification disqualifies
+    /// the old node.
+    pub fn handle_justification(
+        &mut self,
+        dealer: usize,
+        justification: ValueJustification,
+    ) -> ::std::result::Result<(), Fault> {
+        self.dealings.handle_justification(dealer, justification)
+    }
+
+    /// Returns the indices of the old nodes that sent a part and are not disqualified.
+    ///
+    /// This is only final after all complaints and justifications have been handled.
+    pub fn dealers(&self) -> BTreeSet<usize> {
+        self.dealings.qualified_dealers()
+    }
+
+    /// Returns the indices of the old nodes that didn't validly answer all complaints, or that
+    /// more than the new threshold of new nodes complained about.
+    pub fn disqualified_dealers(&self) -> BTreeSet<usize> {
+        self.dealings.disqualified_dealers()
+    }
+
+    /// Returns `true` if at least the old `threshold + 1` old nodes qualify.
+    pub fn is_ready(&self) -> bool {
+        self.dealers().len() > self.old_pk_set.threshold()
+    }
+
+    /// Returns the new public key set, and our new secret key share. Only the parts of the
+    /// `threshold + 1` qualified old nodes with the lowest indices are used.
+    ///
+    /// Returns `Error::NotEnoughShares` if fewer than the old `threshold + 1` old nodes qualify,
+    /// and `Error::MissingValue` if we don't have a valid value from one of the used ones.
+    pub fn generate(&self) -> Result<(PublicKeySet, SecretKeyShare)> {
+        let needed = self.old_pk_set.threshold() + 1;
+        let dealers: Vec<usize> = self.dealers().into_iter().take(needed).collect();
+        if dealers.len() < needed {
+            return Err(Error::NotEnoughShares {
+                supplied: dealers.len(),
+                needed,
+            });
+        }
+        let xs: Vec<Fr> = dealers.iter().map(into_fr_plus_1).collect();
+        let coeffs = lagrange_coeffs(&xs).expect("dealer indices are distinct");
+
+        let mut commit_coeff = vec![G1::zero(); self.threshold + 1];
+        let mut sk_val = Fr::zero();
+        for (l, dealer) in coeffs.iter().zip(dealers) {
+            if let Some(commit) = self.dealings.commitment(dealer) {
+                for (c, part_c) in commit_coeff.iter_mut().zip(&commit.coeff) {
+                    c.add_assign(&part_c.into_affine().mul(*l));
+                }
+            }
+            let mut tmp = match self.dealings.value(dealer) {
+                Ok(value) => *value,
+                Err(err) => {
+                    clear_fr(&mut sk_val);
+                    return Err(err);
+                }
+            };
+            tmp.mul_assign(l);
+            sk_val.add_assign(&tmp);
+            clear_fr(&mut tmp);
+        }
+        let commit = Commitment {
+            coeff: commit_coeff,
+        };
+        Ok((commit.into(), SecretKeyShare::from_mut(&mut sk_val)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::collections::BTreeMap;
+
+    use rand::{self, Rng};
+
+    use test_utils::{self, send_complaints, send_justifications, send_parts, ValueNode};
+    use SecretKeySet;
+
+    impl ValueNode for Reshare {
+        type Part = ResharePart;
+
+        fn handle_part(
+            &mut self,
+            dealer: usize,
+            part: ResharePart,
+        ) -> ::std::result::Result<(), Fault> {
+            Reshare::handle_part(self, dealer, part)
+        }
+
+        fn complaints(&self) -> Vec<Complaint> {
+            Reshare::complaints(self)
+        }
+
+        fn handle_complaint(
+            &mut self,
+            sender: usize,
+            complaint: Complaint,
+        ) -> ::std::result::Result<Option<ValueJustification>, Fault> {
+            Reshare::handle_complaint(self, sender, complaint).map(|()| None)
+        }
+
+        fn handle_justification(
+            &mut self,
+            dealer: usize,
+            justification: ValueJustification,
+        ) -> ::std::result::Result<(), Fault> {
+            Reshare::handle_justification(self, dealer, justification)
+        }
+    }
+
+    /// Runs the resharing of `sk_set` from the given old nodes to `num_nodes` new nodes with the
+    /// new `threshold`. Each old node's part is modified by `corrupt(dealer, part, pub_keys)`, and
+    /// `justify(dealer, justification)` is sent instead of their justifications.
+    fn run_reshare<F, G>(
+        sk_set: &SecretKeySet,
+        dealers: &[usize],
+        num_nodes: usize,
+        threshold: usize,
+        corrupt: F,
+        justify: G,
+    ) -> Vec<Reshare>
+    where
+        F: Fn(usize, &mut ResharePart, &[PublicKey]),
+        G: Fn(usize, ValueJustification) -> Option<ValueJustification>,
+    {
+        let mut rng = rand::thread_rng();
+        let pk_set = sk_set.public_keys();
+        let sec_keys: Vec<SecretKey> = (0..num_nodes).map(|_| rng.gen()).collect();
+        let pub_keys: Vec<PublicKey> = sec_keys.iter().map(SecretKey::public_key).collect();
+        let old_node_num = 5;
+        let mut nodes: Vec<Reshare> = sec_keys
+            .into_iter()
+            .enumerate()
+            .map(|(idx, sec_key)| {
+                Reshare::new(
+                    idx,
+                    sec_key,
+                    pk_set.clone(),
+                    old_node_num,
+                    &pub_keys,
+                    threshold,
+                )
+                .expect("new reshare")
+            }).collect();
+
+        let mut old_nodes = BTreeMap::new();
+        let mut parts = Vec::new();
+        for dealer in dealers {
+            let sk_share = sk_set.secret_key_share(*dealer);
+            let (old_node, mut part) =
+                ReshareDealer::new(*dealer, &sk_share, &pub_keys, threshold, &mut rng)
+                    .expect("new part");
+            corrupt(*dealer, &mut part, &pub_keys);
+            old_nodes.insert(*dealer, old_node);
+            parts.push((*dealer, part));
+        }
+        let complaints = send_parts(&mut nodes, parts);
+
+        // The new nodes don't deal, so only the old nodes answer the complaints.
+        assert!(send_complaints(&mut nodes, &complaints).is_empty());
+        let mut justifications = Vec::new();
+        for (sender, complaint) in complaints {
+            for (dealer, old_node) in &mut old_nodes {
+                let result = old_node.handle_complaint(sender, complaint);
+                if let Some(justification) = result.expect("valid complaint") {
+                    justifications.extend(justify(*dealer, justification).map(|j| (*dealer, j)));
+                }
+            }
+        }
+        send_justifications(&mut nodes, justifications);
+        nodes
+    }
+
+    /// Replaces new node `m`'s value in `dealer`'s part with an invalid one.
+    fn corrupt_value(part: &mut ResharePart, dealer: usize, m: usize, pk: &PublicKey) {
+        test_utils::corrupt_value(&mut part.values, Round::Reshare, dealer, m, pk);
+    }
+
+    /// Checks that all new nodes generate matching keys for the old master key, and returns the
+    /// new public key set and secret key shares.
+    fn check_keys(
+        nodes: &[Reshare],
+        pk_set: &PublicKeySet,
+        threshold: usize,
+    ) -> (PublicKeySet, BTreeMap<usize, SecretKeyShare>) {
+        let mut new_pk_set = None;
+        let mut new_sk_shares = BTreeMap::new();
+        for (m, node) in nodes.iter().enumerate() {
+            assert!(node.is_ready());
+            let (node_pk_set, sk_share) = node.generate().expect("generate keys");
+            assert_eq!(node_pk_set.public_key_share(m), sk_share.public_key_share());
+            assert_eq!(threshold, node_pk_set.threshold());
+            if let Some(ref pk_set) = new_pk_set {
+                assert_eq!(*pk_set, node_pk_set);
+            }
+            new_sk_shares.insert(m, sk_share);
+            new_pk_set = Some(node_pk_set);
+        }
+        let new_pk_set = new_pk_set.expect("at least one node");
+        assert_eq!(pk_set.public_key(), new_pk_set.public_key());
+        (new_pk_set, new_sk_shares)
+    }
+
+    #[test]
+    fn test_reshare() {
+        let mut rng = rand::thread_rng();
+        let sk_set = SecretKeySet::random(1, &mut rng);
+        let pk_set = sk_set.public_keys();
+
+        // Three old nodes reshare to the five new nodes, with threshold 3.
+        let nodes = run_reshare(&sk_set, &[3, 1, 2], 5, 3, |_, _, _| (), |_, j| Some(j));
+        let (new_pk_set, new_sk_shares) = check_keys(&nodes, &pk_set, 3);
+
+        // Four of the new shares can be combined to sign with the old master key.
+        let msg = "Totally real news";
+        let sigs: BTreeMap<_, _> = new_sk_shares
+            .iter()
+            .skip(1)
+            .map(|(m, sk_share)| (*m, sk_share.sign(msg)))
+            .collect();
+        let sig = new_pk_set.combine_signatures(&sigs).expect("enough shares");
+        assert!(pk_set.public_key().verify(&sig, msg));
+    }
+
+    #[test]
+    fn test_reshare_invalid_value() {
+        // Old node 1 sends invalid values to new nodes 0 and 2. They complain, and receive the
+        // correct values in the justifications, so all new nodes use old nodes 1 and 2.
+        let mut rng = rand::thread_rng();
+        let sk_set = SecretKeySet::random(1, &mut rng);
+        let corrupt = |dealer: usize, part: &mut ResharePart, pub_keys: &[PublicKey]| {
+            if dealer == 1 {
+                corrupt_value(part, dealer, 0, &pub_keys[0]);
+                corrupt_value(part, dealer, 2, &pub_keys[2]);
+            }
+        };
+        let nodes = run_reshare(&sk_set, &[4, 2, 1], 4, 2, corrupt, |_, j| Some(j));
+        let dealers: BTreeSet<usize> = [1, 2, 4].iter().cloned().collect();
+        for node in &nodes {
+            assert_eq!(dealers, node.dealers());
+        }
+        check_keys(&nodes, &sk_set.public_keys(), 2);
+    }
+
+    #[test]
+    fn test_reshare_disqualified() {
+        // Old node 1 sends an invalid value to new node 0, and doesn't answer the complaint. All
+        // new nodes use old nodes 2 and 4 instead.
+        let mut rng = rand::thread_rng();
+        let sk_set = SecretKeySet::random(1, &mut rng);
+        let corrupt = |dealer: usize, part: &mut ResharePart, pub_keys: &[PublicKey]| {
+            if dealer == 1 {
+                corrupt_value(part, dealer, 0, &pub_keys[0]);
+            }
+        };
+        let justify = |dealer: usize, justification| match dealer {
+            1 => None,
+            _ => Some(justification),
+        };
+        let nodes = run_reshare(&sk_set, &[4, 2, 1], 4, 2, corrupt, justify);
+        let disqualified: BTreeSet<usize> = Some(1).into_iter().collect();
+        let dealers: BTreeSet<usize> = [2, 4].iter().cloned().collect();
+        for node in &nodes {
+            assert_eq!(disqualified, node.disqualified_dealers());
+            assert_eq!(dealers, node.dealers());
+        }
+        check_keys(&nodes, &sk_set.public_keys(), 2);
+    }
+
+    #[test]
+    fn test_reshare_faults() {
+        let mut rng = rand::thread_rng();
+        let sk_set = SecretKeySet::random(1, &mut rng);
+        let sec_keys: Vec<SecretKey> = (0..3).map(|_| rng.gen()).collect();
+        let pub_keys: Vec<PublicKey> = sec_keys.iter().map(SecretKey::public_key).collect();
+
+        // Our index must be valid, and our secret key must match our public key.
+        let sec_key = &sec_keys[0];
+        let new_reshare =
+            |idx| Reshare::new(idx, sec_key.clone(), sk_set.public_keys(), 3, &pub_keys, 2);
+        let expected = Error::IndexOutOfRange { index: 3, count: 3 };
+        assert_eq!(Some(expected), new_reshare(3).err());
+        assert_eq!(Some(Error::KeyMismatch), new_reshare(1).err());
+        let mut node = new_reshare(0).expect("new reshare");
+        let sk_share = sk_set.secret_key_share(1);
+        let (mut old_node, part) =
+            ReshareDealer::new(1, &sk_share, &pub_keys, 2, &mut rng).expect("new part");
+
+        // The constant term must be the dealer's old secret key share.
+        let result = node.handle_part(2, part.clone());
+        assert_eq!(Err(Fault::ConstantMismatch), result);
+        let (_, wrong_part) =
+            ReshareDealer::new(1, &sk_share, &pub_keys, 1, &mut rng).expect("new part");
+        assert_eq!(Err(Fault::WrongDegree), node.handle_part(1, wrong_part));
+        // There are only three old nodes.
+        let result = node.handle_part(3, part.clone());
+        assert_eq!(Err(Fault::UnknownSender), result);
+
+        node.handle_part(1, part.clone()).expect("valid part");
+        assert_eq!(Err(Fault::DuplicatePart), node.handle_part(1, part));
+        assert!(!node.is_ready());
+        let expected = Error::NotEnoughShares {
+            supplied: 1,
+            needed: 2,
+        };
+        assert_eq!(Some(expected), node.generate().err());
+
+        // The old node answers at most two complaints, since three values would reveal its share.
+        let complaint = Complaint { dealer: 1 };
+        for sender in 0..2 {
+            let result = old_node.handle_complaint(sender, complaint);
+            let justification = result.expect("valid complaint").expect("justification");
+            assert_eq!(sender, justification.complainer());
+        }
+        let result = old_node.handle_complaint(1, complaint);
+        assert_eq!(Err(Fault::DuplicateComplaint), result);
+        let result = old_node.handle_complaint(2, complaint);
+        assert_eq!(Ok(None), result);
+        let result = old_node.handle_complaint(3, complaint);
+        assert_eq!(Err(Fault::UnknownSender), result);
+        let result = old_node.handle_complaint(0, Complaint { dealer: 2 });
+        assert_eq!(Ok(None), result);
+    }
+}
@@ -1,20 +1,63 @@
 //! Utilities for working with secret values. This module includes functionality for overwriting
 //! memory with zeros.
 
+use std::fmt;
 use std::mem::{size_of, size_of_val};
 use std::ops::{Deref, DerefMut};
 
 use memsec::memzero;
-use Fr;
+use pairing::{PrimeField, PrimeFieldRepr};
 
-lazy_static! {
-    /// The size in bytes of a single field element.
-    pub(crate) static ref FR_SIZE: usize = size_of::<Fr>();
+/// Returns the size in bytes of a field element's big-endian representation.
+pub(crate) fn fr_bytes<F: PrimeField>() -> usize {
+    let mut bytes = Vec::new();
+    F::zero()
+        .into_repr()
+        .write_be(&mut bytes)
+        .expect("writing to a vector never fails");
+    bytes.len()
 }
 
 /// Overwrites a single field element with zeros.
-pub(crate) fn clear_fr(fr_ptr: *const Fr) {
-    unsafe { memzero(fr_ptr as *mut u8, *FR_SIZE) };
+pub(crate) fn clear_fr<F: PrimeField>(fr: &mut F) {
+    unsafe { memzero(fr as *mut F as *mut u8, size_of::<F>()) };
+}
+
+/// Overwrites a temporary copy of a secret value, e.g. a field element's representation, with
+/// zeros.
+pub(crate) fn clear_copy<T: Copy>(value: &mut T) {
+    unsafe { memzero(value as *mut T as *mut u8, size_of::<T>()) };
+}
+
+/// A byte buffer containing secret data, e.g. an exported secret key. Its contents are
+/// overwritten with zeros when it is dropped.
+pub struct SecretBytes(pub(crate) Vec<u8>);
+
+impl Deref for SecretBytes {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl AsRef<[u8]> for SecretBytes {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl Drop for SecretBytes {
+    fn drop(&mut self) {
+        unsafe { memzero(self.0.as_mut_ptr(), self.0.len()) };
+    }
+}
+
+/// A debug statement where the secret bytes are redacted.
+impl fmt::Debug for SecretBytes {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("SecretBytes").field(&"...").finish()
+    }
 }
 
 pub(crate) struct MemRange {
@@ -1,24 +1,27 @@
 use std::borrow::Cow;
 
-use super::G1;
+use pairing::{CurveProjective, Engine};
 use serde::de::Error as DeserializeError;
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
-use poly::{coeff_pos, BivarCommitment};
+use generic::poly::{coeff_pos, BivarCommitment};
+use poly::BivarPedersenCommitment;
+use G1;
 
 const ERR_DEG: &str = "commitment degree does not match coefficients";
 
 /// A type with the same content as `BivarCommitment`, but that has not been validated yet.
 #[derive(Serialize, Deserialize)]
-struct WireBivarCommitment<'a> {
+#[serde(bound = "")]
+struct WireBivarCommitment<'a, C: CurveProjective> {
     /// The polynomial's degree in each of the two variables.
     degree: usize,
     /// The commitments to the coefficients.
     #[serde(with = "projective_vec")]
-    coeff: Cow<'a, [G1]>,
+    coeff: Cow<'a, [C]>,
 }
 
-impl Serialize for BivarCommitment {
+impl<E: Engine> Serialize for BivarCommitment<E> {
     fn serialize<S: Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
         WireBivarCommitment {
             degree: self.degree,
@@ -27,9 +30,10 @@ impl Serialize for BivarCommitment {
     }
 }
 
-impl<'de> Deserialize<'de> for BivarCommitment {
+impl<'de, E: Engine> Deserialize<'de> for BivarCommitment<E> {
     fn deserialize<D: Deserializer<'de>>(d: D) -> Result<Self, D::Error> {
-        let WireBivarCommitment { degree, coeff } = Deserialize::deserialize(d)?;
+        let wire: WireBivarCommitment<E::G1> = Deserialize::deserialize(d)?;
+        let WireBivarCommitment { degree, coeff } = wire;
         if coeff_pos(degree, degree).and_then(|l| l.checked_add(1)) != Some(coeff.len()) {
             return Err(D::Error::custom(ERR_DEG));
         }
@@ -40,6 +44,29 @@ impl<'de> Deserialize<'de> for BivarCommitment {
     }
 }
 
+impl Serialize for BivarPedersenCommitment {
+    fn serialize<S: Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+        WireBivarCommitment {
+            degree: self.degree,
+            coeff: Cow::Borrowed(&self.coeff),
+        }.serialize(s)
+    }
+}
+
+impl<'de> Deserialize<'de> for BivarPedersenCommitment {
+    fn deserialize<D: Deserializer<'de>>(d: D) -> Result<Self, D::Error> {
+        let wire: WireBivarCommitment<G1> = Deserialize::deserialize(d)?;
+        let WireBivarCommitment { degree, coeff } = wire;
+        if coeff_pos(degree, degree).and_then(|l| l.checked_add(1)) != Some(coeff.len()) {
+            return Err(D::Error::custom(ERR_DEG));
+        }
+        Ok(BivarPedersenCommitment {
+            degree,
+            coeff: coeff.into(),
+        })
+    }
+}
+
 /// Serialization and deserialization of a group element's compressed representation.
 pub mod projective {
     use pairing::{CurveAffine, CurveProjective, EncodedPoint};
@@ -194,6 +221,114 @@ pub mod field_vec {
     }
 }
 
+/// Serialization and deserialization of a field element's big-endian representation.
+pub mod field {
+    use pairing::PrimeField;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    use super::field_vec::FieldWrap;
+
+    pub fn serialize<S, F>(f: &F, s: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+        F: PrimeField,
+    {
+        FieldWrap::<F, &F>::new(f).serialize(s)
+    }
+
+    pub fn deserialize<'de, D, F>(d: D) -> Result<F, D::Error>
+    where
+        D: Deserializer<'de>,
+        F: PrimeField,
+    {
+        Ok(FieldWrap::<F, F>::deserialize(d)?.into_inner())
+    }
+}
+
+/// Serialization and deserialization of secret keys and secret key shares.
+///
+/// `SecretKey` and `SecretKeyShare` don't implement `Serialize`, so that they can't be sent or
+/// stored by accident. Fields of these types can be explicitly marked for serialization with
+/// `#[serde(with = "threshold_crypto::serde_impl::secret")]`.
+///
+/// *WARNING* The serialized data contains the secret key itself.
+pub mod secret {
+    use serde::de::Error as DeserializeError;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    use pairing::Engine;
+
+    use error::Result;
+    use generic::{min_sig, SecretKey, SecretKeyShare};
+    use SecretBytes;
+
+    /// A secret key type that can be exported as bytes.
+    pub trait SecretKeyBytes: Sized {
+        /// Returns the secret key's bytes.
+        fn to_bytes_secret(&self) -> SecretBytes;
+
+        /// Decodes a secret key from its bytes.
+        fn from_bytes(bytes: &[u8]) -> Result<Self>;
+    }
+
+    impl<E: Engine> SecretKeyBytes for SecretKey<E> {
+        fn to_bytes_secret(&self) -> SecretBytes {
+            SecretKey::to_bytes_secret(self)
+        }
+
+        fn from_bytes(bytes: &[u8]) -> Result<Self> {
+            SecretKey::from_bytes(bytes)
+        }
+    }
+
+    impl<E: Engine> SecretKeyBytes for SecretKeyShare<E> {
+        fn to_bytes_secret(&self) -> SecretBytes {
+            SecretKeyShare::to_bytes_secret(self)
+        }
+
+        fn from_bytes(bytes: &[u8]) -> Result<Self> {
+            SecretKeyShare::from_bytes(bytes)
+        }
+    }
+
+    impl<E: Engine> SecretKeyBytes for min_sig::SecretKey<E> {
+        fn to_bytes_secret(&self) -> SecretBytes {
+            self.0.to_bytes_secret()
+        }
+
+        fn from_bytes(bytes: &[u8]) -> Result<Self> {
+            SecretKey::from_bytes(bytes).map(min_sig::SecretKey::from)
+        }
+    }
+
+    impl<E: Engine> SecretKeyBytes for min_sig::SecretKeyShare<E> {
+        fn to_bytes_secret(&self) -> SecretBytes {
+            (self.0).0.to_bytes_secret()
+        }
+
+        fn from_bytes(bytes: &[u8]) -> Result<Self> {
+            SecretKeyShare::from_bytes(bytes).map(min_sig::SecretKeyShare::from)
+        }
+    }
+
+    pub fn serialize<S, K>(key: &K, s: S) -> ::std::result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+        K: SecretKeyBytes,
+    {
+        key.to_bytes_secret()[..].serialize(s)
+    }
+
+    pub fn deserialize<'de, D, K>(d: D) -> ::std::result::Result<K, D::Error>
+    where
+        D: Deserializer<'de>,
+        K: SecretKeyBytes,
+    {
+        let bytes = SecretBytes(Deserialize::deserialize(d)?);
+        K::from_bytes(&bytes).map_err(D::Error::custom)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::super::PEngine;
@@ -201,7 +336,8 @@ mod tests {
     use pairing::Engine;
     use rand::{self, Rng};
 
-    use poly::BivarPoly;
+    use poly::{BivarPedersenCommitment, BivarPoly};
+    use {min_sig, SecretKey, SecretKeyShare};
 
     #[derive(Debug, Serialize, Deserialize)]
     pub struct Vecs<E: Engine> {
@@ -240,4 +376,51 @@ mod tests {
             assert_eq!(comm, de_comm);
         }
     }
+
+    #[test]
+    fn bivar_pedersen_commitment() {
+        let mut rng = rand::thread_rng();
+        for deg in 1..8 {
+            let poly = BivarPoly::random(deg, &mut rng);
+            let blinding = BivarPoly::random(deg, &mut rng);
+            let comm = poly.pedersen_commitment(&blinding);
+            let ser_comm = bincode::serialize(&comm).expect("serialize commitment");
+            let de_comm = bincode::deserialize(&ser_comm).expect("deserialize commitment");
+            assert_eq!(comm, de_comm);
+            // A commitment with the wrong number of coefficients is rejected.
+            let mut wrong_comm = comm.clone();
+            wrong_comm.coeff.pop();
+            let ser_comm = bincode::serialize(&wrong_comm).expect("serialize commitment");
+            let result: Result<BivarPedersenCommitment, _> = bincode::deserialize(&ser_comm);
+            assert!(result.is_err());
+        }
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Keys {
+        #[serde(with = "super::secret")]
+        sk: SecretKey,
+        #[serde(with = "super::secret")]
+        sk_share: SecretKeyShare,
+        #[serde(with = "super::secret")]
+        min_sig_sk: min_sig::SecretKey,
+        #[serde(with = "super::secret")]
+        min_sig_sk_share: min_sig::SecretKeyShare,
+    }
+
+    #[test]
+    fn secret() {
+        let mut rng = rand::thread_rng();
+        let keys = Keys {
+            sk: rng.gen(),
+            sk_share: rng.gen(),
+            min_sig_sk: rng.gen(),
+            min_sig_sk_share: rng.gen(),
+        };
+        let ser_keys = bincode::serialize(&keys).expect("serialize keys");
+        let de_keys = bincode::deserialize(&ser_keys).expect("deserialize keys");
+        assert_eq!(keys, de_keys);
+        let result: Result<Keys, _> = bincode::deserialize(&ser_keys[1..]);
+        assert!(result.is_err());
+    }
 }
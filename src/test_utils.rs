@@ -0,0 +1,111 @@
+//! Helpers shared by the tests of the refresh, resharing and recovery protocols, in which each
+//! dealer commits to a univariate polynomial and sends one encrypted value of it to every node.
+
+use pairing::Field;
+use rand;
+
+use dkg::{encrypt_fr_vec, Complaint, Fault, Round, ValueJustification};
+use {Ciphertext, Fr, PublicKey};
+
+/// A node that receives values from the dealers, and complains about invalid ones.
+pub(crate) trait ValueNode {
+    /// The message a dealer sends to all nodes.
+    type Part: Clone;
+
+    /// Handles a part from `dealer`.
+    fn handle_part(&mut self, dealer: usize, part: Self::Part) -> Result<(), Fault>;
+
+    /// Returns our complaints about all dealers whose part contained an invalid value for us.
+    fn complaints(&self) -> Vec<Complaint>;
+
+    /// Handles a complaint from node `sender`, and returns our justification if we are the
+    /// dealer the complaint is about.
+    fn handle_complaint(
+        &mut self,
+        sender: usize,
+        complaint: Complaint,
+    ) -> Result<Option<ValueJustification>, Fault>;
+
+    /// Handles a justification from `dealer`.
+    fn handle_justification(
+        &mut self,
+        dealer: usize,
+        justification: ValueJustification,
+    ) -> Result<(), Fault>;
+}
+
+/// Broadcasts the dealers' `parts` to all nodes, then the nodes' complaints, and finally their
+/// justifications, where `justify(dealer, justification)` is sent instead of each of them. All
+/// messages are handled by all nodes, in the same order.
+pub(crate) fn run_dealings<N, G>(nodes: &mut [N], parts: Vec<(usize, N::Part)>, justify: G)
+where
+    N: ValueNode,
+    G: Fn(usize, ValueJustification) -> Option<ValueJustification>,
+{
+    let complaints = send_parts(nodes, parts);
+    let justifications = send_complaints(nodes, &complaints)
+        .into_iter()
+        .filter_map(|(dealer, j)| justify(dealer, j).map(|j| (dealer, j)))
+        .collect();
+    send_justifications(nodes, justifications);
+}
+
+/// Sends the dealers' `parts` to all nodes, and returns the nodes' complaints with their
+/// senders.
+pub(crate) fn send_parts<N: ValueNode>(
+    nodes: &mut [N],
+    parts: Vec<(usize, N::Part)>,
+) -> Vec<(usize, Complaint)> {
+    for (dealer, part) in parts {
+        for node in nodes.iter_mut() {
+            let _ = node.handle_part(dealer, part.clone());
+        }
+    }
+    let mut complaints = Vec::new();
+    for (m, node) in nodes.iter().enumerate() {
+        complaints.extend(node.complaints().into_iter().map(|c| (m, c)));
+    }
+    complaints
+}
+
+/// Sends the `complaints` to all nodes, and returns their justifications with their dealers.
+pub(crate) fn send_complaints<N: ValueNode>(
+    nodes: &mut [N],
+    complaints: &[(usize, Complaint)],
+) -> Vec<(usize, ValueJustification)> {
+    let mut justifications = Vec::new();
+    for &(sender, complaint) in complaints {
+        for (m, node) in nodes.iter_mut().enumerate() {
+            let result = node.handle_complaint(sender, complaint);
+            if let Some(justification) = result.expect("valid complaint") {
+                justifications.push((m, justification));
+            }
+        }
+    }
+    justifications
+}
+
+/// Sends the `justifications` to all nodes.
+pub(crate) fn send_justifications<N: ValueNode>(
+    nodes: &mut [N],
+    justifications: Vec<(usize, ValueJustification)>,
+) {
+    for (dealer, justification) in justifications {
+        for node in nodes.iter_mut() {
+            let _ = node.handle_justification(dealer, justification.clone());
+        }
+    }
+}
+
+/// Replaces node `m`'s value among `dealer`'s `values` with an invalid one.
+pub(crate) fn corrupt_value(
+    values: &mut [Ciphertext],
+    round: Round,
+    dealer: usize,
+    m: usize,
+    pk: &PublicKey,
+) {
+    let mut rng = rand::thread_rng();
+    let label = round.label(&[dealer, m]);
+    values[m] = encrypt_fr_vec(pk, &[Fr::one()], &label, &mut rng);
+}
@@ -0,0 +1,391 @@
+//! Threshold key sets in which the participants have different weights.
+//!
+//! A participant with weight `w` holds `w` consecutive shares of an underlying key set: The
+//! shares are assigned to the participants in order, so participant `p` holds the shares
+//! starting at the sum of the weights of participants `0` to `p - 1`. A participant's signature
+//! and decryption shares cover all of its shares at once. With threshold `t`, any set of
+//! participants whose total weight is greater than `t` can sign and decrypt together.
+
+use std::ops::Range;
+
+use rand::Rng;
+
+use error::{Error, Result};
+use {
+    Ciphertext, DecryptionShare, PublicKey, PublicKeySet, PublicKeyShare, SecretKeySet,
+    SecretKeyShare, Signature, SignatureShare,
+};
+
+/// A participant's public key shares, one for each unit of its weight.
+#[derive(Deserialize, Serialize, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct WeightedPublicKeyShare(Vec<PublicKeyShare>);
+
+impl WeightedPublicKeyShare {
+    /// Returns the participant's weight.
+    pub fn weight(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Returns `true` if the signature contains a valid share of `msg` for each public key share.
+    pub fn verify<M: AsRef<[u8]>>(&self, sig: &WeightedSignatureShare, msg: M) -> bool {
+        self.0.len() == sig.0.len()
+            && self
+                .0
+                .iter()
+                .zip(&sig.0)
+                .all(|(pk_share, sig_share)| pk_share.verify(sig_share, &msg))
+    }
+
+    /// Returns `true` if the signature contains a valid share of `msg` with the domain separation
+    /// tag `dst`, for each public key share.
+    pub fn verify_with_dst<M, D>(&self, sig: &WeightedSignatureShare, msg: M, dst: D) -> bool
+    where
+        M: AsRef<[u8]>,
+        D: AsRef<[u8]>,
+    {
+        self.0.len() == sig.0.len()
+            && self
+                .0
+                .iter()
+                .zip(&sig.0)
+                .all(|(pk_share, sig_share)| pk_share.verify_with_dst(sig_share, &msg, &dst))
+    }
+
+    /// Returns `true` if the decryption share contains a valid share for each public key share.
+    pub fn verify_decryption_share(
+        &self,
+        share: &WeightedDecryptionShare,
+        ct: &Ciphertext,
+    ) -> bool {
+        self.0.len() == share.0.len()
+            && self
+                .0
+                .iter()
+                .zip(&share.0)
+                .all(|(pk_share, dec_share)| pk_share.verify_decryption_share(dec_share, ct))
+    }
+}
+
+/// A participant's signature shares, one for each unit of its weight.
+#[derive(Deserialize, Serialize, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct WeightedSignatureShare(Vec<SignatureShare>);
+
+/// A participant's decryption shares, one for each unit of its weight.
+#[derive(Deserialize, Serialize, Clone, PartialEq, Eq, Debug)]
+pub struct WeightedDecryptionShare(Vec<DecryptionShare>);
+
+/// A participant's secret key shares, one for each unit of its weight.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct WeightedSecretKeyShare(Vec<SecretKeyShare>);
+
+impl WeightedSecretKeyShare {
+    /// Returns the participant's weight.
+    pub fn weight(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Returns the matching public key shares.
+    pub fn public_key_share(&self) -> WeightedPublicKeyShare {
+        WeightedPublicKeyShare(
+            self.0
+                .iter()
+                .map(SecretKeyShare::public_key_share)
+                .collect(),
+        )
+    }
+
+    /// Signs the given message with each of the secret key shares.
+    pub fn sign<M: AsRef<[u8]>>(&self, msg: M) -> WeightedSignatureShare {
+        WeightedSignatureShare(self.0.iter().map(|sk_share| sk_share.sign(&msg)).collect())
+    }
+
+    /// Signs the given message with the domain separation tag `dst`, with each of the secret key
+    /// shares.
+    pub fn sign_with_dst<M, D>(&self, msg: M, dst: D) -> WeightedSignatureShare
+    where
+        M: AsRef<[u8]>,
+        D: AsRef<[u8]>,
+    {
+        let sign = |sk_share: &SecretKeyShare| sk_share.sign_with_dst(&msg, &dst);
+        WeightedSignatureShare(self.0.iter().map(sign).collect())
+    }
+
+    /// Returns the decryption shares of each of the secret key shares, or `None`, if the
+    /// ciphertext isn't valid.
+    pub fn decrypt_share(&self, ct: &Ciphertext) -> Option<WeightedDecryptionShare> {
+        let shares = self.0.iter().map(|sk_share| sk_share.decrypt_share(ct));
+        shares.collect::<Option<_>>().map(WeightedDecryptionShare)
+    }
+
+    /// Returns the decryption shares of each of the secret key shares, without validating the
+    /// ciphertext.
+    pub fn decrypt_share_no_verify(&self, ct: &Ciphertext) -> WeightedDecryptionShare {
+        let decrypt = |sk_share: &SecretKeyShare| sk_share.decrypt_share_no_verify(ct);
+        WeightedDecryptionShare(self.0.iter().map(decrypt).collect())
+    }
+}
+
+/// A public key and the participants' public key shares, according to their weights.
+#[derive(Deserialize, Serialize, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct WeightedPublicKeySet {
+    /// The public key set with the underlying shares.
+    pk_set: PublicKeySet,
+    /// The participants' weights.
+    weights: Vec<usize>,
+}
+
+impl WeightedPublicKeySet {
+    /// Creates a weighted public key set from the underlying key set. The participants' shares
+    /// are assigned in order, according to the weights.
+    pub fn new(pk_set: PublicKeySet, weights: Vec<usize>) -> Self {
+        WeightedPublicKeySet { pk_set, weights }
+    }
+
+    /// Returns the threshold `t`: any set of participants with a total weight of at least `t + 1`
+    /// can combine their signature shares into a full signature.
+    pub fn threshold(&self) -> usize {
+        self.pk_set.threshold()
+    }
+
+    /// Returns the participants' weights.
+    pub fn weights(&self) -> &[usize] {
+        &self.weights
+    }
+
+    /// Returns the sum of all participants' weights.
+    pub fn total_weight(&self) -> usize {
+        self.weights.iter().sum()
+    }
+
+    /// Returns the public key.
+    pub fn public_key(&self) -> PublicKey {
+        self.pk_set.public_key()
+    }
+
+    /// Returns the public key set with the underlying shares.
+    pub fn public_key_set(&self) -> &PublicKeySet {
+        &self.pk_set
+    }
+
+    /// Returns the indices of participant `p`'s shares in the underlying key set. This is empty
+    /// if `p` is not a participant.
+    pub fn share_indices(&self, p: usize) -> Range<usize> {
+        share_indices(&self.weights, p)
+    }
+
+    /// Returns participant `p`'s public key shares.
+    pub fn public_key_share(&self, p: usize) -> WeightedPublicKeyShare {
+        let shares = self
+            .share_indices(p)
+            .map(|i| self.pk_set.public_key_share(i));
+        WeightedPublicKeyShare(shares.collect())
+    }
+
+    /// Combines the participants' shares into a signature that can be verified with the main
+    /// public key.
+    ///
+    /// Returns `Error::WrongWeight` if a participant's number of shares doesn't match its weight,
+    /// and `Error::NotEnoughShares` if the participants' total weight is not greater than the
+    /// threshold. In that case, the numbers in the error refer to weight, not participants.
+    pub fn combine_signatures<'a, I>(&self, shares: I) -> Result<Signature>
+    where
+        I: IntoIterator<Item = (usize, &'a WeightedSignatureShare)>,
+    {
+        let samples = self.flatten(shares.into_iter().map(|(p, share)| (p, &share.0[..])))?;
+        self.pk_set.combine_signatures(samples)
+    }
+
+    /// Combines the participants' shares to decrypt the ciphertext.
+    ///
+    /// Returns `Error::WrongWeight` if a participant's number of shares doesn't match its weight,
+    /// and `Error::NotEnoughShares` if the participants' total weight is not greater than the
+    /// threshold.
+    pub fn decrypt<'a, I>(&self, shares: I, ct: &Ciphertext) -> Result<Vec<u8>>
+    where
+        I: IntoIterator<Item = (usize, &'a WeightedDecryptionShare)>,
+    {
+        let samples = self.flatten(shares.into_iter().map(|(p, share)| (p, &share.0[..])))?;
+        self.pk_set.decrypt(samples, ct)
+    }
+
+    /// Returns the participants' shares together with their indices in the underlying key set,
+    /// until the total weight exceeds the threshold.
+    fn flatten<'a, S, I>(&self, shares: I) -> Result<Vec<(usize, &'a S)>>
+    where
+        I: IntoIterator<Item = (usize, &'a [S])>,
+    {
+        let needed = self.threshold() + 1;
+        let mut samples = Vec::with_capacity(needed);
+        for (p, p_shares) in shares {
+            if samples.len() >= needed {
+                break;
+            }
+            let indices = self.share_indices(p);
+            if p_shares.len() != indices.len() {
+                return Err(Error::WrongWeight {
+                    participant: p,
+                    supplied: p_shares.len(),
+                    weight: indices.len(),
+                });
+            }
+            samples.extend(indices.zip(p_shares));
+        }
+        if samples.len() < needed {
+            return Err(Error::NotEnoughShares {
+                supplied: samples.len(),
+                needed,
+            });
+        }
+        Ok(samples)
+    }
+}
+
+/// A secret key and the participants' secret key shares, according to their weights.
+pub struct WeightedSecretKeySet {
+    /// The secret key set with the underlying shares.
+    sk_set: SecretKeySet,
+    /// The participants' weights.
+    weights: Vec<usize>,
+}
+
+impl WeightedSecretKeySet {
+    /// Creates a weighted secret key set from the underlying key set. The participants' shares
+    /// are assigned in order, according to the weights.
+    pub fn new(sk_set: SecretKeySet, weights: Vec<usize>) -> Self {
+        WeightedSecretKeySet { sk_set, weights }
+    }
+
+    /// Creates a random set of secret key shares for participants with the given weights, where
+    /// any set of participants with a total weight of at least `threshold + 1` can
+    /// collaboratively sign and decrypt.
+    ///
+    /// # Panic
+    ///
+    /// Panics if the `threshold` is too large for the coefficients to fit into a `Vec`.
+    pub fn random<R: Rng>(threshold: usize, weights: Vec<usize>, rng: &mut R) -> Self {
+        WeightedSecretKeySet::new(SecretKeySet::random(threshold, rng), weights)
+    }
+
+    /// Creates a random set of secret key shares for participants with the given weights. This
+    /// is identical to `random`, except that it returns an `Err` where `random` would panic.
+    pub fn try_random<R: Rng>(threshold: usize, weights: Vec<usize>, rng: &mut R) -> Result<Self> {
+        let sk_set = SecretKeySet::try_random(threshold, rng)?;
+        Ok(WeightedSecretKeySet::new(sk_set, weights))
+    }
+
+    /// Returns the threshold `t`: any set of participants with a total weight of at least `t + 1`
+    /// can combine their signature shares into a full signature.
+    pub fn threshold(&self) -> usize {
+        self.sk_set.threshold()
+    }
+
+    /// Returns the participants' weights.
+    pub fn weights(&self) -> &[usize] {
+        &self.weights
+    }
+
+    /// Returns participant `p`'s secret key shares.
+    pub fn secret_key_share(&self, p: usize) -> WeightedSecretKeyShare {
+        let shares = share_indices(&self.weights, p).map(|i| self.sk_set.secret_key_share(i));
+        WeightedSecretKeyShare(shares.collect())
+    }
+
+    /// Returns the corresponding public key set. That information can be shared publicly.
+    pub fn public_keys(&self) -> WeightedPublicKeySet {
+        WeightedPublicKeySet::new(self.sk_set.public_keys(), self.weights.clone())
+    }
+}
+
+/// Returns the indices of participant `p`'s shares, given all participants' weights.
+fn share_indices(weights: &[usize], p: usize) -> Range<usize> {
+    match weights.get(p) {
+        None => 0..0,
+        Some(weight) => {
+            let start = weights[..p].iter().sum();
+            start..(start + weight)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use rand;
+
+    #[test]
+    fn test_weighted_signatures() {
+        let mut rng = rand::thread_rng();
+        let sk_set = WeightedSecretKeySet::random(3, vec![3, 1, 1, 2, 0], &mut rng);
+        let pk_set = sk_set.public_keys();
+        assert_eq!(7, pk_set.total_weight());
+        assert_eq!(3..4, pk_set.share_indices(1));
+        assert_eq!(7..7, pk_set.share_indices(4));
+        assert_eq!(0..0, pk_set.share_indices(5));
+
+        let msg = "Totally real news";
+        let sigs: Vec<WeightedSignatureShare> = (0..5)
+            .map(|p| {
+                let sk_share = sk_set.secret_key_share(p);
+                assert_eq!(pk_set.weights()[p], sk_share.weight());
+                assert_eq!(pk_set.public_key_share(p), sk_share.public_key_share());
+                let sig = sk_share.sign(msg);
+                assert!(pk_set.public_key_share(p).verify(&sig, msg));
+                sig
+            }).collect();
+        assert!(!pk_set.public_key_share(0).verify(&sigs[1], msg));
+
+        // Participants 0 and 1 have a total weight of 4, which is enough.
+        let sig = pk_set
+            .combine_signatures(vec![(0, &sigs[0]), (1, &sigs[1])])
+            .expect("enough weight");
+        assert!(pk_set.public_key().verify(&sig, msg));
+        // So do participants 1, 2, 3 and 4.
+        let shares = (1..5).map(|p| (p, &sigs[p]));
+        let sig2 = pk_set.combine_signatures(shares).expect("enough weight");
+        assert_eq!(sig, sig2);
+
+        // Participant 0 alone has a weight of only 3.
+        let expected = Error::NotEnoughShares {
+            supplied: 3,
+            needed: 4,
+        };
+        let result = pk_set.combine_signatures(vec![(0, &sigs[0]), (4, &sigs[4])]);
+        assert_eq!(Some(expected), result.err());
+
+        // A participant's shares must match its weight.
+        let expected = Error::WrongWeight {
+            participant: 1,
+            supplied: 3,
+            weight: 1,
+        };
+        let result = pk_set.combine_signatures(vec![(1, &sigs[0]), (3, &sigs[3])]);
+        assert_eq!(Some(expected), result.err());
+    }
+
+    #[test]
+    fn test_weighted_decryption() {
+        let mut rng = rand::thread_rng();
+        let sk_set = WeightedSecretKeySet::random(2, vec![2, 1, 2], &mut rng);
+        let pk_set = sk_set.public_keys();
+        let msg = b"Totally real news";
+        let ct = pk_set.public_key().encrypt(&msg[..]);
+
+        let shares: Vec<WeightedDecryptionShare> = (0..3)
+            .map(|p| {
+                let share = sk_set
+                    .secret_key_share(p)
+                    .decrypt_share(&ct)
+                    .expect("valid ct");
+                assert!(pk_set
+                    .public_key_share(p)
+                    .verify_decryption_share(&share, &ct));
+                share
+            }).collect();
+        let decrypted = pk_set
+            .decrypt(vec![(2, &shares[2]), (1, &shares[1])], &ct)
+            .expect("enough weight");
+        assert_eq!(msg[..], decrypted[..]);
+        assert!(pk_set.decrypt(vec![(0, &shares[0])], &ct).is_err());
+    }
+}